@@ -1,39 +1,146 @@
 use clap::{ArgMatches, Command};
 
-use lib::{entry::Entry, shelf::Shelf};
+use std::{fmt::Display, fs::remove_dir_all, path::PathBuf};
+
+use lib::{
+    edit::Fields,
+    entry::Entry,
+    journal::{Error as JournalError, Journal},
+    progress::Progress,
+    rating::Rating,
+    search::{Hit, Index, Update},
+    series::Series,
+    session::Session,
+    shelf::Shelf,
+    speed::SpeedTest,
+    status::Status,
+    tag::Tag,
+};
 
 use crate::{
-    cli::{clap::get_cli_commands, match_subcommand},
+    cli::{clap::get_cli_commands, match_subcommand, report},
     config::Config,
     tui::Tui,
+    utils::format::format_title,
+    viewer::Viewer,
 };
 
+/// The error returned when there is no shelf with the name requested in
+/// the config.
+#[derive(Debug)]
+pub(crate) struct NoSuchShelf(pub(crate) String);
+
+impl Display for NoSuchShelf {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "There is no shelf named '{}'", self.0)
+    }
+}
+
+impl std::error::Error for NoSuchShelf {}
+
 pub(crate) struct App {
     /// App configuration struct
     pub(crate) config: Config,
     /// Entry storage
     pub(crate) shelf: Shelf,
+    /// The name of the shelf in use
+    pub(crate) shelf_name: String,
     /// Clap commands
     pub(crate) cli_commands: Command<'static>,
 }
 
 impl App {
     /// Sets up the `clap` app with information about the program version,
-    /// its description and the author, as well as all of the commands.
-    pub(crate) fn new() -> Result<Self, Box<dyn std::error::Error>> {
+    /// its description and the author, as well as all of the commands, and
+    /// opens the shelf given with `--shelf`, or the default one. No other
+    /// shelf is opened.
+    pub(crate) fn new(
+        matches: &ArgMatches,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
         let config = Config::get_or_default()?;
 
-        let shelf = Shelf::open(config.db())?;
+        let shelf_name = matches
+            .value_of("shelf")
+            .unwrap_or_else(|| config.default_shelf())
+            .to_string();
+        let shelf = open_shelf(&config, &shelf_name)?;
 
         let cli_commands = get_cli_commands();
 
         Ok(Self {
             config,
             shelf,
+            shelf_name,
             cli_commands,
         })
     }
 
+    /// Switches to the named shelf.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if there is no such shelf in the
+    /// config, or if opening it fails.
+    pub(crate) fn switch_shelf(
+        &mut self,
+        name: &str,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        self.shelf = open_shelf(&self.config, name)?;
+        self.shelf_name = name.to_string();
+
+        Ok(())
+    }
+
+    /// Moves or copies the entries with corresponding indices onto the named
+    /// shelf, and saves both of the shelves into the files specified in the
+    /// config. The entries keep all of their data.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the shelf is the one in use or
+    /// doesn't exist, if any of the indices don't correspond to existing
+    /// entries, if any of the entries are already on the other shelf, or if
+    /// saving either of the shelves fails. No entries are moved or copied in
+    /// that case.
+    pub(crate) fn transfer_entries_indices(
+        &mut self,
+        indices: &[usize],
+        name: &str,
+        keep: bool,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        if name == self.shelf_name {
+            return Err(format!("The entries are already on '{}'", name).into());
+        }
+
+        let mut target = open_shelf(&self.config, name)?;
+
+        for &index in indices {
+            target.add(self.shelf.get_index(index)?)?;
+        }
+
+        let entries = self.describe_entries(indices);
+        let operation = match keep {
+            true => format!("Copied {} from '{}'", entries, self.shelf_name),
+            false => format!("Moved {} from '{}'", entries, self.shelf_name),
+        };
+        self.save_shelf(name, &target, &operation)?;
+
+        if !keep {
+            let mut indices = indices.to_vec();
+
+            // Removing from the end keeps the rest of the indices valid
+            indices.sort_unstable();
+            indices.dedup();
+            for &index in indices.iter().rev() {
+                self.shelf.remove_index(index)?;
+            }
+
+            self.save(&format!("Moved {} to '{}'", entries, name))?;
+        }
+
+        Ok(())
+    }
+
     /// Adds an entry onto the shelf and saves the shelf into a file specified
     /// in the config.
     ///
@@ -47,7 +154,38 @@ impl App {
     ) -> Result<(), Box<dyn std::error::Error>> {
         self.shelf.add(entry)?;
 
-        Ok(self.shelf.save(self.config.db())?)
+        self.save(&format!("Added '{}'", format_title(entry)))?;
+        self.sync_index();
+
+        Ok(())
+    }
+
+    /// Adds the entries which are not on the shelf yet onto it, and saves
+    /// the shelf into a file specified in the config once. Returns the
+    /// entries added and the ones skipped, in their order.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if saving the shelf fails.
+    pub(crate) fn add_entries(
+        &mut self,
+        entries: Vec<Entry>,
+    ) -> Result<(Vec<Entry>, Vec<Entry>), Box<dyn std::error::Error>> {
+        let (added, skipped) = entries
+            .into_iter()
+            .partition::<Vec<Entry>, _>(|entry| self.shelf.add(entry).is_ok());
+
+        let operation = match added.as_slice() {
+            [entry] => format!("Added '{}'", format_title(entry)),
+            _ => format!("Added {} entries", added.len()),
+        };
+
+        if !added.is_empty() {
+            self.save(&operation)?;
+            self.sync_index();
+        }
+
+        Ok((added, skipped))
     }
 
     /// Removes the entry with a corresponding index from the bookshelf if
@@ -61,27 +199,581 @@ impl App {
         &mut self,
         index: usize,
     ) -> Result<(), Box<dyn std::error::Error>> {
+        let operation = format!("Removed {}", self.describe_entries(&[index]));
         self.shelf.remove_index(index)?;
 
-        Ok(self.shelf.save(self.config.db())?)
+        self.save(&operation)?;
+        self.sync_index();
+
+        Ok(())
     }
 
-    /// Opens an entry with a default or specified executable.
+    /// Removes the entries with corresponding indices from the bookshelf,
+    /// and saves the shelf into a file specified in the config.
     ///
     /// # Errors
     ///
-    /// This function will return an error if opening the entry fails.
-    pub(crate) fn open_entry(
-        &self,
-        entry: &Entry,
+    /// This function will return an error if any of the indices don't
+    /// correspond to existing entries, or if saving the shelf fails. No
+    /// entries are removed in that case.
+    pub(crate) fn remove_entries_indices(
+        &mut self,
+        indices: &[usize],
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        for &index in indices {
+            self.shelf.get_index(index)?;
+        }
+
+        let operation = format!("Removed {}", self.describe_entries(indices));
+        let mut indices = indices.to_vec();
+
+        // Removing from the end keeps the rest of the indices valid
+        indices.sort_unstable();
+        indices.dedup();
+        for &index in indices.iter().rev() {
+            self.shelf.remove_index(index)?;
+        }
+
+        self.save(&operation)?;
+        self.sync_index();
+
+        Ok(())
+    }
+
+    /// Marks the entry with a corresponding index as opened, and saves the
+    /// shelf into a file specified in the config.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the index doesn't correspond to
+    /// any existing entries, or if saving the shelf fails.
+    pub(crate) fn mark_opened_index(
+        &mut self,
+        index: usize,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        self.shelf.update_index(index, Entry::mark_opened)?;
+
+        // Opening an entry isn't worth undoing
+        self.save_untracked()
+    }
+
+    /// Marks the entry with a corresponding index as opened, and opens it
+    /// with a default or specified executable.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the index doesn't correspond to
+    /// any existing entries, if saving the shelf fails, or if opening the
+    /// entry fails.
+    pub(crate) fn open_entry_index(
+        &mut self,
+        index: usize,
         exe: Option<String>,
     ) -> Result<(), Box<dyn std::error::Error>> {
+        self.mark_opened_index(index)?;
+
+        let entry = self.shelf.get_index(index)?;
+
         match exe {
             Some(exe) => Ok(open::with(entry.path.clone(), exe)?),
             None => Ok(open::that(entry.path.clone())?),
         }
     }
 
+    /// Marks the entry with a corresponding index as opened, and spawns
+    /// a default or specified executable viewing it. The [`Viewer`] returned
+    /// can be waited on to obtain a reading session.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the index doesn't correspond to
+    /// any existing entries, if saving the shelf fails, or if spawning the
+    /// viewer fails.
+    pub(crate) fn spawn_viewer_index(
+        &mut self,
+        index: usize,
+        exe: Option<String>,
+    ) -> Result<Viewer, Box<dyn std::error::Error>> {
+        self.mark_opened_index(index)?;
+
+        let entry = self.shelf.get_index(index)?;
+
+        Ok(Viewer::spawn(entry, exe)?)
+    }
+
+    /// Records a reading session for the entry provided, and saves the shelf
+    /// into a file specified in the config. Like the progress, the sessions
+    /// aren't recorded in the journal, so that reading doesn't push the
+    /// changes worth undoing out of it.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the entry is no longer on the
+    /// shelf, or if saving the shelf fails.
+    pub(crate) fn record_session(
+        &mut self,
+        entry: &Entry,
+        session: Session,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let index = self.shelf.index_of(entry)?;
+
+        self.shelf
+            .update_index(index, |entry| entry.add_session(session))?;
+
+        self.save_untracked()
+    }
+
+    /// Updates the reading progress of the entry provided, and saves the
+    /// shelf into a file specified in the config, without recording it in
+    /// the journal.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the entry is no longer on the
+    /// shelf, or if saving the shelf fails.
+    pub(crate) fn set_progress(
+        &mut self,
+        entry: &Entry,
+        progress: Progress,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let index = self.shelf.index_of(entry)?;
+
+        self.shelf
+            .update_index(index, |entry| entry.set_progress(progress))?;
+
+        self.save_untracked()
+    }
+
+    /// Toggles a bookmark at the position provided in the entry provided,
+    /// and saves the shelf into a file specified in the config. Returns
+    /// whether a bookmark was added.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the entry is no longer on the
+    /// shelf, or if saving the shelf fails.
+    pub(crate) fn toggle_bookmark(
+        &mut self,
+        entry: &Entry,
+        position: usize,
+    ) -> Result<bool, Box<dyn std::error::Error>> {
+        let index = self.shelf.index_of(entry)?;
+
+        let mut added = false;
+        self.shelf.update_index(index, |entry| {
+            added = entry.toggle_bookmark(position);
+        })?;
+
+        let operation = match added {
+            true => format!("Added a bookmark to '{}'", format_title(entry)),
+            false => {
+                format!("Removed a bookmark from '{}'", format_title(entry))
+            },
+        };
+        self.save(&operation)?;
+
+        Ok(added)
+    }
+
+    /// Rates the entry with a corresponding index, optionally replacing its
+    /// review, and saves the shelf into a file specified in the config.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the index doesn't correspond to
+    /// any existing entries, or if saving the shelf fails.
+    pub(crate) fn rate_entry_index(
+        &mut self,
+        index: usize,
+        rating: Rating,
+        review: Option<String>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        self.shelf
+            .update_index(index, |entry| entry.rate(rating, review))?;
+
+        self.save(&format!("Rated {}", self.describe_entries(&[index])))
+    }
+
+    /// Sets the series of the entry with a corresponding index, or unsets
+    /// it, and saves the shelf into a file specified in the config.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the index doesn't correspond to
+    /// any existing entries, or if saving the shelf fails.
+    pub(crate) fn set_series_index(
+        &mut self,
+        index: usize,
+        series: Option<Series>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let operation = match &series {
+            Some(series) => format!(
+                "Set the series of {} to '{}'",
+                self.describe_entries(&[index]),
+                series
+            ),
+            None => {
+                format!(
+                    "Unset the series of {}",
+                    self.describe_entries(&[index])
+                )
+            },
+        };
+
+        self.shelf
+            .update_index(index, |entry| entry.series = series)?;
+
+        self.save(&operation)
+    }
+
+    /// Applies the fields edited by hand to the entry with a corresponding
+    /// index, keeping its position on the shelf, and saves the shelf into a
+    /// file specified in the config.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the index doesn't correspond to
+    /// any existing entries, if the fields are invalid, if the new path is
+    /// already on the bookshelf, or if saving the shelf fails.
+    pub(crate) fn edit_entry_index(
+        &mut self,
+        index: usize,
+        fields: &Fields,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let mut entry = self.shelf.get_index(index)?.clone();
+        fields.apply(&mut entry)?;
+
+        self.shelf.replace_index(index, entry)?;
+        self.save(&format!("Edited {}", self.describe_entries(&[index])))?;
+        self.sync_index();
+
+        Ok(())
+    }
+
+    /// Sets the reading status of the entries with corresponding indices,
+    /// and saves the shelf into a file specified in the config.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if any of the indices don't
+    /// correspond to existing entries, or if saving the shelf fails. No
+    /// entries are changed in that case.
+    pub(crate) fn set_status_indices(
+        &mut self,
+        indices: &[usize],
+        status: Status,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        for &index in indices {
+            self.shelf.get_index(index)?;
+        }
+
+        for &index in indices {
+            self.shelf
+                .update_index(index, |entry| entry.status = status)?;
+        }
+
+        self.save(&format!(
+            "Marked {} as {}",
+            self.describe_entries(indices),
+            status
+        ))
+    }
+
+    /// Adds the tags to the entries with corresponding indices, and saves
+    /// the shelf into a file specified in the config. Returns the number of
+    /// entries retagged.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if any of the indices don't
+    /// correspond to existing entries, if the tags are invalid, or if saving
+    /// the shelf fails. No entries are changed in that case.
+    pub(crate) fn tag_entries_indices(
+        &mut self,
+        indices: &[usize],
+        tags: &[Tag],
+    ) -> Result<usize, Box<dyn std::error::Error>> {
+        let retagged = self.shelf.tag_indices(indices, tags)?;
+
+        self.save(&format!(
+            "Tagged {} with {}",
+            self.describe_entries(indices),
+            describe_tags(tags)
+        ))?;
+
+        Ok(retagged)
+    }
+
+    /// Removes the tags from the entries with corresponding indices, and
+    /// saves the shelf into a file specified in the config. Returns the
+    /// number of entries retagged.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if any of the indices don't
+    /// correspond to existing entries, or if saving the shelf fails.
+    pub(crate) fn untag_entries_indices(
+        &mut self,
+        indices: &[usize],
+        tags: &[Tag],
+    ) -> Result<usize, Box<dyn std::error::Error>> {
+        let retagged = self.shelf.untag_indices(indices, tags)?;
+
+        self.save(&format!(
+            "Untagged {} from {}",
+            describe_tags(tags),
+            self.describe_entries(indices)
+        ))?;
+
+        Ok(retagged)
+    }
+
+    /// Renames a tag along with its subtags on every entry, and saves the
+    /// shelf into a file specified in the config. Returns the number of
+    /// entries retagged.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if no entry has the tag, if some
+    /// entries already have the new tag, or if saving the shelf fails.
+    pub(crate) fn rename_tag(
+        &mut self,
+        from: &Tag,
+        to: &Tag,
+    ) -> Result<usize, Box<dyn std::error::Error>> {
+        let retagged = self.shelf.rename_tag(from, to)?;
+
+        self.save(&format!("Renamed the tag '{}' to '{}'", from, to))?;
+
+        Ok(retagged)
+    }
+
+    /// Merges a tag along with its subtags into another tag on every entry,
+    /// and saves the shelf into a file specified in the config. Returns the
+    /// number of entries retagged.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if no entry has the tag, or if
+    /// saving the shelf fails.
+    pub(crate) fn merge_tag(
+        &mut self,
+        from: &Tag,
+        into: &Tag,
+    ) -> Result<usize, Box<dyn std::error::Error>> {
+        let retagged = self.shelf.merge_tag(from, into)?;
+
+        self.save(&format!("Merged the tag '{}' into '{}'", from, into))?;
+
+        Ok(retagged)
+    }
+
+    /// Merges the entries with corresponding indices into the entry with the
+    /// index `into`, removes them from the bookshelf, and saves the shelf
+    /// into a file specified in the config.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if any of the indices don't
+    /// correspond to existing entries, or if saving the shelf fails.
+    pub(crate) fn merge_entries_indices(
+        &mut self,
+        into: usize,
+        others: &[usize],
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let operation = format!(
+            "Merged {} into {}",
+            self.describe_entries(others),
+            self.describe_entries(&[into])
+        );
+        self.shelf.merge_indices(into, others)?;
+
+        self.save(&operation)?;
+        self.sync_index();
+
+        Ok(())
+    }
+
+    /// Saves a query as a named collection, and saves the shelf into a file
+    /// specified in the config.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the name or the query is
+    /// invalid, or if saving the shelf fails.
+    pub(crate) fn save_collection(
+        &mut self,
+        name: &str,
+        query: &str,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        self.shelf.save_collection(name, query)?;
+
+        self.save(&format!("Saved the collection '@{}'", name))
+    }
+
+    /// Removes a named collection, and saves the shelf into a file specified
+    /// in the config.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if there is no such collection,
+    /// or if saving the shelf fails.
+    pub(crate) fn remove_collection(
+        &mut self,
+        name: &str,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        self.shelf.remove_collection(name)?;
+
+        self.save(&format!("Removed the collection '@{}'", name))
+    }
+
+    /// Saves the result of a speed-reading practice, and saves the shelf
+    /// into a file specified in the config.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if saving the shelf fails.
+    pub(crate) fn add_speed_test(
+        &mut self,
+        test: SpeedTest,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        self.shelf.speed_tests.push(test);
+
+        self.save("Recorded a speed-reading practice")
+    }
+
+    /// Brings the search index of the shelf up to date with the files of the
+    /// entries, and searches it for the phrase. The index is saved next to
+    /// the shelf, and only the files added or changed since the last update
+    /// are indexed, unless it is rebuilt from scratch.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if reading or saving the index
+    /// fails.
+    pub(crate) fn search(
+        &self,
+        phrase: &str,
+        rebuild: bool,
+    ) -> Result<(Vec<Hit>, Update), Box<dyn std::error::Error>> {
+        let path = self.index_path();
+
+        let mut index = match rebuild {
+            true => Index::default(),
+            false => Index::open_or_default(&path)?,
+        };
+        let update = index.update(self.shelf.entries.iter().map(|e| &*e.path));
+        index.save(&path)?;
+
+        Ok((index.search(phrase), update))
+    }
+
+    /// Updates the search index of the shelf after the entries change, if
+    /// the shelf has been searched before.
+    fn sync_index(&self) {
+        let path = self.index_path();
+
+        // The index is brought up to date on the next search otherwise
+        if !path.exists() {
+            return;
+        }
+
+        if let Ok(mut index) = Index::open_or_default(&path) {
+            index.update(self.shelf.entries.iter().map(|e| &*e.path));
+            let _ = index.save(&path);
+        }
+    }
+
+    /// Returns the path to the search index of the shelf, next to the shelf.
+    fn index_path(&self) -> PathBuf {
+        self.shelf_file(&self.shelf_name, ".index")
+    }
+
+    /// Returns the path to the journal of the named shelf, next to the shelf.
+    fn journal_path(&self, name: &str) -> PathBuf {
+        self.shelf_file(name, ".journal")
+    }
+
+    /// Returns the path to the file of the named shelf with the suffix
+    /// appended.
+    fn shelf_file(&self, name: &str, suffix: &str) -> PathBuf {
+        let mut path = self.config.shelf_db(name).unwrap().into_os_string();
+        path.push(suffix);
+
+        PathBuf::from(path)
+    }
+
+    /// Reads the journal of the shelf in use, which holds the operations
+    /// which can be undone and redone.
+    pub(crate) fn journal(&self) -> Journal {
+        self.open_journal(&self.shelf_name)
+    }
+
+    /// Reads the journal of the named shelf. A journal which can't be read
+    /// is replaced with an empty one, so that it never stands in the way of
+    /// changing the shelf.
+    fn open_journal(&self, name: &str) -> Journal {
+        let path = self.journal_path(name);
+
+        Journal::open_or_default(&path).unwrap_or_else(|e| {
+            report::warning(format!("{}, starting a new one", e));
+
+            // The shelves recorded can't be told apart without the list
+            let _ = remove_dir_all(&path);
+            Journal::new(&path)
+        })
+    }
+
+    /// Undoes the last operation on the shelf in use by restoring the shelf
+    /// as it was before it, and saves the shelf into a file specified in the
+    /// config. Returns the description of the operation, or None if there is
+    /// nothing to undo.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if reading or saving either the
+    /// journal or the shelf fails.
+    pub(crate) fn undo(
+        &mut self,
+    ) -> Result<Option<String>, Box<dyn std::error::Error>> {
+        self.replay(Journal::undo)
+    }
+
+    /// Redoes the last operation undone on the shelf in use by restoring
+    /// the shelf as it was after it, and saves the shelf into a file
+    /// specified in the config. Returns the description of the operation, or
+    /// None if there is nothing to redo.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if reading or saving either the
+    /// journal or the shelf fails.
+    pub(crate) fn redo(
+        &mut self,
+    ) -> Result<Option<String>, Box<dyn std::error::Error>> {
+        self.replay(Journal::redo)
+    }
+
+    /// Undoes or redoes an operation with the step of the journal provided.
+    fn replay(
+        &mut self,
+        step: StepFn,
+    ) -> Result<Option<String>, Box<dyn std::error::Error>> {
+        let mut journal = self.journal();
+
+        let (shelf, description) = match step(&mut journal, &self.shelf)? {
+            Some(step) => step,
+            None => return Ok(None),
+        };
+
+        journal.save()?;
+        self.shelf = shelf;
+        self.save_untracked()?;
+        self.sync_index();
+
+        Ok(Some(description))
+    }
+
     /// Simply lists all of the entries on the bookshelf.
     ///
     /// # Returns
@@ -96,13 +788,56 @@ impl App {
         self.shelf.entries.len()
     }
 
-    /// Decides whether the user is to run a CLI command or use the TUI.
-    pub(crate) async fn start(&mut self) {
-        let matches = self.cli_commands.clone().get_matches();
+    /// Names the entries with corresponding indices in the description of
+    /// an operation: by its title if there is a single entry, or by their
+    /// number otherwise.
+    fn describe_entries(&self, indices: &[usize]) -> String {
+        match indices {
+            [index] => match self.shelf.get_index(*index) {
+                Ok(entry) => format!("'{}'", format_title(entry)),
+                Err(_) => "1 entry".to_string(),
+            },
+            _ => format!("{} entries", indices.len()),
+        }
+    }
 
-        match matches.args_present() || matches.subcommand().is_some() {
-            true => self.run_command(&matches), // CLI
-            false => self.run_tui().await,      // TUI
+    /// Saves the shelf into a file specified in the config, and records the
+    /// operation described in its journal so that it can be undone.
+    fn save(&self, operation: &str) -> Result<(), Box<dyn std::error::Error>> {
+        self.save_shelf(&self.shelf_name, &self.shelf, operation)
+    }
+
+    /// Saves the shelf into a file specified in the config, without
+    /// recording anything in its journal.
+    fn save_untracked(&self) -> Result<(), Box<dyn std::error::Error>> {
+        let db = self.config.shelf_db(&self.shelf_name).unwrap();
+
+        Ok(self.shelf.save(db)?)
+    }
+
+    /// Saves the named shelf into a file specified in the config, and
+    /// records the operation described in its journal, along with a copy of
+    /// the file as it was saved before.
+    fn save_shelf(
+        &self,
+        name: &str,
+        shelf: &Shelf,
+        operation: &str,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let db = self.config.shelf_db(name).unwrap();
+
+        let mut journal = self.open_journal(name);
+        journal.record(operation, &db)?;
+        journal.save()?;
+
+        Ok(shelf.save(db)?)
+    }
+
+    /// Decides whether the user is to run a CLI command or use the TUI.
+    pub(crate) async fn start(&mut self, matches: &ArgMatches) {
+        match matches.subcommand().is_some() {
+            true => self.run_command(matches), // CLI
+            false => self.run_tui().await,     // TUI
         }
     }
 
@@ -116,7 +851,35 @@ impl App {
         let mut tui = Tui::new(self);
 
         if let Err(e) = tui.run().await {
-            println!("Something went wrong: {}", e)
+            report::error("Something went wrong", e)
         }
     }
 }
+
+/// A step of the journal, either undoing or redoing an operation.
+type StepFn =
+    fn(&mut Journal, &Shelf) -> Result<Option<(Shelf, String)>, JournalError>;
+
+/// Describes the tags in the description of an operation, e.g. `'a', 'b'`.
+fn describe_tags(tags: &[Tag]) -> String {
+    tags.iter()
+        .map(|tag| format!("'{}'", tag))
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+/// Opens the named shelf, or an empty one if it hasn't been saved yet.
+///
+/// # Errors
+///
+/// This function will return an error if there is no such shelf in the
+/// config, or if reading its file fails.
+fn open_shelf(
+    config: &Config,
+    name: &str,
+) -> Result<Shelf, Box<dyn std::error::Error>> {
+    match config.shelf_db(name) {
+        Some(db) => Ok(Shelf::open_or_default(db)?),
+        None => Err(NoSuchShelf(name.to_string()).into()),
+    }
+}
@@ -0,0 +1,75 @@
+use ::clap::ArgMatches;
+
+use crate::{
+    app::App,
+    cli::{report, select_entries},
+    utils::{
+        clipboard::copy,
+        export::{to_bib_entry, to_citation},
+        format::format_entry,
+    },
+};
+
+/// Prints the BibTeX of the entries selected, or their citations with
+/// `--cite`, or copies it to the clipboard with `--copy`.
+pub(crate) fn show_bib(app: &mut App, matches: &ArgMatches) {
+    let indices = match select_entries(app, matches, None) {
+        Some(indices) => indices,
+        None => return,
+    };
+
+    let cite = matches.is_present("cite");
+    let text = indices
+        .iter()
+        .map(|&index| {
+            let entry = app.shelf.get_index(index).unwrap();
+
+            if cite {
+                return to_citation(entry);
+            }
+
+            let (bib_entry, error) = to_bib_entry(entry);
+
+            if let Some(e) = error {
+                report::warning(format!(
+                    "Using @misc for '{}', its BibTeX couldn't be read: {}",
+                    format_entry(index, entry),
+                    e
+                ));
+            }
+
+            bib_entry
+        })
+        .collect::<Vec<String>>()
+        .join(if cite { "\n" } else { "\n\n" });
+
+    if !matches.is_present("copy") {
+        println!("{}", text);
+        return;
+    }
+
+    let what = match cite {
+        true => "citation",
+        false => "BibTeX",
+    };
+    let entries = match indices.as_slice() {
+        &[index] => {
+            format!(
+                "'{}'",
+                format_entry(index, app.shelf.get_index(index).unwrap())
+            )
+        },
+        _ => format!("{} entries", indices.len()),
+    };
+
+    match copy(&text, app.config.clipboard_command()) {
+        Ok(()) => report::success(format!(
+            "Successfully copied the {} of {}",
+            what, entries
+        )),
+        Err(e) => report::error(
+            format!("Couldn't copy the {} of {}", what, entries),
+            e,
+        ),
+    }
+}
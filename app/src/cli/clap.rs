@@ -1,13 +1,63 @@
-use clap::{arg, crate_description, crate_name, crate_version, Command};
+use clap::{arg, crate_description, crate_name, crate_version, Arg, Command};
+
+use lib::{
+    edit::Fields, import::Filter, organize::Layout, rating::Rating,
+    series::Series, sort::SortKey,
+};
+
+use crate::utils::output::{Field, Format};
 
 pub(crate) fn get_cli_commands() -> Command<'static> {
     Command::new(crate_name!())
         .about(crate_description!())
         .version(crate_version!())
+        .arg(
+            arg!(--shelf <NAME> "Uses the named shelf instead of the default")
+                .required(false)
+                .global(true),
+        )
+        .arg(
+            arg!(-q --quiet "Prints only the output asked for and the errors")
+                .required(false)
+                .global(true),
+        )
+        .arg(
+            arg!(--json "Prints the errors as JSON objects")
+                .long_help(
+                    "Prints the errors to stderr as JSON objects, one per \
+                    line, with the kind of the error, the exit code and the \
+                    message, e.g. {\"error\":\"not_found\",\"code\":3,\
+                    \"message\":\"...\"}",
+                )
+                .required(false)
+                .global(true),
+        )
         .subcommand(add_command())
         .subcommand(remove_command())
         .subcommand(open_command())
         .subcommand(list_command())
+        .subcommand(rate_command())
+        .subcommand(status_command())
+        .subcommand(export_command())
+        .subcommand(bib_command())
+        .subcommand(speed_command())
+        .subcommand(stats_command())
+        .subcommand(tag_command())
+        .subcommand(shelf_command())
+        .subcommand(collection_command())
+        .subcommand(organize_command())
+        .subcommand(search_command())
+        .subcommand(dedupe_command())
+        .subcommand(series_command())
+        .subcommand(edit_command())
+        .subcommand(info_command())
+        .subcommand(undo_command())
+        .subcommand(redo_command())
+        .subcommand(history_command())
+        .subcommand(completions_command())
+        .subcommand(complete_command())
+        .subcommand(transfer_command("move"))
+        .subcommand(transfer_command("copy"))
 }
 
 pub(crate) fn add_command() -> Command<'static> {
@@ -19,30 +69,595 @@ pub(crate) fn add_command() -> Command<'static> {
                 .required(false)
                 .allow_invalid_utf8(true),
         )
+        .arg(
+            arg!(-t --tag <TAG> "Tags the entry; can be given several times")
+                .required(false)
+                .multiple_occurrences(true),
+        )
+        .arg(
+            arg!(-r --recursive "Adds all of the files in a directory tree")
+                .long_help(
+                    "Adds all of the files in the directory tree under FILE, \
+                    following the symbolic links. The files already on the \
+                    bookshelf are skipped, and a file is linked to the \
+                    BibTeX file next to it with the same name, if that has \
+                    an entry with the name as its cite key.",
+                )
+                .required(false)
+                .conflicts_with("bib"),
+        )
+        .arg(
+            arg!(--ext <EXTS> "Adds only the files with the extensions")
+                .long_help(
+                    "Adds only the files with the comma-separated \
+                    extensions, e.g. 'pdf,epub'",
+                )
+                .required(false)
+                .use_value_delimiter(true)
+                .requires("recursive"),
+        )
+        .arg(
+            arg!(--exclude <PATTERN> "Leaves out the files matching a pattern")
+                .long_help(
+                    "Leaves out the files and the directories matching a \
+                    glob pattern, e.g. '*draft*'; can be given several times",
+                )
+                .required(false)
+                .multiple_occurrences(true)
+                .requires("recursive")
+                .validator(|s| Filter::new(&[], &[s])),
+        )
+        .arg(
+            arg!(-n --"dry-run" "Only lists the files that would be added")
+                .required(false)
+                .requires("recursive"),
+        )
 }
 
 pub(crate) fn remove_command() -> Command<'static> {
     Command::new("remove")
-        .about("Removes an entry from your bookshelf (by its index)")
-        .arg(arg!(<INDEX>).required(true))
+        .about("Removes entries from your bookshelf")
+        .arg(selector_arg().required(true))
+        .arg(yes_arg())
 }
 
 pub(crate) fn list_command() -> Command<'static> {
     Command::new("list")
         .about("List all of the entries on your bookshelf")
-        .arg(arg!(-v - -verbose).required(false))
+        .arg(
+            arg!(-v --verbose "Shows the details of every entry")
+                .long_help(
+                    "Shows the details of every entry: the full path, the \
+                    BibTeX file, the authors, the year and the series, the \
+                    tags, the reading status and progress, and the rating",
+                )
+                .required(false)
+                .conflicts_with_all(&["series", "format", "fields"]),
+        )
+        .arg(
+            arg!(--series "Groups the entries by series, in reading order")
+                .required(false)
+                .conflicts_with_all(&["format", "fields"]),
+        )
+        .arg(
+            arg!(-s --sort <KEYS> "Sorts the entries by the keys provided")
+                .long_help(
+                    "Sorts the entries by a comma-separated list of keys: \
+                    title, author, year, added, opened, rating, progress, \
+                    path, series or tag. A key prefixed with '-' is sorted in \
+                    reverse, e.g. 'author,-year'.",
+                )
+                .required(false)
+                .allow_hyphen_values(true)
+                .validator(SortKey::parse_list),
+        )
+        .arg(
+            arg!(--"min-rating" <RATING> "Lists only entries rated at least")
+                .required(false)
+                .validator(|s| s.parse::<Rating>()),
+        )
+        .arg(
+            arg!(-t --tag <TAG> "Lists only entries with the tag or subtags")
+                .required(false),
+        )
+        .arg(filter_arg())
+        .arg(format_arg())
+        .arg(fields_arg())
+}
+
+/// The `SELECTOR` argument, which takes the indices, the identifiers or
+/// a query of the entries, or a fuzzy search for one.
+pub(crate) fn selector_arg() -> Arg<'static> {
+    arg!([SELECTOR] "Index of the entry, or another way to select entries")
+        .long_help(
+            "Selects the entries by a comma-separated list of indices, ranges \
+            and identifiers, e.g. '3', '1,4,9', '3-7' or 'id:ab12', or 'last' \
+            for the entry added last. A query, e.g. 'tag:fiction' or \
+            '@collection', selects all of the entries matching it. Anything \
+            else is a fuzzy search for an entry by its title, authors, tags \
+            or file name, e.g. 'sicp'; you are asked to choose one of the \
+            entries if several match. A bare number is always an index, so \
+            quote text looking like a list to search for it, e.g. '\"1984\"'.",
+        )
+}
+
+/// The `--yes` flag, which skips the confirmation before changing several
+/// entries at once.
+pub(crate) fn yes_arg() -> Arg<'static> {
+    arg!(-y --yes "Doesn't ask before changing several entries").required(false)
+}
+
+/// The `--filter` option, which takes a query the entries have to match.
+/// The query is parsed once the shelf is open, as it may refer to the saved
+/// collections of the shelf.
+pub(crate) fn filter_arg() -> Arg<'static> {
+    arg!(-f --filter <QUERY> "Keeps only the entries matching the query")
+        .required(false)
+}
+
+/// The `--format` option, which prints the entries as a table or in
+/// a machine-readable format instead of one name per line.
+pub(crate) fn format_arg() -> Arg<'static> {
+    arg!(--format <FORMAT> "Prints the entries in the format provided")
+        .long_help(
+            "Prints the entries in the format provided: table, json, ndjson, \
+            csv or tsv. Every format but the table is meant for scripts, and \
+            the names and the values of the fields are kept stable. Lists, \
+            such as the authors and the tags, are separated with semicolons \
+            in every format but JSON.",
+        )
+        .required(false)
+        .validator(|s| s.parse::<Format>())
+}
+
+/// The `--fields` option, which chooses the fields of the entries printed.
+pub(crate) fn fields_arg() -> Arg<'static> {
+    arg!(--fields <FIELDS> "Sets the fields of the entries printed")
+        .long_help(
+            "Sets a comma-separated list of the fields of the entries \
+            printed, as a table unless another format is set: index, id, \
+            title, authors, year, path, bib, series, status, rating, \
+            progress (in percent), tags, added, opened, read (in seconds) or \
+            review. \
+            Defaults to 'index,title,authors,year,status,rating,tags,path'.",
+        )
+        .required(false)
+        .validator(Field::parse_list)
 }
 
 pub(crate) fn open_command() -> Command<'static> {
     Command::new("open")
-        .about("Opens an entry in an external viewer")
+        .about("Opens entries in an external viewer")
+        .arg(selector_arg().required(true))
+        .arg(yes_arg())
         .arg(
-            arg!(<INDEX> "Numerical index of the entry in the bookshelf")
-                .required(true),
+            arg!(-e --exec <EXE> "Sets the executable to use")
+                .required(false)
+                .allow_invalid_utf8(true),
         )
         .arg(
-            arg!(-e --exec <EXE> "Sets the executable to use")
+            arg!(-t --track "Records a reading session until the viewer exits")
+                .required(false),
+        )
+}
+
+pub(crate) fn rate_command() -> Command<'static> {
+    Command::new("rate")
+        .about("Rates an entry from 1 to 5 stars (in half-star steps)")
+        .arg(selector_arg().required(true))
+        .arg(
+            arg!(<RATING> "The rating, e.g. 4 or 4.5")
+                .required(true)
+                .validator(|s| s.parse::<Rating>()),
+        )
+        .arg(
+            arg!(-r --review <TEXT> "Sets a free-text review of the entry")
+                .required(false),
+        )
+}
+
+pub(crate) fn status_command() -> Command<'static> {
+    Command::new("status")
+        .about(
+            "Sets the reading status of an entry, or of all matching entries",
+        )
+        .arg(
+            arg!(<STATUS> "The reading status")
+                .required(true)
+                .possible_values(["want", "reading", "finished"]),
+        )
+        .arg(
+            selector_arg()
+                .required_unless_present("filter")
+                .conflicts_with("filter"),
+        )
+        .arg(filter_arg())
+        .arg(yes_arg())
+}
+
+pub(crate) fn export_command() -> Command<'static> {
+    Command::new("export")
+        .about("Exports the entries on your bookshelf")
+        .long_about(
+            "Exports the entries on your bookshelf. JSON and CSV exports \
+            have every field 'list --fields' can print, under the same \
+            names, while BibTeX exports have the linked BibTeX of the \
+            entries, or @misc entries for the rest.",
+        )
+        .arg(
+            arg!(<FORMAT> "The format to export the entries in")
+                .required(true)
+                .possible_values(["json", "csv", "bib"]),
+        )
+        .arg(
+            arg!(-o --output <FILE> "Writes the export to a file")
                 .required(false)
                 .allow_invalid_utf8(true),
         )
+        .arg(filter_arg())
+}
+
+pub(crate) fn bib_command() -> Command<'static> {
+    Command::new("bib")
+        .about("Prints the BibTeX or a citation of entries, or copies it")
+        .long_about(
+            "Prints the BibTeX of entries, or a citation formatted from \
+            their authors, year and title, or copies it to the clipboard. \
+            The terminal is asked to copy it with an OSC 52 escape \
+            sequence, which works over SSH and in tmux. Without a terminal, \
+            it is piped into the command set as clipboard_command in the \
+            config, such as 'wl-copy' or 'xclip -selection clipboard'.",
+        )
+        .arg(selector_arg().required(true))
+        .arg(
+            arg!(--cite "Gives a formatted citation instead of the BibTeX")
+                .required(false),
+        )
+        .arg(
+            arg!(-c --copy "Copies to the clipboard instead of printing")
+                .required(false),
+        )
+}
+
+pub(crate) fn speed_command() -> Command<'static> {
+    Command::new("speed")
+        .about("Practices speed reading on a passage of a text or EPUB entry")
+        .arg(selector_arg().required(true))
+        .arg(
+            arg!(-w --words <N> "Sets the length of the passage in words")
+                .required(false)
+                .default_value("250")
+                .validator(|s| s.parse::<usize>()),
+        )
+}
+
+pub(crate) fn stats_command() -> Command<'static> {
+    Command::new("stats")
+        .about("Shows your reading statistics and speed-reading progress")
+}
+
+pub(crate) fn tag_command() -> Command<'static> {
+    Command::new("tag")
+        .about("Manages the tags of the entries on your bookshelf")
+        .subcommand_required(true)
+        .subcommand(
+            Command::new("tree")
+                .about("Shows the tag hierarchy with the number of entries"),
+        )
+        .subcommand(edit_tags_command("add"))
+        .subcommand(edit_tags_command("rm"))
+        .subcommand(
+            Command::new("rename")
+                .about("Renames a tag along with all of its subtags")
+                .arg(arg!(<FROM> "The tag to rename").required(true))
+                .arg(arg!(<TO> "The new name of the tag").required(true)),
+        )
+        .subcommand(
+            Command::new("merge")
+                .about(
+                    "Merges a tag along with all of its subtags into another",
+                )
+                .arg(arg!(<FROM> "The tag to merge").required(true))
+                .arg(arg!(<INTO> "The tag to merge into").required(true)),
+        )
+}
+
+/// The `tag add` and `tag rm` commands, which take an entry and the tags,
+/// or just the tags if the entries are filtered by a query instead.
+pub(crate) fn edit_tags_command(name: &'static str) -> Command<'static> {
+    Command::new(name)
+        .about(match name {
+            "add" => "Adds tags to an entry, or to all matching entries",
+            _ => "Removes tags from an entry, or from all matching entries",
+        })
+        .override_usage(match name {
+            "add" => {
+                "bookshelf tag add <SELECTOR> <TAGS>...\n    \
+                bookshelf tag add --filter <QUERY> <TAGS>..."
+            },
+            _ => {
+                "bookshelf tag rm <SELECTOR> <TAGS>...\n    \
+                bookshelf tag rm --filter <QUERY> <TAGS>..."
+            },
+        })
+        .after_help(
+            "With --filter, all of the entries matching the query are \
+            changed, and all of the values given are tags.",
+        )
+        .arg(selector_arg().required(true))
+        .arg(arg!([TAGS] ... "The tags, e.g. cs/algorithms"))
+        .arg(filter_arg())
+        .arg(yes_arg())
+}
+
+pub(crate) fn shelf_command() -> Command<'static> {
+    Command::new("shelf")
+        .about("Manages the named shelves")
+        .subcommand_required(true)
+        .subcommand(
+            Command::new("list")
+                .about("Lists the shelves with the number of entries on them"),
+        )
+        .subcommand(
+            Command::new("create")
+                .about("Creates a new named shelf")
+                .arg(arg!(<NAME> "The name of the shelf").required(true))
+                .arg(
+                    arg!([DB] "Sets the path to the file of the shelf")
+                        .allow_invalid_utf8(true),
+                ),
+        )
+        .subcommand(
+            Command::new("default")
+                .about("Sets the shelf used unless another one is specified")
+                .arg(arg!(<NAME> "The name of the shelf").required(true)),
+        )
+}
+
+pub(crate) fn collection_command() -> Command<'static> {
+    Command::new("collection")
+        .about("Manages the saved queries, usable in filters as @name")
+        .subcommand_required(true)
+        .subcommand(
+            Command::new("list")
+                .about("Lists the collections with the number of entries"),
+        )
+        .subcommand(
+            Command::new("save")
+                .about("Saves a query as a collection")
+                .arg(arg!(<NAME> "The name of the collection").required(true))
+                .arg(arg!(<QUERY> "The query to save").required(true)),
+        )
+        .subcommand(
+            Command::new("remove")
+                .about("Removes a collection, leaving the entries untouched")
+                .arg(arg!(<NAME> "The name of the collection").required(true)),
+        )
+}
+
+/// The `move` and `copy` commands, which differ only in whether the entries
+/// are kept on the shelf in use.
+pub(crate) fn transfer_command(name: &'static str) -> Command<'static> {
+    Command::new(name)
+        .about(match name {
+            "move" => "Moves an entry, or all matching entries, to a shelf",
+            _ => "Copies an entry, or all matching entries, to a shelf",
+        })
+        .arg(arg!(<SHELF> "The name of the shelf").required(true))
+        .arg(
+            selector_arg()
+                .required_unless_present("filter")
+                .conflicts_with("filter"),
+        )
+        .arg(filter_arg())
+        .arg(yes_arg())
+}
+
+pub(crate) fn search_command() -> Command<'static> {
+    Command::new("search")
+        .about("Searches the text of the entries' files for a phrase")
+        .long_about(
+            "Searches the text of the entries' PDF, EPUB, plain text and \
+            Markdown files for a phrase, ignoring case and punctuation. The \
+            files are indexed on the first search, and only the files added \
+            or changed since are indexed on the following ones. The text of \
+            PDF files is extracted with pdftotext from Poppler; a file whose \
+            text couldn't be extracted is tried again once it changes, or \
+            with --reindex.",
+        )
+        .arg(arg!(<PHRASE> "The phrase to search for").required(true))
+        .arg(
+            arg!(-n --matches <COUNT> "Sets how many matches to show per entry")
+                .required(false)
+                .default_value("3")
+                .validator(|s| s.parse::<usize>()),
+        )
+        .arg(
+            arg!(--reindex "Indexes all of the files anew")
+                .long_help(
+                    "Indexes all of the files anew, including the ones whose \
+                    text couldn't be extracted before",
+                )
+                .required(false),
+        )
+        .arg(filter_arg())
+        .arg(format_arg())
+        .arg(fields_arg())
+}
+
+pub(crate) fn dedupe_command() -> Command<'static> {
+    Command::new("dedupe")
+        .about("Finds duplicate entries and merges them")
+        .long_about(
+            "Finds the entries which are likely duplicates of each other: \
+            the ones whose files have the same content, the ones with the \
+            same ISBN or DOI, and the ones with nearly the same title. For \
+            every group of them, asks which entry to merge the rest into. \
+            The merged entry combines their tags, reviews and reading \
+            sessions, and keeps their files as other formats.",
+        )
+        .arg(
+            arg!(-l --list "Only lists the duplicates without merging them")
+                .required(false),
+        )
+}
+
+pub(crate) fn series_command() -> Command<'static> {
+    Command::new("series")
+        .about("Manages the series of the entries")
+        .long_about(
+            "Manages the series of the entries. An entry is part of the \
+            series set with 'series set', or else of the one in the series \
+            and number fields of its BibLaTeX entry.",
+        )
+        .subcommand_required(true)
+        .subcommand(
+            Command::new("set")
+                .about("Sets the series of an entry")
+                .arg(selector_arg().required(true))
+                .arg(
+                    arg!(<SERIES> "The series and the number, e.g. 'Dune #2'")
+                        .required(true)
+                        .validator(|s| s.parse::<Series>()),
+                ),
+        )
+        .subcommand(
+            Command::new("unset")
+                .about("Unsets the series set for an entry")
+                .arg(selector_arg().required(true)),
+        )
+        .subcommand(
+            Command::new("next")
+                .about("Suggests the entry to read next in every series"),
+        )
+}
+
+pub(crate) fn edit_command() -> Command<'static> {
+    Command::new("edit")
+        .about("Edits the fields of an entry")
+        .long_about(
+            "Edits the fields of an entry. Without any options, the fields \
+            are opened as a TOML document in $VISUAL or $EDITOR, and applied \
+            once the editor exits. The title, the authors and the year set \
+            here override the ones from the BibLaTeX entry.",
+        )
+        .arg(selector_arg().required(true))
+        .arg(
+            arg!(-s --set <FIELD> "Sets a field, e.g. 'title=SICP'")
+                .required(false)
+                .multiple_occurrences(true)
+                .long_help(
+                    "Sets a field, e.g. 'title=SICP'. The authors are \
+                    separated with ' and ', and the tags with commas.",
+                )
+                .validator(|s| match s.split_once('=') {
+                    Some((key, _)) if Fields::KEYS.contains(&key) => Ok(()),
+                    Some((key, _)) => Err(format!("Unknown field '{}'", key)),
+                    None => Err("Expected FIELD=VALUE".to_string()),
+                }),
+        )
+        .arg(
+            arg!(-u --unset <FIELD> "Unsets a field")
+                .required(false)
+                .multiple_occurrences(true)
+                .possible_values(Fields::KEYS),
+        )
+        .arg(
+            arg!(-b --bib <FILE> "Sets a path to the Bib(La)TeX entry")
+                .required(false)
+                .allow_invalid_utf8(true),
+        )
+        .arg(
+            arg!(-p --path <FILE> "Sets a path to the file of the entry")
+                .required(false)
+                .allow_invalid_utf8(true),
+        )
+}
+
+pub(crate) fn info_command() -> Command<'static> {
+    Command::new("info")
+        .about("Shows everything known about an entry")
+        .long_about(
+            "Shows everything known about an entry: its bibliographic \
+            fields, its files along with their sizes and formats, the dates \
+            it was added and last opened, its status, progress, rating and \
+            review, and the history of its reading sessions.",
+        )
+        .arg(selector_arg().required(true))
+}
+
+pub(crate) fn undo_command() -> Command<'static> {
+    Command::new("undo")
+        .about("Undoes the last change to the shelf")
+        .long_about(
+            "Undoes the last change to the shelf, such as adding, removing, \
+            editing or tagging entries, by restoring the shelf as it was \
+            before it. The last 50 changes can be undone, one at a time. \
+            Opening an entry isn't undone, but its last opening date is \
+            restored along with the rest of the shelf.",
+        )
+}
+
+pub(crate) fn redo_command() -> Command<'static> {
+    Command::new("redo")
+        .about("Redoes the last change undone, unless the shelf changed since")
+}
+
+pub(crate) fn history_command() -> Command<'static> {
+    Command::new("history")
+        .about("Shows the recent changes to the shelf, the newest first")
+        .arg(
+            arg!(-n --number <N> "Shows only the last N changes")
+                .required(false)
+                .validator(|s| s.parse::<usize>()),
+        )
+}
+
+pub(crate) fn completions_command() -> Command<'static> {
+    Command::new("completions")
+        .about("Prints the completion script for a shell")
+        .long_about(
+            "Prints the completion script for a shell, which completes the \
+            commands and the options, as well as the indices of the entries \
+            for 'open' and 'remove', and the tags on the shelf for '--tag'. \
+            For example, add 'source <(bookshelf completions bash)' to \
+            ~/.bashrc, or 'bookshelf completions fish | source' to \
+            ~/.config/fish/config.fish.",
+        )
+        .arg(
+            arg!(<SHELL> "The shell to complete in")
+                .possible_values(["bash", "zsh", "fish"]),
+        )
+}
+
+/// The hidden command the completion scripts call to list the entries or
+/// the tags on the shelf.
+pub(crate) fn complete_command() -> Command<'static> {
+    Command::new("complete-values")
+        .hide(true)
+        .arg(arg!(<KIND>).possible_values(["entries", "tags"]))
+}
+
+pub(crate) fn organize_command() -> Command<'static> {
+    Command::new("organize")
+        .about("Builds a directory tree of links to the entries' files")
+        .long_about(
+            "Builds a directory tree of symbolic links to the entries' \
+            files. Running it again updates the tree: the links no longer \
+            needed are removed, while files other than links are never \
+            touched.",
+        )
+        .arg(
+            arg!(-i --into <DIR> "Sets the directory to build the tree in")
+                .required(true)
+                .allow_invalid_utf8(true),
+        )
+        .arg(
+            arg!(-b --by <LAYOUT> "Sets what the directories stand for")
+                .required(false)
+                .default_value("tag")
+                .possible_values(["tag", "author", "year"])
+                .validator(|s| s.parse::<Layout>()),
+        )
+        .arg(filter_arg())
 }
@@ -0,0 +1,73 @@
+use ::clap::ArgMatches;
+
+use crate::{app::App, cli::report};
+
+pub(crate) fn match_collection_subcommand(app: &mut App, matches: &ArgMatches) {
+    match matches.subcommand() {
+        Some(("list", _)) => list_collections(app),
+        Some(("save", matches)) => save_collection(app, matches),
+        Some(("remove", matches)) => remove_collection(app, matches),
+        _ => panic!("The clap app should have handled this"),
+    }
+}
+
+/// Lists the saved collections along with their queries and the number of
+/// entries matching them right now.
+fn list_collections(app: &App) {
+    if app.shelf.collections.is_empty() {
+        report::success("There are no saved collections on the bookshelf");
+        return;
+    }
+
+    for (name, query) in app.shelf.collections.iter() {
+        let size = match app.shelf.parse_query(query) {
+            Ok(parsed) => {
+                let matching = app
+                    .shelf
+                    .entries
+                    .iter()
+                    .filter(|entry| parsed.matches(entry))
+                    .count();
+
+                format!("{} entries", matching)
+            },
+            Err(e) => e.to_string(),
+        };
+
+        println!("@{} ({}) - {}", name, size, query);
+    }
+}
+
+/// Saves a query as a named collection, replacing the collection with the
+/// same name, if there was one.
+fn save_collection(app: &mut App, matches: &ArgMatches) {
+    let name = matches.value_of("NAME").unwrap().trim_start_matches('@');
+    let query = matches.value_of("QUERY").unwrap();
+
+    match app.save_collection(name, query) {
+        Ok(()) => report::success(format!(
+            "Successfully saved the collection '@{}'",
+            name
+        )),
+        Err(e) => report::error(
+            format!("Couldn't save the collection '@{}'", name),
+            e,
+        ),
+    }
+}
+
+/// Removes a named collection. The entries in it are left untouched.
+fn remove_collection(app: &mut App, matches: &ArgMatches) {
+    let name = matches.value_of("NAME").unwrap().trim_start_matches('@');
+
+    match app.remove_collection(name) {
+        Ok(()) => report::success(format!(
+            "Successfully removed the collection '@{}'",
+            name
+        )),
+        Err(e) => report::error(
+            format!("Couldn't remove the collection '@{}'", name),
+            e,
+        ),
+    }
+}
@@ -0,0 +1,142 @@
+use ::clap::ArgMatches;
+use clap_complete::{generate, Shell};
+
+use crate::{app::App, utils::format::format_title};
+
+/// Completes the indices of the entries for `open` and `remove`, and the
+/// tags for `--tag` of `add` and `list`, by asking `bookshelf
+/// complete-values`, and leaves the rest to the completions generated by
+/// clap. The options, which may come before the command, are skipped along
+/// with the values of the ones taking any.
+const BASH_DYNAMIC: &str = r#"
+_bookshelf_dynamic() {
+    local cur="${COMP_WORDS[COMP_CWORD]}" prev="${COMP_WORDS[COMP_CWORD-1]}"
+    local command="" values=0 i
+
+    for (( i = 1; i < COMP_CWORD; i++ )); do
+        case "${COMP_WORDS[i]}" in
+            --shelf|--exec|-e)
+                # bash splits "--shelf=work" into three words
+                [[ "${COMP_WORDS[i+1]}" == "=" ]] && (( i++ ))
+                (( i++ )) ;;
+            -*) ;;
+            *) [[ -z "$command" ]] && command="${COMP_WORDS[i]}" \
+                   || (( values++ )) ;;
+        esac
+    done
+
+    if [[ ( "$command" == "add" || "$command" == "list" ) \
+            && ( "$prev" == "--tag" || "$prev" == "-t" ) ]]; then
+        local tags="$(bookshelf complete-values tags)"
+        COMPREPLY=( $(compgen -W "$tags" -- "$cur") )
+    elif [[ ( "$command" == "open" || "$command" == "remove" ) \
+            && "$cur" != -* && "$prev" != -* && $values -eq 0 ]]; then
+        local indices="$(bookshelf complete-values entries | cut -f1)"
+        COMPREPLY=( $(compgen -W "$indices" -- "$cur") )
+    else
+        _bookshelf "$@"
+    fi
+}
+
+complete -F _bookshelf_dynamic -o bashdefault -o default bookshelf
+"#;
+
+/// The same as [`BASH_DYNAMIC`], with the titles of the entries shown next
+/// to their indices. Called instead of the clap completions at the end of
+/// the script, which zsh runs on every completion.
+const ZSH_DYNAMIC: &str = r#"
+_bookshelf_dynamic() {
+    local command="" prev="${words[CURRENT-1]}"
+    local -i values=0 i
+    local -a candidates
+
+    for (( i = 2; i < CURRENT; i++ )); do
+        case "${words[i]}" in
+            --shelf|--exec|-e) (( i++ )) ;;
+            -*) ;;
+            *) [[ -z "$command" ]] && command="${words[i]}" \
+                   || (( values++ )) ;;
+        esac
+    done
+
+    if [[ ( "$command" == "add" || "$command" == "list" ) \
+            && ( "$prev" == "--tag" || "$prev" == "-t" ) ]]; then
+        candidates=("${(@f)$(bookshelf complete-values tags)}")
+        compadd -a candidates
+    elif [[ ( "$command" == "open" || "$command" == "remove" ) \
+            && "$PREFIX" != -* && "$prev" != -* && $values -eq 0 ]]; then
+        candidates=("${(@f)$(bookshelf complete-values entries)}")
+        candidates=("${candidates[@]//:/\\:}")
+        candidates=("${candidates[@]/$'\t'/:}")
+        _describe -V 'entry' candidates
+    else
+        _bookshelf "$@"
+    fi
+}
+
+_bookshelf_dynamic "$@"
+"#;
+
+/// The same as [`BASH_DYNAMIC`], with the titles of the entries shown next
+/// to their indices.
+const FISH_DYNAMIC: &str = r#"
+complete -c bookshelf -n "__fish_seen_subcommand_from open remove" -f -k \
+    -a "(bookshelf complete-values entries)"
+complete -c bookshelf -n "__fish_seen_subcommand_from add list" -s t -l tag \
+    -x -a "(bookshelf complete-values tags)"
+"#;
+
+/// Prints the completion script for the shell provided, generated from the
+/// clap commands along with the completion of the entries and the tags.
+pub(crate) fn print_completions(app: &mut App, matches: &ArgMatches) {
+    let shell = matches.value_of("SHELL").unwrap();
+    let (shell, dynamic) = match shell {
+        "bash" => (Shell::Bash, BASH_DYNAMIC),
+        "zsh" => (Shell::Zsh, ZSH_DYNAMIC),
+        "fish" => (Shell::Fish, FISH_DYNAMIC),
+        _ => panic!("The clap app should have handled this"),
+    };
+
+    let mut script = vec![];
+    let mut command = app.cli_commands.clone();
+    generate(shell, &mut command, "bookshelf", &mut script);
+
+    // The dynamic completions are called in place of the clap ones in zsh
+    let script = String::from_utf8_lossy(&script);
+    let script = match shell {
+        Shell::Zsh => script
+            .trim_end()
+            .trim_end_matches("_bookshelf \"$@\"")
+            .trim_end(),
+        _ => script.trim_end(),
+    };
+
+    println!("{}\n\n{}", script, dynamic.trim());
+}
+
+/// Prints the values the completion scripts offer, one per line: the
+/// indices of the entries, each followed by a tab and the title, or the
+/// tags on the shelf.
+pub(crate) fn complete_values(app: &mut App, matches: &ArgMatches) {
+    match matches.value_of("KIND").unwrap() {
+        "entries" => {
+            for (i, entry) in app.list_entries() {
+                println!("{}\t{}", i + 1, format_title(entry));
+            }
+        },
+        "tags" => {
+            let mut tags = app
+                .shelf
+                .tags
+                .iter()
+                .map(|tag| tag.to_string())
+                .collect::<Vec<String>>();
+            tags.sort();
+
+            for tag in tags {
+                println!("{}", tag);
+            }
+        },
+        _ => panic!("The clap app should have handled this"),
+    }
+}
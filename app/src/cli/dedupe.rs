@@ -0,0 +1,116 @@
+use std::{
+    io::{stdin, stdout, Write},
+    path::{Path, PathBuf},
+};
+
+use ::clap::ArgMatches;
+
+use lib::dedupe::{find_duplicates, Reason};
+
+use crate::{app::App, cli::report, utils::format::format_entry};
+
+/// Finds the entries which are likely duplicates of each other, and asks
+/// the user which entry of every group the rest should be merged into,
+/// unless the duplicates are only listed.
+pub(crate) fn dedupe_entries(app: &mut App, matches: &ArgMatches) {
+    // The entries are looked up by their paths, as merging shifts the indices
+    let groups = find_duplicates(app.shelf.entries.iter())
+        .into_iter()
+        .map(|group| {
+            let paths = group
+                .entries
+                .iter()
+                .map(|&i| app.shelf.get_index(i + 1).unwrap().path.clone())
+                .collect::<Vec<PathBuf>>();
+
+            (paths, group.reasons)
+        })
+        .collect::<Vec<(Vec<PathBuf>, Vec<Reason>)>>();
+
+    if groups.is_empty() {
+        report::success("No duplicate entries found");
+        return;
+    }
+
+    for (group, (paths, reasons)) in groups.iter().enumerate() {
+        if group > 0 {
+            println!();
+        }
+
+        let indices = paths
+            .iter()
+            .filter_map(|path| find_index(app, path))
+            .collect::<Vec<usize>>();
+
+        let reasons = reasons
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<String>>();
+        println!("Likely duplicates, with {}:", reasons.join(", "));
+
+        for (choice, &index) in indices.iter().enumerate() {
+            let entry = app.shelf.get_index(index).unwrap();
+
+            println!(
+                "  {}) {} ({})",
+                choice + 1,
+                format_entry(index, entry),
+                entry.path.display()
+            );
+        }
+
+        if matches.is_present("list") {
+            continue;
+        }
+
+        print!(
+            "Merge into which one? [1-{}, Enter to skip] ",
+            indices.len()
+        );
+        if stdout().flush().is_err() {
+            return;
+        }
+
+        let mut answer = String::new();
+        if stdin().read_line(&mut answer).is_err() {
+            return;
+        }
+
+        let into = match answer.trim() {
+            "" => continue,
+            answer => match answer.parse::<usize>() {
+                Ok(choice) if (1..=indices.len()).contains(&choice) => {
+                    indices[choice - 1]
+                },
+                _ => {
+                    report::success("No entry chosen");
+                    continue;
+                },
+            },
+        };
+
+        let path = app.shelf.get_index(into).unwrap().path.clone();
+
+        match app.merge_entries_indices(into, &indices) {
+            Ok(()) => {
+                let index = find_index(app, &path).unwrap();
+                let entry = app.shelf.get_index(index).unwrap();
+
+                report::success(format!(
+                    "Successfully merged {} entries into '{}'",
+                    indices.len() - 1,
+                    format_entry(index, entry)
+                ));
+            },
+            Err(e) => report::error("Couldn't merge the entries", e),
+        }
+    }
+}
+
+/// Returns the **1-based** index of the entry with the file provided.
+fn find_index(app: &App, path: &Path) -> Option<usize> {
+    app.list_entries()
+        .into_iter()
+        .find(|(_, entry)| entry.path == path)
+        .map(|(i, _)| i + 1)
+}
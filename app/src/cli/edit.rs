@@ -0,0 +1,176 @@
+use std::{
+    env::var,
+    fs::read_to_string,
+    io::{stdin, stdout, Write},
+    path::Path,
+    process::Command,
+};
+
+use ::clap::ArgMatches;
+use tempfile::Builder;
+
+use lib::edit::Fields;
+
+use crate::{
+    app::App,
+    cli::{
+        report::{self, Failure},
+        select_entry,
+    },
+    utils::format::format_entry,
+};
+
+/// The comment written at the top of the document opened in the editor.
+const HEADER: &str = "\
+# Edit the fields of the entry, then save and quit. The fields commented
+# out or removed are unset; the title, the authors and the year then come
+# from the BibTeX file, if any.
+";
+
+/// Edits the fields of an entry, either with the options provided or, if
+/// there are none, as a TOML document in the user's editor.
+pub(crate) fn edit_entry(app: &mut App, matches: &ArgMatches) {
+    let entry_index = match select_entry(app, matches) {
+        Some(entry_index) => entry_index,
+        None => return,
+    };
+
+    let (entry_name, old_fields) = match app.shelf.get_index(entry_index) {
+        Ok(entry) => (format_entry(entry_index, entry), Fields::of(entry)),
+        Err(e) => {
+            report::error(format!("Couldn't edit entry {}", entry_index), e);
+            return;
+        },
+    };
+
+    let has_options = ["set", "unset", "bib", "path"]
+        .iter()
+        .any(|option| matches.is_present(option));
+
+    let fields = if has_options {
+        fields_from_options(old_fields.clone(), matches)
+    } else {
+        fields_from_editor(&old_fields)
+    };
+
+    let fields = match fields {
+        Ok(Some(fields)) => fields,
+        Ok(None) => return,
+        Err(e) => {
+            report::error(format!("Couldn't edit '{}'", entry_name), e);
+            return;
+        },
+    };
+
+    if fields == old_fields {
+        report::success(format!("No changes made to '{}'", entry_name));
+        return;
+    }
+
+    match app.edit_entry_index(entry_index, &fields) {
+        Ok(()) => {
+            let entry = app.shelf.get_index(entry_index).unwrap();
+            report::success(format!(
+                "Successfully edited '{}'",
+                format_entry(entry_index, entry)
+            ))
+        },
+        Err(e) => report::error(format!("Couldn't edit '{}'", entry_name), e),
+    }
+}
+
+/// Applies the `--set`, `--unset`, `--bib` and `--path` options to the
+/// fields, in that order.
+fn fields_from_options(
+    mut fields: Fields,
+    matches: &ArgMatches,
+) -> Result<Option<Fields>, Box<dyn std::error::Error>> {
+    for field in matches.values_of("set").into_iter().flatten() {
+        let (key, value) = field.split_once('=').unwrap();
+        fields.set(key, value)?;
+    }
+
+    for key in matches.values_of("unset").into_iter().flatten() {
+        fields.unset(key)?;
+    }
+
+    if let Some(bib_path) = matches.value_of_os("bib") {
+        fields.bib = Some(bib_path.into());
+    }
+
+    if let Some(path) = matches.value_of_os("path") {
+        fields.path = path.into();
+    }
+
+    Ok(Some(fields))
+}
+
+/// Opens the fields as a TOML document in the user's editor, and parses
+/// them once it exits. The user is asked to edit the document again if it
+/// is invalid. Returns None if they choose not to.
+fn fields_from_editor(
+    fields: &Fields,
+) -> Result<Option<Fields>, Box<dyn std::error::Error>> {
+    // The file is created with a random name only the user can access, and
+    // is removed once dropped
+    let mut file = Builder::new()
+        .prefix("bookshelf-")
+        .suffix(".toml")
+        .tempfile()?;
+    write!(file, "{}\n{}", HEADER, fields.to_document())?;
+    file.flush()?;
+
+    loop {
+        run_editor(file.path())?;
+
+        let document = read_to_string(file.path())?;
+
+        match Fields::from_document(&document) {
+            Ok(fields) => return Ok(Some(fields)),
+            Err(e) => {
+                report::warning(&e);
+
+                if !edit_again() {
+                    report::failure(
+                        Failure::Invalid,
+                        "No changes made, the fields are invalid",
+                    );
+                    return Ok(None);
+                }
+            },
+        }
+    }
+}
+
+/// Asks the user whether to edit the invalid document again, which they
+/// do unless they answer no.
+fn edit_again() -> bool {
+    print!("Edit again? [Y/n] ");
+    let mut answer = String::new();
+
+    stdout().flush().is_ok()
+        && stdin().read_line(&mut answer).is_ok()
+        && !answer.trim().eq_ignore_ascii_case("n")
+}
+
+/// Runs the editor set in `$VISUAL` or `$EDITOR`, or `vi` otherwise, on the
+/// file provided, and waits for it to exit.
+fn run_editor(path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    let editor = var("VISUAL")
+        .or_else(|_| var("EDITOR"))
+        .unwrap_or_else(|_| "vi".to_string());
+
+    // The editor may come with arguments, e.g. `code --wait`
+    let mut words = editor.split_whitespace();
+    let program = words.next().unwrap_or("vi");
+
+    let status = Command::new(program).args(words).arg(path).status()?;
+
+    if !status.success() {
+        return Err(
+            format!("The editor '{}' exited with {}", editor, status).into()
+        );
+    }
+
+    Ok(())
+}
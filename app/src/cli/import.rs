@@ -0,0 +1,152 @@
+use std::{
+    collections::HashSet,
+    io::{Error, ErrorKind},
+    path::PathBuf,
+};
+
+use ::clap::ArgMatches;
+
+use lib::{
+    entry::Entry,
+    import::{find_files, Filter},
+    tag::Tag,
+};
+
+use crate::{
+    app::App,
+    cli::report::{self, Failure},
+};
+
+/// Walks the directory provided and adds the files found in it, filtered by
+/// their extensions and exclusion patterns, along with the BibTeX files next
+/// to them. The files already on the bookshelf are skipped. With a dry run,
+/// only lists what would be added.
+pub(crate) fn import_entries(app: &mut App, matches: &ArgMatches) {
+    let root = PathBuf::from(matches.value_of_os("FILE").unwrap());
+    let extensions = matches
+        .values_of("ext")
+        .into_iter()
+        .flatten()
+        .collect::<Vec<&str>>();
+    let exclude = matches
+        .values_of("exclude")
+        .into_iter()
+        .flatten()
+        .collect::<Vec<&str>>();
+    let tags = matches
+        .values_of("tag")
+        .into_iter()
+        .flatten()
+        .map(Tag::new)
+        .collect::<Vec<Tag>>();
+    let dry_run = matches.is_present("dry-run");
+
+    if !root.is_dir() {
+        report::failure(
+            Failure::Invalid,
+            format!("Couldn't import '{}': not a directory", root.display()),
+        );
+        return;
+    }
+
+    let filter = Filter::new(&extensions, &exclude).unwrap();
+    let (found, mut failed) = find_files(&root, &filter);
+
+    let mut entries = vec![];
+
+    for found in found {
+        let path = match found.path.to_str() {
+            Some(path) => path,
+            None => {
+                let e = Error::new(ErrorKind::InvalidData, "Invalid UTF-8");
+                failed.push((found.path, e));
+                continue;
+            },
+        };
+
+        let mut entry = Entry::new(path);
+
+        if let Some(bib_path) = found.bib_path.as_ref().and_then(|p| p.to_str())
+        {
+            entry = entry.with_bib(bib_path);
+        }
+
+        if !tags.is_empty() {
+            entry = match entry.with_tags(&tags) {
+                Ok(entry) => entry,
+                Err(e) => {
+                    report::error(
+                        format!("Couldn't import '{}'", root.display()),
+                        e,
+                    );
+                    return;
+                },
+            };
+        }
+
+        entries.push(entry);
+    }
+
+    let (added, skipped) = match dry_run {
+        true => {
+            let mut seen = HashSet::new();
+
+            entries.into_iter().partition(|entry| {
+                !app.shelf.contains(entry) && seen.insert(entry.path.clone())
+            })
+        },
+        false => match app.add_entries(entries) {
+            Ok(result) => result,
+            Err(e) => {
+                report::error(
+                    format!("Couldn't import '{}'", root.display()),
+                    e,
+                );
+                return;
+            },
+        },
+    };
+
+    for entry in added.iter() {
+        let verb = match dry_run {
+            true => "Would add",
+            false => "Added",
+        };
+
+        let line = match &entry.bib_path {
+            Some(bib_path) => format!(
+                "{} '{}' with '{}'",
+                verb,
+                entry.path.display(),
+                bib_path.display()
+            ),
+            None => format!("{} '{}'", verb, entry.path.display()),
+        };
+
+        // The files a dry run would add are the output asked for
+        match dry_run {
+            true => println!("{}", line),
+            false => report::success(line),
+        }
+    }
+
+    let failures = failed.len();
+    for (path, e) in failed {
+        report::error(format!("Couldn't add '{}'", path.display()), e);
+    }
+
+    report::success(match dry_run {
+        true => format!(
+            "Would add {} entries, skip {} already on the bookshelf, {} failed",
+            added.len(),
+            skipped.len(),
+            failures
+        ),
+        false => format!(
+            "Added {} entries, skipped {} already on the bookshelf, {} failed",
+            added.len(),
+            skipped.len(),
+            failures
+        ),
+    });
+}
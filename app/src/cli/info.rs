@@ -0,0 +1,112 @@
+use std::{fs::metadata, path::Path};
+
+use ::clap::ArgMatches;
+
+use lib::entry::Entry;
+
+use crate::{
+    app::App,
+    cli::{report, select_entry},
+    utils::format::{
+        format_date, format_details, format_duration, format_entry,
+        format_size, wrap,
+    },
+};
+
+/// The width the review is wrapped to
+const REVIEW_WIDTH: usize = 76;
+
+/// Prints everything known about an entry: its details as in a verbose
+/// listing, its identifiers, its files, the dates it was added and last
+/// opened, its review and bookmarks, and the history of its reading
+/// sessions.
+pub(crate) fn show_info(app: &mut App, matches: &ArgMatches) {
+    let entry_index = match select_entry(app, matches) {
+        Some(entry_index) => entry_index,
+        None => return,
+    };
+
+    let entry = match app.shelf.get_index(entry_index) {
+        Ok(entry) => entry,
+        Err(e) => {
+            report::error(format!("Couldn't show entry {}", entry_index), e);
+            return;
+        },
+    };
+
+    println!("{}", format_entry(entry_index, entry));
+    println!("Title: {}", entry.title());
+
+    for line in format_details(entry) {
+        println!("{}", line);
+    }
+
+    if let Some(isbn) = entry.isbn() {
+        println!("ISBN: {}", isbn);
+    }
+
+    if let Some(doi) = entry.doi() {
+        println!("DOI: {}", doi);
+    }
+
+    println!("File: {}", describe_file(&entry.path));
+    for path in &entry.formats {
+        println!("Other format: {}, {}", path.display(), describe_file(path));
+    }
+
+    if let Some(added) = entry.added {
+        println!("Added: {}", format_date(added));
+    }
+
+    match entry.last_opened {
+        Some(last_opened) => {
+            println!("Last opened: {}", format_date(last_opened))
+        },
+        None => println!("Last opened: never"),
+    }
+
+    if let Some(review) = &entry.review {
+        println!("Review:");
+
+        for line in wrap(review, REVIEW_WIDTH) {
+            println!("    {}", line);
+        }
+    }
+
+    if !entry.bookmarks.is_empty() {
+        println!("Bookmarks: {}", entry.bookmarks.len());
+    }
+
+    show_sessions(entry);
+}
+
+/// Prints the time spent reading the entry, along with every reading
+/// session.
+fn show_sessions(entry: &Entry) {
+    println!(
+        "Time read: {} over {} sessions",
+        format_duration(entry.time_read()),
+        entry.sessions.len()
+    );
+
+    for session in &entry.sessions {
+        println!(
+            "    {}  {}",
+            format_date(session.started),
+            format_duration(session.duration)
+        );
+    }
+}
+
+/// Describes the format and the size of a file, e.g. `PDF, 1.5 MiB`.
+fn describe_file(path: &Path) -> String {
+    let format = path
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_uppercase())
+        .unwrap_or_else(|| "no extension".to_string());
+
+    match metadata(path) {
+        Ok(metadata) => format!("{}, {}", format, format_size(metadata.len())),
+        Err(e) => format!("{}, couldn't be read: {}", format, e),
+    }
+}
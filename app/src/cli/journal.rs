@@ -0,0 +1,69 @@
+use ::clap::ArgMatches;
+
+use crate::{
+    app::App,
+    cli::report::{self, Failure},
+    utils::format::format_date,
+};
+
+/// Undoes the last change to the shelf.
+pub(crate) fn undo_change(app: &mut App) {
+    match app.undo() {
+        Ok(Some(change)) => {
+            report::success(format!("Successfully undid: {}", change))
+        },
+        Ok(None) => {
+            report::failure(Failure::NotFound, "There is nothing to undo")
+        },
+        Err(e) => report::error("Couldn't undo the last change", e),
+    }
+}
+
+/// Redoes the last change undone.
+pub(crate) fn redo_change(app: &mut App) {
+    match app.redo() {
+        Ok(Some(change)) => {
+            report::success(format!("Successfully redid: {}", change))
+        },
+        Ok(None) => {
+            report::failure(Failure::NotFound, "There is nothing to redo")
+        },
+        Err(e) => report::error("Couldn't redo the last change", e),
+    }
+}
+
+/// Prints the recent changes to the shelf with their dates, the newest
+/// first. The changes undone, which can be redone, are listed above the
+/// rest.
+pub(crate) fn show_history(app: &mut App, matches: &ArgMatches) {
+    let journal = app.journal();
+
+    let number = matches
+        .value_of("number")
+        .map_or(usize::MAX, |n| n.parse().unwrap());
+
+    // The next change to redo is the oldest of the undone ones
+    let mut undone = journal.undone().collect::<Vec<_>>();
+    undone.reverse();
+
+    let changes = undone
+        .into_iter()
+        .map(|record| (record, " (undone)"))
+        .chain(journal.done().map(|record| (record, "")))
+        .take(number)
+        .collect::<Vec<_>>();
+
+    if changes.is_empty() {
+        report::success("There are no changes to the shelf yet");
+        return;
+    }
+
+    for (record, note) in changes {
+        println!(
+            "{}  {}{}",
+            format_date(record.date),
+            record.description,
+            note
+        );
+    }
+}
@@ -1,11 +1,81 @@
+/// BibTeX and citations of the entries
+mod bib;
 /// Clap-related commands and arguments for the CLI
 pub(crate) mod clap;
+/// Saved queries
+mod collection;
+/// Shell completions
+mod completions;
+/// Duplicate entries
+mod dedupe;
+/// Editing the entries by hand
+mod edit;
+/// Importing directory trees
+mod import;
+/// Detailed information about an entry
+mod info;
+/// Undoing and redoing the changes to the shelf
+mod journal;
+/// Exit codes and diagnostics
+pub(crate) mod report;
+/// Full-text search
+mod search;
+/// Series of entries
+mod series;
+/// Named shelves
+mod shelf;
+/// Speed-reading practice
+mod speed;
+/// Reading statistics
+mod stats;
+/// Tag management
+mod tag;
+
+use std::{
+    fs::write,
+    io::{stdin, stdout, Write},
+    path::PathBuf,
+};
 
 use ::clap::ArgMatches;
+use shellexpand::tilde;
 
-use lib::entry::Entry;
+use lib::{
+    entry::Entry,
+    organize::{organize, Layout},
+    rating::Rating,
+    selector::{Error as SelectError, Selector},
+    sort::{sort_by_keys, SortKey},
+    status::Status,
+    tag::Tag,
+};
 
-use crate::{app::App, utils::format::format_entry};
+use crate::{
+    app::App,
+    cli::{
+        bib::show_bib,
+        collection::match_collection_subcommand,
+        completions::{complete_values, print_completions},
+        dedupe::dedupe_entries,
+        edit::edit_entry,
+        import::import_entries,
+        info::show_info,
+        journal::{redo_change, show_history, undo_change},
+        report::Failure,
+        search::search_entries,
+        series::{list_series, match_series_subcommand, suggest_next},
+        shelf::match_shelf_subcommand,
+        speed::speed_test,
+        stats::show_stats,
+        tag::match_tag_subcommand,
+    },
+    utils::{
+        export::to_bib,
+        format::{format_details, format_duration, format_entry},
+        output::{format_entries, Field, Format},
+    },
+    viewer::MIN_SESSION,
+};
 
 pub(crate) fn match_subcommand(app: &mut App, matches: &ArgMatches) {
     match matches.subcommand() {
@@ -14,6 +84,30 @@ pub(crate) fn match_subcommand(app: &mut App, matches: &ArgMatches) {
             ("remove", matches) => remove_entry(app, matches),
             ("list", matches) => list_entries(app, matches),
             ("open", matches) => open_entry(app, matches),
+            ("rate", matches) => rate_entry(app, matches),
+            ("status", matches) => set_status(app, matches),
+            ("export", matches) => export_entries(app, matches),
+            ("bib", matches) => show_bib(app, matches),
+            ("speed", matches) => speed_test(app, matches),
+            ("stats", matches) => show_stats(app, matches),
+            ("tag", matches) => match_tag_subcommand(app, matches),
+            ("shelf", matches) => match_shelf_subcommand(app, matches),
+            ("collection", matches) => {
+                match_collection_subcommand(app, matches)
+            },
+            ("organize", matches) => organize_entries(app, matches),
+            ("search", matches) => search_entries(app, matches),
+            ("dedupe", matches) => dedupe_entries(app, matches),
+            ("series", matches) => match_series_subcommand(app, matches),
+            ("edit", matches) => edit_entry(app, matches),
+            ("info", matches) => show_info(app, matches),
+            ("undo", _) => undo_change(app),
+            ("redo", _) => redo_change(app),
+            ("history", matches) => show_history(app, matches),
+            ("completions", matches) => print_completions(app, matches),
+            ("complete-values", matches) => complete_values(app, matches),
+            ("move", matches) => transfer_entries(app, matches, false),
+            ("copy", matches) => transfer_entries(app, matches, true),
             (_, &_) => panic!("The clap app should have handled this"),
         },
         None => panic!("Should not be here - TUI has to be run instead"),
@@ -21,8 +115,14 @@ pub(crate) fn match_subcommand(app: &mut App, matches: &ArgMatches) {
 }
 
 // Extracts a file path from the matches provided, constructs an entry from
-// it and adds it to the bookshelf.
+// it and adds it to the bookshelf. A directory is imported as a whole when
+// walked recursively.
 pub(crate) fn add_entry(app: &mut App, matches: &ArgMatches) {
+    if matches.is_present("recursive") {
+        import_entries(app, matches);
+        return;
+    }
+
     let mut entry =
         Entry::new(matches.value_of_os("FILE").unwrap().to_str().unwrap());
 
@@ -30,6 +130,19 @@ pub(crate) fn add_entry(app: &mut App, matches: &ArgMatches) {
         entry = entry.with_bib(bib_path.to_str().unwrap());
     }
 
+    if let Some(tags) = matches.values_of("tag") {
+        let tags = tags.map(Tag::new).collect::<Vec<Tag>>();
+        let path = entry.path.clone();
+
+        entry = match entry.with_tags(&tags) {
+            Ok(entry) => entry,
+            Err(e) => {
+                report::error(format!("Couldn't add '{}'", path.display()), e);
+                return;
+            },
+        };
+    }
+
     // New entry index
     let entry_index = app.size() + 1;
 
@@ -37,41 +150,248 @@ pub(crate) fn add_entry(app: &mut App, matches: &ArgMatches) {
 
     match app.add_entry(&entry) {
         Ok(()) => {
-            println!("Successfully added '{}'", entry_name)
+            report::success(format!("Successfully added '{}'", entry_name))
+        },
+        Err(e) => report::error(format!("Couldn't add '{}'", entry_name), e),
+    }
+}
+
+/// Extracts a selector from the matches provided, and removes the entries
+/// it selects from the bookshelf, after a confirmation if there are several.
+pub(crate) fn remove_entry(app: &mut App, matches: &ArgMatches) {
+    let indices = match select_entries(app, matches, Some("Remove")) {
+        Some(indices) => indices,
+        None => return,
+    };
+
+    let entry_name = match indices.as_slice() {
+        &[entry_index] => {
+            format_entry(entry_index, app.shelf.get_index(entry_index).unwrap())
         },
+        _ => format!("{} entries", indices.len()),
+    };
+
+    match app.remove_entries_indices(&indices) {
+        Ok(()) => {
+            report::success(format!("Successfully removed '{}'", entry_name))
+        },
+        Err(e) => report::error(format!("Couldn't remove '{}'", entry_name), e),
+    }
+}
+
+/// Extracts a selector from the matches provided, and opens the entries it
+/// selects, one after another, via platform-specific default program, or
+/// a program specified by the user. If reading sessions are tracked, waits
+/// for the program to exit and records the session of every entry.
+pub(crate) fn open_entry(app: &mut App, matches: &ArgMatches) {
+    let indices = match select_entries(app, matches, Some("Open")) {
+        Some(indices) => indices,
+        None => return,
+    };
+
+    let exe = matches
+        .value_of_os("exec")
+        .map(|os_str| String::from(os_str.to_str().unwrap()));
+    let track = matches.is_present("track") || app.config.track_sessions();
+
+    for entry_index in indices {
+        open_entry_index(app, entry_index, exe.clone(), track);
+    }
+}
+
+/// Opens the entry with a corresponding index, and tracks the reading
+/// session if asked to.
+fn open_entry_index(
+    app: &mut App,
+    entry_index: usize,
+    exe: Option<String>,
+    track: bool,
+) {
+    let entry_name = match app.shelf.get_index(entry_index) {
+        Ok(entry) => format_entry(entry_index, entry),
         Err(e) => {
-            println!("Couldn't add '{}': {}", entry_name, e)
+            report::error(format!("Couldn't open entry {}", entry_index), e);
+            return;
+        },
+    };
+
+    if track {
+        return track_entry(app, entry_index, &entry_name, exe);
+    }
+
+    match app.open_entry_index(entry_index, exe.clone()) {
+        Ok(()) if exe.is_some() => report::success(format!(
+            "Successfully opened '{}' in {}",
+            entry_name,
+            exe.unwrap()
+        )),
+        Ok(()) => {
+            report::success(format!("Successfully opened '{}'", entry_name))
         },
+        Err(_) if exe.is_some() => report::failure(
+            Failure::NotFound,
+            format!(
+                "Couldn't open '{}': {} not found",
+                entry_name,
+                exe.unwrap()
+            ),
+        ),
+        Err(e) => report::error(format!("Couldn't open '{}'", entry_name), e),
     }
 }
 
-/// Extracts an entry index from the matches provided, and removes the
-/// corresponding entry from the bookshelf, if it exists.
-pub(crate) fn remove_entry(app: &mut App, matches: &ArgMatches) {
-    let entry_index =
-        matches.value_of("INDEX").unwrap().parse::<usize>().unwrap();
+/// Opens an entry and blocks until the viewer exits, then records the
+/// reading session for the entry.
+fn track_entry(
+    app: &mut App,
+    entry_index: usize,
+    entry_name: &str,
+    exe: Option<String>,
+) {
+    let viewer = match app.spawn_viewer_index(entry_index, exe) {
+        Ok(viewer) => viewer,
+        Err(e) => {
+            report::error(format!("Couldn't open '{}'", entry_name), e);
+            return;
+        },
+    };
 
-    let entry = app.shelf.get_index(entry_index);
+    report::success(format!(
+        "Reading '{}', the session ends with the viewer",
+        entry_name
+    ));
 
-    match entry {
-        Ok(entry) => {
-            let entry_name = format_entry(entry_index, entry);
+    let (entry, session) = match viewer.wait() {
+        Ok(session) => session,
+        Err(e) => {
+            report::error("Couldn't time the reading session", e);
+            return;
+        },
+    };
+
+    let duration = format_duration(session.duration);
 
-            match app.remove_entry_index(entry_index) {
-                Ok(()) => println!("Successfully removed '{}''", entry_name),
-                Err(e) => println!("Couldn't remove '{}': {}", entry_name, e),
+    if session.duration < MIN_SESSION {
+        report::warning(format!(
+            "The viewer exited after {}, most likely handing '{}' off to \
+             another program, so the session wasn't recorded. Use --exe \
+             with a program which stays open while reading",
+            duration, entry_name
+        ));
+        return;
+    }
+
+    match app.record_session(&entry, session) {
+        Ok(()) => report::success(format!(
+            "Recorded a reading session of {}",
+            duration
+        )),
+        Err(e) => report::error("Couldn't record the reading session", e),
+    }
+}
+
+/// Simply lists all of the entries on the bookshelf. The output format
+/// depends on whether the entry has a bibliography file associated with
+/// it or not. The entries can be filtered by their rating, tag or a query,
+/// and sorted.
+pub(crate) fn list_entries(app: &mut App, matches: &ArgMatches) {
+    let mut entries = match filter_entries(app, matches) {
+        Some(entries) => entries,
+        None => return,
+    };
+
+    if let Some(min_rating) = matches.value_of("min-rating") {
+        let min_rating = min_rating.parse::<Rating>().unwrap();
+
+        entries.retain(|(_, entry)| {
+            matches!(entry.rating, Some(rating) if rating >= min_rating)
+        });
+    }
+
+    if let Some(tag) = matches.value_of("tag") {
+        let tag = Tag::new(tag);
+
+        entries.retain(|(_, entry)| entry.has_tag(&tag));
+    }
+
+    if let Some(keys) = matches.value_of("sort") {
+        let keys = SortKey::parse_list(keys).unwrap();
+
+        sort_by_keys(&mut entries, &keys, |(_, entry)| entry);
+    }
+
+    if matches.is_present("series") {
+        list_series(entries);
+        return;
+    }
+
+    if let Some((format, fields)) = output_options(matches) {
+        print_entries(&entries, format, &fields);
+        return;
+    }
+
+    let verbose = matches.is_present("verbose");
+
+    for (i, entry) in entries {
+        let entry_name = format_entry(i + 1, entry);
+        println!("{}", entry_name);
+
+        if verbose {
+            for line in format_details(entry) {
+                println!("    {}", line);
             }
-        },
-        Err(e) => println!("Couldn't remove entry {}: {}", entry_index, e),
+        }
     }
 }
 
-/// Extracts an entry index from the matches provided, and opens the
-/// corresponding entry via platform-specific default program, or a program
-/// specified by the user, if the entry exists.
-pub(crate) fn open_entry(app: &mut App, matches: &ArgMatches) {
-    let entry_index =
-        matches.value_of("INDEX").unwrap().parse::<usize>().unwrap();
+/// Extracts the output format and the fields of the entries from the
+/// matches provided, if either of them is set. The entries are printed as
+/// a table of the default fields unless set otherwise.
+pub(crate) fn output_options(
+    matches: &ArgMatches,
+) -> Option<(Format, Vec<Field>)> {
+    if !matches.is_present("format") && !matches.is_present("fields") {
+        return None;
+    }
+
+    let format = match matches.value_of("format") {
+        Some(format) => format.parse::<Format>().unwrap(),
+        None => Format::Table,
+    };
+    let fields = match matches.value_of("fields") {
+        Some(fields) => Field::parse_list(fields).unwrap(),
+        None => Field::DEFAULT.to_vec(),
+    };
+
+    Some((format, fields))
+}
+
+/// Prints the fields of the entries provided, along with their **0-based**
+/// indices, in the format provided.
+pub(crate) fn print_entries(
+    entries: &[(usize, &Entry)],
+    format: Format,
+    fields: &[Field],
+) {
+    match format_entries(entries, format, fields) {
+        Ok(output) => print!("{}", output),
+        Err(e) => report::error("Couldn't print the entries", e),
+    }
+}
+
+/// Extracts an entry index, a rating and an optional review from the
+/// matches provided, and rates the corresponding entry, if it exists.
+pub(crate) fn rate_entry(app: &mut App, matches: &ArgMatches) {
+    let entry_index = match select_entry(app, matches) {
+        Some(entry_index) => entry_index,
+        None => return,
+    };
+    let rating = matches
+        .value_of("RATING")
+        .unwrap()
+        .parse::<Rating>()
+        .unwrap();
+    let review = matches.value_of("review").map(String::from);
 
     let entry = app.shelf.get_index(entry_index);
 
@@ -79,40 +399,393 @@ pub(crate) fn open_entry(app: &mut App, matches: &ArgMatches) {
         Ok(entry) => {
             let entry_name = format_entry(entry_index, entry);
 
-            let exe = matches
-                .value_of_os("exec")
-                .map(|os_str| String::from(os_str.to_str().unwrap()));
-
-            match app.open_entry(entry, exe.clone()) {
-                Ok(()) if exe.is_some() => println!(
-                    "Successfully opened '{}' in {}",
+            match app.rate_entry_index(entry_index, rating, review) {
+                Ok(()) => report::success(format!(
+                    "Successfully rated '{}' {}",
                     entry_name,
-                    exe.unwrap()
-                ),
-                Ok(()) => println!("Successfully opened '{}'", entry_name),
-                Err(_) if exe.is_some() => {
-                    println!(
-                        "Couldn't open '{}': {} not found",
-                        entry_name,
-                        exe.unwrap()
-                    )
-                },
+                    rating.to_star_string()
+                )),
                 Err(e) => {
-                    println!("Couldn't open '{}': {}", entry_name, e)
+                    report::error(format!("Couldn't rate '{}'", entry_name), e)
                 },
             }
         },
-        Err(e) => println!("Couldn't open entry {}: {}", entry_index, e),
+        Err(e) => {
+            report::error(format!("Couldn't rate entry {}", entry_index), e)
+        },
     }
 }
 
-/// Simply lists all of the entries on the bookshelf. The output format
-/// depends on whether the entry has a bibliography file associated with
-/// it or not.
-pub(crate) fn list_entries(app: &mut App, _matches: &ArgMatches) {
-    // TODO: parse matches for output options
-    for (i, entry) in app.list_entries() {
-        let entry_name = format_entry(i + 1, entry);
-        println!("{}", entry_name);
+/// Extracts a reading status, and either an entry index or a query from the
+/// matches provided, and sets the status of the corresponding entry, or of
+/// all of the entries matching the query.
+pub(crate) fn set_status(app: &mut App, matches: &ArgMatches) {
+    let status = matches
+        .value_of("STATUS")
+        .unwrap()
+        .parse::<Status>()
+        .unwrap();
+
+    let indices = match matches.value_of("SELECTOR") {
+        Some(_) => match select_entries(app, matches, Some("Mark")) {
+            Some(indices) => indices,
+            None => return,
+        },
+        None => match filter_entries(app, matches) {
+            Some(entries) => entries.into_iter().map(|(i, _)| i + 1).collect(),
+            None => return,
+        },
+    };
+
+    if indices.is_empty() {
+        report::failure(Failure::NotFound, "No entries match the filter");
+        return;
     }
+
+    match app.set_status_indices(&indices, status) {
+        Ok(()) if indices.len() == 1 => {
+            let entry = app.shelf.get_index(indices[0]).unwrap();
+            report::success(format!(
+                "Successfully marked '{}' as {}",
+                format_entry(indices[0], entry),
+                status
+            ));
+
+            if status == Status::Finished {
+                suggest_next(app, indices[0]);
+            }
+        },
+        Ok(()) => report::success(format!(
+            "Successfully marked {} entries as {}",
+            indices.len(),
+            status
+        )),
+        Err(e) => report::error("Couldn't set the status", e),
+    }
+}
+
+/// Extracts a shelf name, and either an entry index or a query from the
+/// matches provided, and moves or copies the corresponding entry, or all of
+/// the entries matching the query, onto the named shelf.
+pub(crate) fn transfer_entries(
+    app: &mut App,
+    matches: &ArgMatches,
+    keep: bool,
+) {
+    let shelf = matches.value_of("SHELF").unwrap();
+    let (action, verb) = match keep {
+        true => ("Copy", "copied"),
+        false => ("Move", "moved"),
+    };
+
+    let indices = match matches.value_of("SELECTOR") {
+        Some(_) => match select_entries(app, matches, Some(action)) {
+            Some(indices) => indices,
+            None => return,
+        },
+        None => match filter_entries(app, matches) {
+            Some(entries) => entries.into_iter().map(|(i, _)| i + 1).collect(),
+            None => return,
+        },
+    };
+
+    if indices.is_empty() {
+        report::failure(Failure::NotFound, "No entries match the filter");
+        return;
+    }
+
+    let entry_name = match app.shelf.get_index(indices[0]) {
+        Ok(entry) if indices.len() == 1 => format_entry(indices[0], entry),
+        _ => format!("{} entries", indices.len()),
+    };
+
+    match app.transfer_entries_indices(&indices, shelf, keep) {
+        Ok(()) => report::success(format!(
+            "Successfully {} '{}' to '{}'",
+            verb, entry_name, shelf
+        )),
+        Err(e) => {
+            report::error(format!("Couldn't transfer '{}'", entry_name), e)
+        },
+    }
+}
+
+/// Builds a directory tree of links to the files of the entries, possibly
+/// filtered by a query, in the directory and the layout provided.
+pub(crate) fn organize_entries(app: &mut App, matches: &ArgMatches) {
+    let root = PathBuf::from(
+        tilde(matches.value_of_os("into").unwrap().to_str().unwrap())
+            .into_owned(),
+    );
+    let layout = matches.value_of("by").unwrap().parse::<Layout>().unwrap();
+
+    let entries = match filter_entries(app, matches) {
+        Some(entries) => entries.into_iter().map(|(_, entry)| entry),
+        None => return,
+    };
+
+    let organized = match organize(entries, &root, layout) {
+        Ok(organized) => organized,
+        Err(e) => {
+            report::error("Couldn't organize the entries", e);
+            return;
+        },
+    };
+
+    report::success(format!(
+        "Organized the entries by {} in '{}': {} linked, {} unchanged, {} \
+        pruned",
+        layout,
+        root.display(),
+        organized.linked,
+        organized.unchanged,
+        organized.pruned
+    ));
+
+    if organized.skipped > 0 {
+        report::success(format!(
+            "Skipped {} entries with no {}",
+            organized.skipped, layout
+        ));
+    }
+
+    for conflict in organized.conflicts {
+        report::failure(
+            Failure::Duplicate,
+            format!("Couldn't link '{}': the file exists", conflict.display()),
+        );
+    }
+}
+
+/// Exports the entries on the bookshelf in the format provided, either to
+/// the standard output or to a file. The entries can be filtered by a query.
+pub(crate) fn export_entries(app: &mut App, matches: &ArgMatches) {
+    let entries = match filter_entries(app, matches) {
+        Some(entries) => entries,
+        None => return,
+    };
+
+    // JSON and CSV are the same as listing every field, so that there is
+    // only one schema to rely on
+    let export = match matches.value_of("FORMAT").unwrap() {
+        "json" => format_entries(&entries, Format::Json, &Field::ALL),
+        "csv" => format_entries(&entries, Format::Csv, &Field::ALL),
+        "bib" => {
+            let entries = entries
+                .iter()
+                .map(|(_, entry)| *entry)
+                .collect::<Vec<&Entry>>();
+            let (bib, failed) = to_bib(&entries);
+
+            for (entry, e) in failed {
+                report::warning(format!(
+                    "Exported '{}' as @misc, its BibTeX couldn't be read: {}",
+                    entry.path.display(),
+                    e
+                ));
+            }
+
+            Ok(bib)
+        },
+        _ => panic!("The clap app should have handled this"),
+    };
+
+    let export = match export {
+        Ok(export) => export,
+        Err(e) => {
+            report::error("Couldn't export the entries", e);
+            return;
+        },
+    };
+
+    match matches.value_of_os("output").map(PathBuf::from) {
+        Some(path) => match write(&path, export) {
+            Ok(()) => report::success(format!(
+                "Successfully exported {} entries to '{}'",
+                entries.len(),
+                path.display()
+            )),
+            Err(e) => report::error("Couldn't export the entries", e),
+        },
+        None => print!("{}", export),
+    }
+}
+
+/// Lists the entries on the bookshelf, keeping only the ones matching the
+/// query of the `--filter` option, if it is present. The query may refer to
+/// the saved collections. A malformed query is reported with the offending
+/// part underlined, and None is returned.
+pub(crate) fn filter_entries<'a>(
+    app: &'a App,
+    matches: &ArgMatches,
+) -> Option<Vec<(usize, &'a Entry)>> {
+    let mut entries = app.list_entries();
+
+    if let Some(query) = matches.value_of("filter") {
+        let parsed = match app.shelf.parse_query(query) {
+            Ok(parsed) => parsed,
+            Err(e) => {
+                report::failure(
+                    Failure::Invalid,
+                    format!(
+                        "Invalid filter: {}\n\n    {}",
+                        e,
+                        e.underline(query).replace('\n', "\n    ")
+                    ),
+                );
+                return None;
+            },
+        };
+
+        entries.retain(|(_, entry)| parsed.matches(entry));
+    }
+
+    Some(entries)
+}
+
+/// Resolves the `SELECTOR` argument to a single entry. The selector is
+/// either a list, a query or a fuzzy search (see [`Selector`]); the user is
+/// asked to choose one of the entries if a fuzzy search matches several.
+/// Prints what went wrong and returns None if no entry is chosen, or if
+/// the selector selects several entries.
+pub(crate) fn select_entry(app: &App, matches: &ArgMatches) -> Option<usize> {
+    let pattern = matches.value_of("SELECTOR").unwrap();
+
+    match select_indices(app, pattern)? {
+        (_, indices) if indices.len() == 1 => Some(indices[0]),
+        (Selector::Fuzzy(_), indices) => choose_entry(app, pattern, &indices),
+        (_, indices) => {
+            report::failure(
+                Failure::Invalid,
+                format!(
+                    "'{}' selects {} entries, but only one can be chosen here",
+                    pattern,
+                    indices.len()
+                ),
+            );
+            None
+        },
+    }
+}
+
+/// Resolves the `SELECTOR` argument to the entries it selects (see
+/// [`Selector`]); the user is asked to choose one of the entries if a fuzzy
+/// search matches several. Before doing the action named to several
+/// entries, the user is asked to confirm it unless `--yes` is present.
+/// Prints what went wrong and returns None if no entries are chosen.
+pub(crate) fn select_entries(
+    app: &App,
+    matches: &ArgMatches,
+    action: Option<&str>,
+) -> Option<Vec<usize>> {
+    let pattern = matches.value_of("SELECTOR").unwrap();
+
+    let indices = match select_indices(app, pattern)? {
+        (Selector::Fuzzy(_), indices) if indices.len() > 1 => {
+            vec![choose_entry(app, pattern, &indices)?]
+        },
+        (_, indices) => indices,
+    };
+
+    match action {
+        Some(action) if indices.len() > 1 && !matches.is_present("yes") => {
+            match confirm(app, action, &indices) {
+                true => Some(indices),
+                false => None,
+            }
+        },
+        _ => Some(indices),
+    }
+}
+
+/// Parses the selector and returns the indices of the entries it selects,
+/// of which there is at least one. Prints what went wrong and returns None
+/// otherwise.
+fn select_indices(app: &App, pattern: &str) -> Option<(Selector, Vec<usize>)> {
+    let selector = match app.shelf.parse_selector(pattern) {
+        Ok(selector) => selector,
+        Err(SelectError::Parse(e)) => {
+            report::failure(
+                Failure::Invalid,
+                format!(
+                    "Invalid selector: {}\n\n    {}",
+                    e,
+                    e.underline(pattern).replace('\n', "\n    ")
+                ),
+            );
+            return None;
+        },
+        Err(e) => {
+            report::failure(Failure::of(&e), e);
+            return None;
+        },
+    };
+
+    match selector.select(&app.shelf.entries) {
+        Ok(indices) if indices.is_empty() => {
+            report::failure(
+                Failure::NotFound,
+                format!("No entries match '{}'", pattern),
+            );
+            None
+        },
+        Ok(indices) => Some((selector, indices)),
+        Err(e) => {
+            report::failure(Failure::of(&e), e);
+            None
+        },
+    }
+}
+
+/// Asks the user to choose one of the entries matching a fuzzy search, the
+/// best matches first. Returns None if none is chosen.
+fn choose_entry(app: &App, pattern: &str, indices: &[usize]) -> Option<usize> {
+    /// The number of the best matching entries to choose from
+    const CHOICES: usize = 9;
+
+    println!("Several entries match '{}':", pattern);
+    for (choice, &entry_index) in indices.iter().take(CHOICES).enumerate() {
+        let entry = app.shelf.get_index(entry_index).ok()?;
+        println!("  {}) {}", choice + 1, format_entry(entry_index, entry));
+    }
+
+    let choices = indices.len().min(CHOICES);
+    print!("Which one? [1-{}] ", choices);
+    stdout().flush().ok()?;
+
+    let mut answer = String::new();
+    stdin().read_line(&mut answer).ok()?;
+
+    match answer.trim().parse::<usize>() {
+        Ok(choice) if (1..=choices).contains(&choice) => {
+            Some(indices[choice - 1])
+        },
+        _ => {
+            report::success("No entry chosen");
+            None
+        },
+    }
+}
+
+/// Lists the entries about to be changed, and asks the user to confirm the
+/// action named, e.g. `Remove`. Returns whether they did.
+fn confirm(app: &App, action: &str, indices: &[usize]) -> bool {
+    for &entry_index in indices {
+        if let Ok(entry) = app.shelf.get_index(entry_index) {
+            println!("  {}", format_entry(entry_index, entry));
+        }
+    }
+
+    print!("{} {} entries? [y/N] ", action, indices.len());
+    let mut answer = String::new();
+
+    let confirmed = stdout().flush().is_ok()
+        && stdin().read_line(&mut answer).is_ok()
+        && matches!(answer.trim().to_lowercase().as_str(), "y" | "yes");
+
+    if !confirmed {
+        report::success("Nothing changed");
+    }
+
+    confirmed
 }
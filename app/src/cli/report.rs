@@ -0,0 +1,230 @@
+use std::{
+    error::Error,
+    fmt::Display,
+    io::ErrorKind,
+    sync::atomic::{AtomicBool, AtomicI32, Ordering},
+};
+
+use config::ConfigError;
+use serde_json::json;
+
+use lib::{
+    document, edit, entry, import, journal, query::ParseError, search,
+    selector, shelf,
+};
+
+use crate::app::NoSuchShelf;
+
+/// Whether the messages about what the commands did are left out
+static QUIET: AtomicBool = AtomicBool::new(false);
+/// Whether the failures are printed as JSON objects
+static JSON: AtomicBool = AtomicBool::new(false);
+/// The code the program exits with, set by the first failure
+static EXIT_CODE: AtomicI32 = AtomicI32::new(0);
+
+/// The kinds of failures, each with its own exit code. The code 2 is left
+/// to clap, which exits with it when the arguments are invalid.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) enum Failure {
+    /// Anything not covered by the other kinds
+    Other,
+    /// An entry, a tag, a collection or a shelf doesn't exist
+    NotFound,
+    /// An entry, a tag or a shelf already exists
+    Duplicate,
+    /// A query, a selector, a field or another input is invalid
+    Invalid,
+    /// Reading or writing a file failed
+    Io,
+}
+
+impl Failure {
+    /// Returns the code the program exits with.
+    pub(crate) fn code(self) -> i32 {
+        match self {
+            Failure::Other => 1,
+            Failure::NotFound => 3,
+            Failure::Duplicate => 4,
+            Failure::Invalid => 5,
+            Failure::Io => 6,
+        }
+    }
+
+    /// Classifies an error by its type. The errors of unknown types are
+    /// [`Failure::Other`].
+    pub(crate) fn of(error: &(dyn Error + 'static)) -> Self {
+        if let Some(e) = error.downcast_ref::<shelf::Error>() {
+            return match e {
+                shelf::Error::NoSuchEntry
+                | shelf::Error::NoSuchTag
+                | shelf::Error::NoSuchCollection => Failure::NotFound,
+                shelf::Error::DuplicateEntry | shelf::Error::TagExists => {
+                    Failure::Duplicate
+                },
+                shelf::Error::InvalidTags(_)
+                | shelf::Error::TagIntoSubtag
+                | shelf::Error::InvalidCollectionName
+                | shelf::Error::InvalidQuery(_) => Failure::Invalid,
+                shelf::Error::Write
+                | shelf::Error::Read
+                | shelf::Error::UnknownVersion(_) => Failure::Io,
+            };
+        }
+
+        if let Some(e) = error.downcast_ref::<selector::Error>() {
+            return match e {
+                selector::Error::NoSuchIndex(_)
+                | selector::Error::NoSuchId(_)
+                | selector::Error::NoEntries => Failure::NotFound,
+                selector::Error::Parse(_)
+                | selector::Error::InvalidRange(..)
+                | selector::Error::AmbiguousId(..) => Failure::Invalid,
+            };
+        }
+
+        if let Some(e) = error.downcast_ref::<edit::Error>() {
+            return match e {
+                edit::Error::NoSuchFile(_) => Failure::NotFound,
+                _ => Failure::Invalid,
+            };
+        }
+
+        if let Some(e) = error.downcast_ref::<document::Error>() {
+            return match e {
+                document::Error::Read => Failure::Io,
+                _ => Failure::Invalid,
+            };
+        }
+
+        if let Some(e) = error.downcast_ref::<std::io::Error>() {
+            return match e.kind() {
+                ErrorKind::NotFound => Failure::NotFound,
+                ErrorKind::AlreadyExists => Failure::Duplicate,
+                _ => Failure::Io,
+            };
+        }
+
+        if let Some(e) = error.downcast_ref::<ConfigError>() {
+            return match e {
+                ConfigError::Foreign(e) => Failure::of(&**e),
+                ConfigError::Message(_) => Failure::Other,
+                _ => Failure::Io,
+            };
+        }
+
+        if error.is::<NoSuchShelf>() {
+            return Failure::NotFound;
+        }
+
+        let is_invalid = error.is::<entry::Error>()
+            || error.is::<import::Error>()
+            || error.is::<ParseError>();
+        let is_io = error.is::<journal::Error>() || error.is::<search::Error>();
+
+        match (is_invalid, is_io) {
+            (true, _) => Failure::Invalid,
+            (_, true) => Failure::Io,
+            _ => Failure::Other,
+        }
+    }
+}
+
+impl Display for Failure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Failure::Other => write!(f, "other"),
+            Failure::NotFound => write!(f, "not_found"),
+            Failure::Duplicate => write!(f, "duplicate"),
+            Failure::Invalid => write!(f, "invalid"),
+            Failure::Io => write!(f, "io"),
+        }
+    }
+}
+
+/// Sets whether the messages about what the commands did are left out, and
+/// whether the failures are printed as JSON objects.
+pub(crate) fn set_mode(quiet: bool, json: bool) {
+    QUIET.store(quiet, Ordering::Relaxed);
+    JSON.store(json, Ordering::Relaxed);
+}
+
+/// Prints a message about what a command did, unless `--quiet` is present.
+pub(crate) fn success(message: impl Display) {
+    if !QUIET.load(Ordering::Relaxed) {
+        println!("{}", message);
+    }
+}
+
+/// Reports a failure caused by an error, classified by its type (see
+/// [`Failure::of`]), with the message followed by the error.
+pub(crate) fn error(message: impl Display, error: impl Into<Box<dyn Error>>) {
+    let error = error.into();
+
+    failure(Failure::of(&*error), format!("{}: {}", message, error));
+}
+
+/// Reports a failure on stderr, as a JSON object with `--json`, and sets
+/// the exit code unless another failure has set it already.
+pub(crate) fn failure(kind: Failure, message: impl Display) {
+    let _ = EXIT_CODE.compare_exchange(
+        0,
+        kind.code(),
+        Ordering::Relaxed,
+        Ordering::Relaxed,
+    );
+
+    match JSON.load(Ordering::Relaxed) {
+        true => eprintln!(
+            "{}",
+            json!({
+                "error": kind.to_string(),
+                "code": kind.code(),
+                "message": message.to_string(),
+            })
+        ),
+        false => eprintln!("{}", message),
+    }
+}
+
+/// Reports something which went wrong on stderr, as a JSON object with
+/// `--json`, without failing the command.
+pub(crate) fn warning(message: impl Display) {
+    match JSON.load(Ordering::Relaxed) {
+        true => eprintln!("{}", json!({ "warning": message.to_string() })),
+        false => eprintln!("Warning: {}", message),
+    }
+}
+
+/// Returns the code the program is to exit with: 0 if nothing failed, or
+/// the code of the first failure.
+pub(crate) fn exit_code() -> i32 {
+    EXIT_CODE.load(Ordering::Relaxed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classified_errors() {
+        let of = |error: Box<dyn Error>| Failure::of(&*error);
+
+        assert_eq!(of(shelf::Error::NoSuchTag.into()), Failure::NotFound);
+        assert_eq!(of(shelf::Error::DuplicateEntry.into()), Failure::Duplicate);
+        assert_eq!(
+            of(selector::Error::InvalidRange(3, 2).into()),
+            Failure::Invalid
+        );
+        assert_eq!(of(journal::Error::Write.into()), Failure::Io);
+        assert_eq!(
+            of(std::io::Error::from(ErrorKind::PermissionDenied).into()),
+            Failure::Io
+        );
+        assert_eq!(
+            of(ConfigError::NotFound("db".to_string()).into()),
+            Failure::Io
+        );
+        assert_eq!(of("Something went wrong".into()), Failure::Other);
+        assert_eq!(Failure::Invalid.code(), 5);
+    }
+}
@@ -0,0 +1,100 @@
+use std::io::stdout;
+
+use ::clap::ArgMatches;
+use crossterm::{style::Stylize, tty::IsTty};
+
+use lib::{entry::Entry, search::Hit};
+
+use crate::{
+    app::App,
+    cli::{filter_entries, output_options, print_entries, report},
+    utils::format::format_entry,
+};
+
+/// Searches the text of the files of the entries, possibly filtered by
+/// a query, for a phrase. Lists the entries with the most matches first,
+/// each with a few of the matches: where they are in the file, along with
+/// the text around them.
+pub(crate) fn search_entries(app: &mut App, matches: &ArgMatches) {
+    let phrase = matches.value_of("PHRASE").unwrap();
+    let shown = matches
+        .value_of("matches")
+        .unwrap()
+        .parse::<usize>()
+        .unwrap();
+
+    let entries = match filter_entries(app, matches) {
+        Some(entries) => entries,
+        None => return,
+    };
+
+    let (hits, update) = match app.search(phrase, matches.is_present("reindex"))
+    {
+        Ok(found) => found,
+        Err(e) => {
+            report::error("Couldn't search the entries", e);
+            return;
+        },
+    };
+
+    // The files which couldn't be indexed don't fail the search itself
+    for (path, e) in update.failed {
+        report::warning(format!("Couldn't index '{}': {}", path.display(), e));
+    }
+
+    let mut results = entries
+        .into_iter()
+        .map(|(i, entry)| {
+            let hits = hits
+                .iter()
+                .filter(|hit| hit.path == entry.path)
+                .collect::<Vec<&Hit>>();
+
+            (i, entry, hits)
+        })
+        .filter(|(_, _, hits)| !hits.is_empty())
+        .collect::<Vec<(usize, &Entry, Vec<&Hit>)>>();
+
+    // The entries with the most matches first, in the order of the shelf
+    results.sort_by_key(|(_, _, hits)| std::cmp::Reverse(hits.len()));
+
+    // Scripts get the entries found alone, without the matches
+    if let Some((format, fields)) = output_options(matches) {
+        let entries = results
+            .into_iter()
+            .map(|(i, entry, _)| (i, entry))
+            .collect::<Vec<(usize, &Entry)>>();
+
+        print_entries(&entries, format, &fields);
+        return;
+    }
+
+    if results.is_empty() {
+        report::success(format!("No entries contain '{}'", phrase));
+        return;
+    }
+
+    let highlight = stdout().is_tty();
+
+    for (i, entry, hits) in results {
+        println!("{}", format_entry(i + 1, entry));
+
+        for hit in hits.iter().take(shown) {
+            let matched = match highlight {
+                true => {
+                    hit.snippet.matched.as_str().bold().yellow().to_string()
+                },
+                false => hit.snippet.matched.clone(),
+            };
+
+            println!(
+                "    {}: {}{}{}",
+                hit.location, hit.snippet.before, matched, hit.snippet.after
+            );
+        }
+
+        if hits.len() > shown {
+            println!("    and {} more matches", hits.len() - shown);
+        }
+    }
+}
@@ -0,0 +1,165 @@
+use ::clap::ArgMatches;
+
+use lib::{
+    entry::Entry,
+    series::{group, next_unread, Series},
+};
+
+use crate::{
+    app::App,
+    cli::{report, select_entry},
+    utils::format::format_entry,
+};
+
+pub(crate) fn match_series_subcommand(app: &mut App, matches: &ArgMatches) {
+    match matches.subcommand() {
+        Some(("set", matches)) => set_series(app, matches),
+        Some(("unset", matches)) => unset_series(app, matches),
+        Some(("next", _)) => show_next_unread(app),
+        _ => panic!("The clap app should have handled this"),
+    }
+}
+
+/// Sets the series of an entry, e.g. `The Expanse #3`.
+fn set_series(app: &mut App, matches: &ArgMatches) {
+    let entry_index = match select_entry(app, matches) {
+        Some(entry_index) => entry_index,
+        None => return,
+    };
+    let series = matches
+        .value_of("SERIES")
+        .unwrap()
+        .parse::<Series>()
+        .unwrap();
+
+    let entry_name = match app.shelf.get_index(entry_index) {
+        Ok(entry) => format_entry(entry_index, entry),
+        Err(e) => {
+            report::error(
+                format!("Couldn't set the series of entry {}", entry_index),
+                e,
+            );
+            return;
+        },
+    };
+
+    match app.set_series_index(entry_index, Some(series.clone())) {
+        Ok(()) => report::success(format!(
+            "Successfully added '{}' to {}",
+            entry_name, series
+        )),
+        Err(e) => report::error(
+            format!("Couldn't set the series of '{}'", entry_name),
+            e,
+        ),
+    }
+}
+
+/// Unsets the series set by hand of an entry. The series from its BibTeX
+/// metadata, if any, is used again.
+fn unset_series(app: &mut App, matches: &ArgMatches) {
+    let entry_index = match select_entry(app, matches) {
+        Some(entry_index) => entry_index,
+        None => return,
+    };
+
+    let entry_name = match app.shelf.get_index(entry_index) {
+        Ok(entry) => format_entry(entry_index, entry),
+        Err(e) => {
+            report::error(
+                format!("Couldn't unset the series of entry {}", entry_index),
+                e,
+            );
+            return;
+        },
+    };
+
+    match app.set_series_index(entry_index, None) {
+        Ok(()) => report::success(format!(
+            "Successfully unset the series of '{}'",
+            entry_name
+        )),
+        Err(e) => report::error(
+            format!("Couldn't unset the series of '{}'", entry_name),
+            e,
+        ),
+    }
+}
+
+/// Suggests the entry to read next in every series that has been started.
+fn show_next_unread(app: &App) {
+    let suggestions = group(app.list_entries(), |(_, entry)| entry)
+        .into_iter()
+        .filter_map(|(name, entries)| {
+            next_unread(&entries, |(_, entry)| entry)
+                .map(|&(i, entry)| (name, i, entry))
+        })
+        .collect::<Vec<(String, usize, &Entry)>>();
+
+    if suggestions.is_empty() {
+        report::success("There is nothing to read next in any of the series");
+        return;
+    }
+
+    for (name, i, entry) in suggestions {
+        println!("{}: {}", name, format_entry(i + 1, entry));
+    }
+}
+
+/// Lists the entries grouped by their series, in the order of their numbers,
+/// with the one to read next in each series marked.
+pub(crate) fn list_series(entries: Vec<(usize, &Entry)>) {
+    let groups = group(entries, |(_, entry)| entry);
+
+    if groups.is_empty() {
+        report::success("None of the entries are part of a series");
+        return;
+    }
+
+    for (name, entries) in groups.iter() {
+        println!("{}", name);
+
+        let next = next_unread(entries, |(_, entry)| entry).map(|(i, _)| *i);
+
+        for &(i, entry) in entries.iter() {
+            let number = match entry.series().and_then(|series| series.number) {
+                Some(number) => format!("#{}", number),
+                None => "-".to_string(),
+            };
+            let marker = match next == Some(i) {
+                true => " <- next",
+                false => "",
+            };
+
+            println!(
+                "  {:>3} {} ({}){}",
+                number,
+                format_entry(i + 1, entry),
+                entry.status,
+                marker
+            );
+        }
+    }
+}
+
+/// Suggests the entry to read next in the series of an entry just finished.
+pub(crate) fn suggest_next(app: &App, index: usize) {
+    let series = match app.shelf.get_index(index).ok().and_then(Entry::series) {
+        Some(series) => series,
+        None => return,
+    };
+
+    let entries = app
+        .list_entries()
+        .into_iter()
+        .filter(|(_, entry)| {
+            matches!(entry.series(), Some(other) if other.name == series.name)
+        })
+        .collect();
+
+    for (name, entries) in group(entries, |(_, entry)| entry) {
+        if let Some(&(i, entry)) = next_unread(&entries, |(_, entry)| entry) {
+            println!("Next in {}: {}", name, format_entry(i + 1, entry));
+        }
+    }
+}
@@ -0,0 +1,99 @@
+use std::path::PathBuf;
+
+use ::clap::ArgMatches;
+
+use lib::shelf::Shelf;
+
+use crate::{
+    app::{App, NoSuchShelf},
+    cli::report::{self, Failure},
+    config::Config,
+};
+
+pub(crate) fn match_shelf_subcommand(app: &mut App, matches: &ArgMatches) {
+    match matches.subcommand() {
+        Some(("list", _)) => list_shelves(app),
+        Some(("create", matches)) => create_shelf(app, matches),
+        Some(("default", matches)) => set_default_shelf(app, matches),
+        _ => panic!("The clap app should have handled this"),
+    }
+}
+
+/// Lists the names of the shelves along with the number of entries on them
+/// and the paths to their files. The shelf in use is marked with `*`.
+fn list_shelves(app: &App) {
+    for name in app.config.shelf_names() {
+        let db = app.config.shelf_db(&name).unwrap();
+
+        let size = match name == app.shelf_name {
+            true => Ok(app.size()),
+            false => {
+                Shelf::open_or_default(&db).map(|shelf| shelf.entries.len())
+            },
+        };
+        let size = match size {
+            Ok(size) => format!("{} entries", size),
+            Err(e) => e.to_string(),
+        };
+
+        let marker = match name == app.shelf_name {
+            true => '*',
+            false => ' ',
+        };
+
+        println!("{} {} ({}) - {}", marker, name, size, db.display());
+    }
+}
+
+/// Creates a new named shelf, saved to the file provided or to a file named
+/// after it in the config directory.
+fn create_shelf(app: &mut App, matches: &ArgMatches) {
+    let name = matches.value_of("NAME").unwrap();
+    let db = match matches.value_of_os("DB") {
+        Some(db) => PathBuf::from(db),
+        None => Config::default_config_dir().join(format!("{}.db", name)),
+    };
+
+    if app.config.shelf_db(name).is_some() {
+        report::failure(
+            Failure::Duplicate,
+            format!("Couldn't create the shelf '{}': it already exists", name),
+        );
+        return;
+    }
+
+    let created = app
+        .config
+        .update(|config| config.add_shelf(name, db.clone()));
+
+    match created {
+        Ok(()) => report::success(format!(
+            "Successfully created the shelf '{}' at '{}'",
+            name,
+            db.display()
+        )),
+        Err(e) => {
+            report::error(format!("Couldn't create the shelf '{}'", name), e)
+        },
+    }
+}
+
+/// Sets the shelf used unless another one is specified.
+fn set_default_shelf(app: &mut App, matches: &ArgMatches) {
+    let name = matches.value_of("NAME").unwrap();
+
+    if app.config.shelf_db(name).is_none() {
+        let e = NoSuchShelf(name.to_string());
+        report::error("Couldn't set the default shelf", e);
+        return;
+    }
+
+    let set = app.config.update(|config| config.set_default_shelf(name));
+
+    match set {
+        Ok(()) => {
+            report::success(format!("'{}' is now the default shelf", name))
+        },
+        Err(e) => report::error("Couldn't set the default shelf", e),
+    }
+}
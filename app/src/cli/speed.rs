@@ -0,0 +1,167 @@
+use std::{
+    io::{stdin, stdout, Write},
+    time::{Instant, SystemTime, UNIX_EPOCH},
+};
+
+use ::clap::ArgMatches;
+use chrono::Utc;
+use crossterm::{
+    cursor::MoveTo,
+    execute,
+    terminal::{size, Clear, ClearType},
+};
+
+use lib::{
+    document::Document,
+    speed::{Passage, SpeedTest},
+};
+
+use crate::{
+    app::App,
+    cli::{
+        report::{self, Failure},
+        select_entry,
+    },
+    utils::format::{format_entry, wrap},
+};
+
+/// The number of comprehension questions asked after each practice
+const QUESTIONS: usize = 4;
+
+/// Extracts an entry index and a passage length from the matches provided,
+/// and times the user reading a passage of the corresponding entry. Then
+/// asks a few comprehension questions, and saves the result.
+pub(crate) fn speed_test(app: &mut App, matches: &ArgMatches) {
+    let entry_index = match select_entry(app, matches) {
+        Some(entry_index) => entry_index,
+        None => return,
+    };
+    let length = matches.value_of("words").unwrap().parse::<usize>().unwrap();
+
+    let entry = match app.shelf.get_index(entry_index) {
+        Ok(entry) => entry,
+        Err(e) => {
+            report::error(
+                format!("Couldn't practice on entry {}", entry_index),
+                e,
+            );
+            return;
+        },
+    };
+    let entry_name = format_entry(entry_index, entry);
+
+    let document = match Document::open(&entry.path) {
+        Ok(document) => document,
+        Err(e) => {
+            report::error(format!("Couldn't read '{}'", entry_name), e);
+            return;
+        },
+    };
+
+    let seed = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|time| time.as_nanos() as u64)
+        .unwrap_or_default();
+
+    let passage = match Passage::pick(&document, length, QUESTIONS, seed) {
+        Some(passage) => passage,
+        None => {
+            report::failure(
+                Failure::Invalid,
+                format!("'{}' is too short to practice on", entry_name),
+            );
+            return;
+        },
+    };
+
+    println!(
+        "You are about to read {} words from '{}'",
+        passage.words.len(),
+        entry_name
+    );
+    if prompt("Press Enter to start reading...").is_none() {
+        return;
+    }
+
+    clear_screen();
+
+    let width = size().map_or(80, |(width, _)| width as usize).min(80);
+    for line in wrap(&passage.words.join(" "), width) {
+        println!("{}", line);
+    }
+
+    let started = Utc::now();
+    let timer = Instant::now();
+
+    if prompt("\nPress Enter as soon as you are done...").is_none() {
+        return;
+    }
+
+    let duration = timer.elapsed();
+
+    clear_screen();
+    println!("Now answer a few questions about the passage");
+
+    let mut correct = 0;
+
+    for (i, question) in passage.questions.iter().enumerate() {
+        let message = format!(
+            "{}. Was '{}' in the passage? [y/n] ",
+            i + 1,
+            question.word
+        );
+
+        let answer = loop {
+            let answer = prompt(&message).map(|a| a.trim().to_lowercase());
+
+            match answer.as_deref() {
+                Some("y" | "yes") => break true,
+                Some("n" | "no") => break false,
+                Some(_) => continue,
+                None => return,
+            }
+        };
+
+        if answer == question.answer {
+            correct += 1;
+        }
+    }
+
+    let test = SpeedTest {
+        date: started,
+        words: passage.words.len(),
+        duration,
+        questions: passage.questions.len(),
+        correct,
+    };
+
+    println!(
+        "You read at {:.0} WPM and answered {} of {} questions correctly",
+        test.wpm(),
+        test.correct,
+        test.questions
+    );
+
+    if let Err(e) = app.add_speed_test(test) {
+        report::error("Couldn't save the result", e);
+    }
+}
+
+/// Prints the message and reads a line from the standard input. Returns
+/// None if the input has ended.
+fn prompt(message: &str) -> Option<String> {
+    print!("{}", message);
+    stdout().flush().ok()?;
+
+    let mut line = String::new();
+
+    match stdin().read_line(&mut line) {
+        Ok(0) | Err(_) => None,
+        Ok(_) => Some(line),
+    }
+}
+
+/// Clears the terminal, so that the passage can't be looked at again.
+fn clear_screen() {
+    let _ = execute!(stdout(), Clear(ClearType::All), MoveTo(0, 0));
+}
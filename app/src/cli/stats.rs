@@ -0,0 +1,101 @@
+use ::clap::ArgMatches;
+
+use lib::{document::Document, speed::estimate, status::Status};
+
+use crate::{
+    app::App,
+    utils::format::{format_duration, format_entry},
+};
+
+/// The number of latest speed-reading practices shown on the chart
+const CHART_PRACTICES: usize = 10;
+
+/// The width of the longest bar on the chart
+const CHART_WIDTH: usize = 40;
+
+/// Prints the reading statistics: the entries by status, the time spent
+/// reading, a chart of the speed-reading progress, and the estimated time
+/// to finish the entries that can be estimated.
+pub(crate) fn show_stats(app: &mut App, _matches: &ArgMatches) {
+    let entries = app.list_entries();
+
+    let count = |status| {
+        entries
+            .iter()
+            .filter(|(_, entry)| entry.status == status)
+            .count()
+    };
+
+    println!(
+        "Entries: {} ({} to read, {} reading, {} finished)",
+        entries.len(),
+        count(Status::Want),
+        count(Status::Reading),
+        count(Status::Finished)
+    );
+
+    let sessions = entries
+        .iter()
+        .map(|(_, entry)| entry.sessions.len())
+        .sum::<usize>();
+    let time_read = entries.iter().map(|(_, entry)| entry.time_read()).sum();
+
+    println!(
+        "Time read: {} over {} sessions",
+        format_duration(time_read),
+        sessions
+    );
+
+    let wpm = match app.shelf.reading_speed() {
+        Some(wpm) => wpm,
+        None => {
+            println!(
+                "\nPractice with `bookshelf speed` to track your reading \
+                 speed and estimate the time to finish the entries"
+            );
+            return;
+        },
+    };
+
+    println!("\nReading speed: {:.0} WPM", wpm);
+
+    let tests = &app.shelf.speed_tests;
+    let latest = &tests[tests.len().saturating_sub(CHART_PRACTICES)..];
+    let max_wpm = latest.iter().map(|test| test.wpm()).fold(0.0, f64::max);
+
+    for test in latest {
+        let bar = match max_wpm > 0.0 {
+            true => (test.wpm() / max_wpm * CHART_WIDTH as f64) as usize,
+            false => 0,
+        };
+
+        println!(
+            "{} {} {:.0}",
+            test.date.format("%Y-%m-%d"),
+            "█".repeat(bar.max(1)),
+            test.wpm()
+        );
+    }
+
+    let estimates = entries
+        .iter()
+        .filter(|(_, entry)| entry.status != Status::Finished)
+        .filter(|(_, entry)| Document::is_supported(&entry.path))
+        .filter_map(|(i, entry)| {
+            let words = match entry.progress {
+                Some(progress) => progress.remaining(),
+                None => Document::open(&entry.path).ok()?.word_count(),
+            };
+
+            Some((format_entry(i + 1, entry), estimate(words, wpm)))
+        })
+        .collect::<Vec<_>>();
+
+    if !estimates.is_empty() {
+        println!("\nEstimated time to finish:");
+
+        for (entry_name, time_left) in estimates {
+            println!("{}: {}", entry_name, format_duration(time_left));
+        }
+    }
+}
@@ -0,0 +1,179 @@
+use ::clap::ArgMatches;
+
+use lib::tag::{Tag, TagTree};
+
+use crate::{
+    app::App,
+    cli::{
+        filter_entries,
+        report::{self, Failure},
+        select_entries,
+    },
+    utils::format::format_entry,
+};
+
+pub(crate) fn match_tag_subcommand(app: &mut App, matches: &ArgMatches) {
+    match matches.subcommand() {
+        Some(("tree", _)) => show_tag_tree(app),
+        Some(("add", matches)) => edit_tags(app, matches, true),
+        Some(("rm", matches)) => edit_tags(app, matches, false),
+        Some(("rename", matches)) => rename_tag(app, matches),
+        Some(("merge", matches)) => merge_tag(app, matches),
+        _ => panic!("The clap app should have handled this"),
+    }
+}
+
+/// Prints the tag hierarchy of the bookshelf, with the number of entries
+/// tagged with each tag or any of its subtags.
+fn show_tag_tree(app: &App) {
+    let tree = app.shelf.tag_tree();
+
+    if tree.is_empty() {
+        report::success("No entries on the bookshelf are tagged");
+        return;
+    }
+
+    for node in tree.iter() {
+        println!("{} ({})", node.tag.name(), node.count);
+        print_subtrees(&node.children, "");
+    }
+}
+
+/// Prints the subtrees of a tag, each line prefixed with the branches of
+/// its ancestors.
+fn print_subtrees(subtrees: &[TagTree], prefix: &str) {
+    for (i, node) in subtrees.iter().enumerate() {
+        let (branch, indent) = match i + 1 == subtrees.len() {
+            true => ("└── ", "    "),
+            false => ("├── ", "│   "),
+        };
+
+        println!("{}{}{} ({})", prefix, branch, node.tag.name(), node.count);
+        print_subtrees(&node.children, &format!("{}{}", prefix, indent));
+    }
+}
+
+/// Renames a tag along with all of its subtags on every entry.
+fn rename_tag(app: &mut App, matches: &ArgMatches) {
+    let from = Tag::new(matches.value_of("FROM").unwrap());
+    let to = Tag::new(matches.value_of("TO").unwrap());
+
+    match app.rename_tag(&from, &to) {
+        Ok(retagged) => report::success(format!(
+            "Successfully renamed '{}' to '{}' on {} entries",
+            from, to, retagged
+        )),
+        Err(e) => report::error(format!("Couldn't rename '{}'", from), e),
+    }
+}
+
+/// Merges a tag along with all of its subtags into another tag on every
+/// entry.
+fn merge_tag(app: &mut App, matches: &ArgMatches) {
+    let from = Tag::new(matches.value_of("FROM").unwrap());
+    let into = Tag::new(matches.value_of("INTO").unwrap());
+
+    match app.merge_tag(&from, &into) {
+        Ok(retagged) => report::success(format!(
+            "Successfully merged '{}' into '{}' on {} entries",
+            from, into, retagged
+        )),
+        Err(e) => report::error(format!("Couldn't merge '{}'", from), e),
+    }
+}
+
+/// Adds tags to, or removes them from, the entries selected, or all of the
+/// entries matching a query. Given a query, all of the positional values are
+/// tags.
+fn edit_tags(app: &mut App, matches: &ArgMatches, add: bool) {
+    let values = matches.values_of("TAGS").into_iter().flatten();
+
+    let (indices, tags) = match matches.is_present("filter") {
+        true => {
+            let tags = matches
+                .values_of("SELECTOR")
+                .into_iter()
+                .flatten()
+                .chain(values)
+                .map(Tag::new)
+                .collect::<Vec<Tag>>();
+
+            match filter_entries(app, matches) {
+                Some(entries) => {
+                    let indices = entries.into_iter().map(|(i, _)| i + 1);
+
+                    (indices.collect::<Vec<usize>>(), tags)
+                },
+                None => return,
+            }
+        },
+        false => {
+            let tags = values.map(Tag::new).collect::<Vec<Tag>>();
+
+            if tags.is_empty() {
+                report::failure(Failure::Invalid, "No tags given");
+                return;
+            }
+
+            let action = match add {
+                true => "Tag",
+                false => "Untag",
+            };
+
+            match select_entries(app, matches, Some(action)) {
+                Some(indices) => (indices, tags),
+                None => return,
+            }
+        },
+    };
+
+    if indices.is_empty() {
+        report::failure(Failure::NotFound, "No entries match the filter");
+        return;
+    }
+
+    let tag_list = tags
+        .iter()
+        .map(|tag| format!("'{}'", tag))
+        .collect::<Vec<String>>()
+        .join(", ");
+
+    let result = match add {
+        true => app.tag_entries_indices(&indices, &tags),
+        false => app.untag_entries_indices(&indices, &tags),
+    };
+
+    let retagged = match result {
+        Ok(retagged) => retagged,
+        Err(e) => {
+            report::error("Couldn't change the tags", e);
+            return;
+        },
+    };
+
+    let entries = match indices.as_slice() {
+        &[entry_index] => {
+            let entry = app.shelf.get_index(entry_index).unwrap();
+
+            format!("'{}'", format_entry(entry_index, entry))
+        },
+        _ => format!("{} entries", retagged),
+    };
+
+    match (add, retagged) {
+        (true, 0) if indices.len() == 1 => {
+            report::success(format!("{} already has {}", entries, tag_list))
+        },
+        (false, 0) if indices.len() == 1 => {
+            report::success(format!("{} has none of {}", entries, tag_list))
+        },
+        (true, _) => report::success(format!(
+            "Successfully tagged {} with {}",
+            entries, tag_list
+        )),
+        (false, _) => report::success(format!(
+            "Successfully removed {} from {}",
+            tag_list, entries
+        )),
+    }
+}
@@ -5,28 +5,61 @@ use directories::ProjectDirs;
 use serde::{Deserialize, Serialize};
 use shellexpand::tilde;
 use std::{
+    collections::BTreeMap,
     fs::{create_dir_all, File},
     io::Write,
-    path::PathBuf,
+    path::{Path, PathBuf},
 };
 use toml::to_vec;
 
 /// The configuration for the app.
 #[derive(Deserialize, Serialize)]
 pub struct Config {
-    /// The path to a file for the Shelf to be saved to.
+    /// The path to a file for the default Shelf to be saved to.
     db: PathBuf,
+    /// Whether to wait for the viewer to exit and record a reading session
+    /// each time an entry is opened.
+    #[serde(default)]
+    track_sessions: bool,
+    /// The speed of the speed reader in the TUI, in words per minute.
+    #[serde(default = "Config::default_rsvp_wpm")]
+    rsvp_wpm: u32,
+    /// Whether to open plain text, Markdown and EPUB entries in the reader
+    /// built into the TUI instead of an external program.
+    #[serde(default = "Config::default_builtin_reader")]
+    builtin_reader: bool,
+    /// The name of the shelf used unless another one is specified.
+    #[serde(default = "Config::default_shelf_name")]
+    default_shelf: String,
+    /// The command the text copied is piped into, e.g. `wl-copy` or
+    /// `xclip -selection clipboard`, when there is no terminal to copy it
+    /// with an OSC 52 escape sequence.
+    #[serde(default)]
+    clipboard_command: Option<String>,
+    /// The paths to the files of the named shelves other than the default
+    /// one. Has to go last, since it is a table in config.toml.
+    #[serde(default)]
+    shelves: BTreeMap<String, PathBuf>,
 }
 
 impl Default for Config {
     fn default() -> Self {
         Config {
             db: Self::default_config_dir().join("db"),
+            track_sessions: false,
+            rsvp_wpm: Self::default_rsvp_wpm(),
+            builtin_reader: Self::default_builtin_reader(),
+            default_shelf: Self::default_shelf_name(),
+            clipboard_command: None,
+            shelves: BTreeMap::new(),
         }
     }
 }
 
 impl Config {
+    /// The name of the shelf saved to the file at `db`
+    pub const DEFAULT_SHELF: &'static str = "default";
+
     /// A helper function, returns the PathBuf to the default config directory.
     pub fn default_config_dir() -> PathBuf {
         ProjectDirs::from("com", "kirusfg", "bookshelf")
@@ -94,6 +127,46 @@ impl Config {
         Ok(default_config)
     }
 
+    /// Applies a change both to this `Config` and to the settings read from
+    /// config.toml alone, and writes the latter back, so that the settings
+    /// from the environment don't end up in the file.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the change fails, or if the config file could not
+    /// be read or written. The file is left unchanged in that case.
+    pub fn update<F>(&mut self, change: F) -> Result<(), Error>
+    where
+        F: Fn(&mut Config) -> Result<(), Error>,
+    {
+        let mut file_config = Self::get_file()?;
+
+        change(&mut file_config)?;
+        file_config.save()?;
+
+        change(self)
+    }
+
+    /// Returns a `Config` with settings from config.toml alone.
+    fn get_file() -> Result<Self, Error> {
+        AppConfig::builder()
+            .add_source(ConfigFile::from(
+                Self::default_config_dir().join("config.toml"),
+            ))
+            .build()?
+            .try_deserialize::<Config>()
+    }
+
+    /// Writes the `Config` into config.toml located at the OS's default
+    /// config directory.
+    fn save(&self) -> Result<(), Error> {
+        let config_toml = to_vec(self).map_err(|e| Error::Foreign(e.into()))?;
+
+        File::create(Self::default_config_dir().join("config.toml"))
+            .and_then(|mut config_file| config_file.write_all(&config_toml))
+            .map_err(|e| Error::Foreign(e.into()))
+    }
+
     /// Returns whether the config folder and config.toml exist.
     pub fn exists() -> bool {
         Self::default_config_dir().join("config.toml").exists()
@@ -101,12 +174,109 @@ impl Config {
 
     /// Returns the path to db file from config.toml. Expands `~` to $HOME.
     pub fn db(&self) -> PathBuf {
-        // Expand possible `~` in the path
-        let path = &self.db.to_str().unwrap();
-        let path = tilde(path);
+        expand(&self.db)
+    }
+
+    /// Returns the path to the db file of the named shelf, or None if there
+    /// is no such shelf. Expands `~` to $HOME.
+    pub fn shelf_db(&self, name: &str) -> Option<PathBuf> {
+        match name {
+            Self::DEFAULT_SHELF => Some(self.db()),
+            _ => self.shelves.get(name).map(|path| expand(path)),
+        }
+    }
+
+    /// Returns the names of all of the shelves, the default one first.
+    pub fn shelf_names(&self) -> Vec<String> {
+        let mut names = vec![Self::DEFAULT_SHELF.to_string()];
+        names.extend(self.shelves.keys().cloned());
+
+        names
+    }
+
+    /// Adds a named shelf saved to the file provided.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there already is a shelf with the name.
+    pub fn add_shelf(&mut self, name: &str, db: PathBuf) -> Result<(), Error> {
+        if self.shelf_db(name).is_some() {
+            return Err(Error::Message(format!(
+                "There already is a shelf named '{}'",
+                name
+            )));
+        }
+
+        self.shelves.insert(name.to_string(), db);
+
+        Ok(())
+    }
 
-        PathBuf::from(path.into_owned())
+    /// Returns the name of the shelf used unless another one is specified.
+    pub fn default_shelf(&self) -> &str {
+        &self.default_shelf
     }
+
+    /// Sets the shelf used unless another one is specified.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there is no shelf with the name.
+    pub fn set_default_shelf(&mut self, name: &str) -> Result<(), Error> {
+        if self.shelf_db(name).is_none() {
+            return Err(Error::Message(format!(
+                "There is no shelf named '{}'",
+                name
+            )));
+        }
+
+        self.default_shelf = name.to_string();
+
+        Ok(())
+    }
+
+    fn default_shelf_name() -> String {
+        Self::DEFAULT_SHELF.to_string()
+    }
+
+    /// Returns whether reading sessions should be recorded when opening
+    /// entries.
+    pub fn track_sessions(&self) -> bool {
+        self.track_sessions
+    }
+
+    /// Returns the speed of the speed reader in the TUI, in words per minute.
+    pub fn rsvp_wpm(&self) -> u32 {
+        self.rsvp_wpm
+    }
+
+    fn default_rsvp_wpm() -> u32 {
+        300
+    }
+
+    /// Returns whether the supported entries should be opened in the reader
+    /// built into the TUI.
+    pub fn builtin_reader(&self) -> bool {
+        self.builtin_reader
+    }
+
+    /// Returns the command the text copied is piped into when there is no
+    /// terminal to copy it, if any.
+    pub fn clipboard_command(&self) -> Option<&str> {
+        self.clipboard_command.as_deref()
+    }
+
+    fn default_builtin_reader() -> bool {
+        true
+    }
+}
+
+/// Expands a possible `~` in the path to $HOME.
+fn expand(path: &Path) -> PathBuf {
+    let path = path.to_str().unwrap();
+    let path = tilde(path);
+
+    PathBuf::from(path.into_owned())
 }
 
 #[cfg(test)]
@@ -3,14 +3,22 @@ mod cli;
 mod config;
 mod tui;
 mod utils;
+mod viewer;
 
-use crate::app::App;
+use crate::{
+    app::App,
+    cli::{clap::get_cli_commands, report},
+};
 
 #[tokio::main]
-async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let mut app = App::new()?;
+async fn main() {
+    let matches = get_cli_commands().get_matches();
+    report::set_mode(matches.is_present("quiet"), matches.is_present("json"));
 
-    app.start().await;
+    match App::new(&matches) {
+        Ok(mut app) => app.start(&matches).await,
+        Err(e) => report::error("Couldn't start bookshelf", e),
+    }
 
-    Ok(())
+    std::process::exit(report::exit_code());
 }
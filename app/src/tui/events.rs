@@ -1,4 +1,10 @@
-use std::time::Duration;
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
 
 use crossterm::event::{poll, read, KeyCode};
 use tokio::{
@@ -15,16 +21,27 @@ pub(crate) enum Event<I> {
 
 pub(crate) struct EventLoop {
     pub rx: UnboundedReceiver<Event<KeyCode>>,
+    /// The tick rate in milliseconds, shared with the event loop task
+    tick_rate: Arc<AtomicU64>,
 }
 
 impl EventLoop {
+    /// The tick rate used when there is nothing to animate
+    pub const DEFAULT_TICK_RATE: Duration = Duration::from_millis(1000);
+
     pub fn new(tick_rate: Duration) -> Self {
         let (tx, rx) = unbounded_channel();
+        let tick_rate = Arc::new(AtomicU64::new(tick_rate.as_millis() as u64));
+        let task_tick_rate = tick_rate.clone();
 
         spawn(async move {
             let mut last_tick = Instant::now();
 
             loop {
+                let tick_rate = Duration::from_millis(
+                    task_tick_rate.load(Ordering::Relaxed),
+                );
+
                 // The default tick_rate is 1 FPS, which means that the terminal
                 // will wait for an event for 1 second before sending a Tick
                 // event itself.
@@ -55,12 +72,19 @@ impl EventLoop {
             }
         });
 
-        Self { rx }
+        Self { rx, tick_rate }
+    }
+
+    /// Changes the rate at which the Tick events are sent, starting from the
+    /// next one.
+    pub fn set_tick_rate(&self, tick_rate: Duration) {
+        self.tick_rate
+            .store(tick_rate.as_millis() as u64, Ordering::Relaxed);
     }
 }
 
 impl Default for EventLoop {
     fn default() -> Self {
-        Self::new(Duration::from_millis(1000))
+        Self::new(Self::DEFAULT_TICK_RATE)
     }
 }
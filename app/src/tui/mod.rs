@@ -1,8 +1,13 @@
 mod events;
+mod reader;
+mod rsvp;
 mod state;
 mod ui;
 
-use std::io::{self, stdout, Stdout};
+use std::{
+    io::{self, stdout, Stdout},
+    mem::replace,
+};
 
 use crossterm::{
     event::{DisableMouseCapture, EnableMouseCapture, KeyCode},
@@ -12,13 +17,37 @@ use crossterm::{
         LeaveAlternateScreen,
     },
 };
+use tokio::{
+    sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender},
+    task::spawn_blocking,
+};
 use tui::{backend::CrosstermBackend, Terminal};
 
-use crate::{app::App, utils::format::format_entry};
+use lib::{
+    document::Document,
+    entry::Entry,
+    fuzzy::{rank, Fields},
+    progress::Progress,
+    series::group,
+    session::Session,
+    sort::{sort_by_keys, Key, SortKey},
+};
+
+use crate::{
+    app::App,
+    utils::{
+        clipboard::copy,
+        export::{to_bib_entry, to_citation},
+        format::{format_duration, format_entry},
+    },
+    viewer::MIN_SESSION,
+};
 
 use self::{
     events::{Event, EventLoop},
-    state::State,
+    reader::Reader,
+    rsvp::Rsvp,
+    state::{Focus, Mode, PromptAction, SidebarItem, State},
     ui::ui,
 };
 
@@ -27,34 +56,30 @@ pub(crate) struct Tui<'a> {
     state: State,
     terminal: Terminal<CrosstermBackend<Stdout>>,
     event_loop: EventLoop,
+    /// Sends reading sessions timed in the background
+    sessions_tx: UnboundedSender<(Entry, Session)>,
+    /// Receives reading sessions timed in the background
+    sessions_rx: UnboundedReceiver<(Entry, Session)>,
 }
 
 impl<'a> Tui<'a> {
     pub fn new(app: &'a mut App) -> Self {
         let terminal = setup_terminal().unwrap();
-        let mut state = State::default();
-        state.entries.items = app
-            .list_entries()
-            .iter()
-            .map(|(_, entry)| {
-                entry
-                    .path
-                    .file_name()
-                    .unwrap()
-                    .to_str()
-                    .unwrap()
-                    .to_string()
-            })
-            .collect();
-
+        let state = State::default();
         let event_loop = EventLoop::default();
+        let (sessions_tx, sessions_rx) = unbounded_channel();
 
-        Self {
+        let mut tui = Self {
             app,
             state,
             terminal,
             event_loop,
-        }
+            sessions_tx,
+            sessions_rx,
+        };
+        tui.get_entry_list();
+
+        tui
     }
 
     /// Runs the TUI life cycle: draw the UI, check for events, gracefully
@@ -87,10 +112,16 @@ impl<'a> Tui<'a> {
 
     async fn match_events(&mut self) {
         match self.event_loop.rx.recv().await {
-            // TODO: think if you need the tick event at all, because the
-            // TUI is reactive. Could there be something progress related?
+            // Ticks drive the speed reader and pick up the reading sessions
+            // timed in the background
             Some(Event::Tick) => {
-                self.state.should_redraw = false;
+                let recorded = self.record_sessions();
+                let advanced = match &mut self.state.mode {
+                    Mode::Rsvp(rsvp) => rsvp.advance(),
+                    _ => false,
+                };
+
+                self.state.should_redraw = recorded || advanced;
             },
             Some(Event::Input(key)) => {
                 self.match_inputs(key);
@@ -110,105 +141,966 @@ impl<'a> Tui<'a> {
             match key {
                 KeyCode::Char(c) => {
                     self.state.prompt.push(c);
+                    self.find_incrementally();
                 },
                 KeyCode::Backspace => {
                     self.state.prompt.pop();
+                    self.find_incrementally();
                 },
                 KeyCode::Esc => {
                     self.state.prompt_title = "Prompt".to_string();
                     self.state.editing_prompt = false;
                     self.state.prompt.clear();
+
+                    if self.state.prompt_action.take()
+                        == Some(PromptAction::Find)
+                    {
+                        self.state.find = None;
+                        self.update_filters();
+                    }
                 },
                 KeyCode::Enter => {
-                    // Execute the last queued command here
-                    todo!();
+                    self.state.editing_prompt = false;
+
+                    match self.state.prompt_action.take() {
+                        Some(PromptAction::Search) => self.search(),
+                        Some(PromptAction::Filter) => self.filter_by_query(),
+                        Some(PromptAction::SwitchShelf) => self.switch_shelf(),
+                        Some(PromptAction::Find) => {
+                            self.state.prompt_title = "Find".to_string();
+                            self.state.prompt = format!(
+                                "{} entries found",
+                                self.state.entries.items.len()
+                            );
+                        },
+                        // Nothing is done with the prompts without an action
+                        None => {
+                            self.state.prompt_title = "Prompt".to_string();
+                            self.state.prompt.clear();
+                        },
+                    }
                 },
                 _ => {
                     self.state.should_redraw = false;
                 },
             }
         } else {
-            match key {
-                KeyCode::Up | KeyCode::Char('k') => {
-                    self.state.entries.previous();
+            match self.state.mode {
+                Mode::Browse if self.state.focus == Focus::Sidebar => {
+                    self.match_sidebar_inputs(key)
                 },
-                KeyCode::Down | KeyCode::Char('j') => {
-                    self.state.entries.next();
-                },
-                KeyCode::Enter | KeyCode::Right | KeyCode::Char('l') => {
-                    self.open_entry();
-                    self.state.prompt_title = "Success".to_string();
-                },
-                KeyCode::Home | KeyCode::Char('K') => {
-                    self.state.entries.first();
+                Mode::Browse => self.match_browse_inputs(key),
+                Mode::Rsvp(_) => self.match_rsvp_inputs(key),
+                Mode::Reader(_) => self.match_reader_inputs(key),
+            }
+        }
+    }
+
+    fn match_browse_inputs(&mut self, key: KeyCode) {
+        match key {
+            KeyCode::Up | KeyCode::Char('k') => {
+                self.state.entries.previous();
+            },
+            KeyCode::Down | KeyCode::Char('j') => {
+                self.state.entries.next();
+            },
+            KeyCode::Enter | KeyCode::Right | KeyCode::Char('l') => {
+                self.open_entry();
+            },
+            KeyCode::Char('o') => {
+                self.open_entry_externally();
+            },
+            KeyCode::Home | KeyCode::Char('K') => {
+                self.state.entries.first();
+            },
+            KeyCode::End | KeyCode::Char('J') => {
+                self.state.entries.last();
+            },
+            KeyCode::Delete | KeyCode::Char('d') => {
+                self.remove_entry();
+            },
+            KeyCode::Char('u') => {
+                self.undo();
+            },
+            KeyCode::Char('y') => {
+                self.copy_bib(false);
+            },
+            KeyCode::Char('Y') => {
+                self.copy_bib(true);
+            },
+            KeyCode::Char('r') => {
+                self.start_rsvp();
+            },
+            KeyCode::Char('a') => {
+                self.state.prompt_title = "Add an entry".to_string();
+                self.state.editing_prompt = true;
+                self.state.prompt.clear();
+            },
+            KeyCode::Char('s') => {
+                self.cycle_sort_key();
+            },
+            KeyCode::Char('S') => {
+                self.reverse_sort_key();
+            },
+            KeyCode::Char('w') => {
+                self.state.prompt_title = format!(
+                    "Switch to a shelf ({})",
+                    self.app.config.shelf_names().join(", ")
+                );
+                self.state.prompt_action = Some(PromptAction::SwitchShelf);
+                self.state.editing_prompt = true;
+                self.state.prompt.clear();
+            },
+            KeyCode::Char('/') => {
+                self.state.prompt_title = "Find".to_string();
+                self.state.prompt_action = Some(PromptAction::Find);
+                self.state.editing_prompt = true;
+                self.state.prompt = self.state.find.clone().unwrap_or_default();
+            },
+            KeyCode::Char('f') => {
+                self.state.prompt_title = "Filter".to_string();
+                self.state.prompt_action = Some(PromptAction::Filter);
+                self.state.editing_prompt = true;
+                self.state.prompt = match &self.state.query_filter {
+                    Some((text, _)) => text.clone(),
+                    None => String::new(),
+                };
+            },
+            KeyCode::Tab if !self.state.sidebar.items.is_empty() => {
+                self.state.focus = Focus::Sidebar;
+            },
+            KeyCode::Esc
+                if self.state.tag_filter.is_some()
+                    || self.state.collection_filter.is_some()
+                    || self.state.series_filter.is_some()
+                    || self.state.query_filter.is_some()
+                    || self.state.find.is_some() =>
+            {
+                self.state.tag_filter = None;
+                self.state.collection_filter = None;
+                self.state.series_filter = None;
+                self.state.query_filter = None;
+                self.state.find = None;
+                self.update_filters();
+            },
+            KeyCode::Esc | KeyCode::Char('q') => {
+                self.state.should_exit = true;
+            },
+            _ => {
+                self.state.should_redraw = false;
+            },
+        }
+    }
+
+    fn match_sidebar_inputs(&mut self, key: KeyCode) {
+        match key {
+            KeyCode::Up | KeyCode::Char('k') => {
+                self.state.sidebar.previous();
+            },
+            KeyCode::Down | KeyCode::Char('j') => {
+                self.state.sidebar.next();
+            },
+            KeyCode::Home | KeyCode::Char('K') => {
+                self.state.sidebar.first();
+            },
+            KeyCode::End | KeyCode::Char('J') => {
+                self.state.sidebar.last();
+            },
+            KeyCode::Enter | KeyCode::Right | KeyCode::Char('l') => {
+                // Only one of the collection, the series and the tag is
+                // filtered by
+                let item = match self.state.sidebar.selected().cloned() {
+                    Some(item) => item,
+                    None => return,
+                };
+
+                self.state.collection_filter = None;
+                self.state.series_filter = None;
+                self.state.tag_filter = None;
+
+                match item {
+                    SidebarItem::Collection(name, _) => {
+                        self.state.collection_filter = Some(name);
+                    },
+                    SidebarItem::Series(name, _) => {
+                        self.state.series_filter = Some(name);
+                    },
+                    SidebarItem::Tag(item) => {
+                        self.state.tag_filter = Some(item.tag);
+                    },
+                }
+
+                self.state.focus = Focus::Entries;
+                self.update_filters();
+            },
+            KeyCode::Char(' ') => {
+                self.toggle_collapsed_tag();
+            },
+            KeyCode::Tab => {
+                self.state.focus = Focus::Entries;
+            },
+            KeyCode::Esc => {
+                self.state.tag_filter = None;
+                self.state.collection_filter = None;
+                self.state.series_filter = None;
+                self.update_filters();
+            },
+            KeyCode::Char('q') => {
+                self.state.should_exit = true;
+            },
+            _ => {
+                self.state.should_redraw = false;
+            },
+        }
+    }
+
+    /// Filters the entries by the query in the prompt, or stops filtering
+    /// them by a query if the prompt is empty.
+    fn filter_by_query(&mut self) {
+        let text = self.state.prompt.trim().to_string();
+
+        if text.is_empty() {
+            self.state.query_filter = None;
+            self.state.prompt_title = "Prompt".to_string();
+            return self.update_filters();
+        }
+
+        match self.app.shelf.parse_query(&text) {
+            Ok(query) => {
+                self.state.query_filter = Some((text, query));
+                self.update_filters();
+
+                self.state.prompt_title = "Filter".to_string();
+                self.state.prompt = format!(
+                    "{} entries match the filter",
+                    self.state.entries.items.len()
+                );
+            },
+            Err(e) => {
+                self.state.prompt_title = "Error".to_string();
+                self.state.prompt = format!("Invalid filter: {}", e);
+            },
+        }
+    }
+
+    /// Narrows the entries down to the ones matching the text of the prompt
+    /// as it is typed, the best match selected.
+    fn find_incrementally(&mut self) {
+        if self.state.prompt_action != Some(PromptAction::Find) {
+            return;
+        }
+
+        self.state.find = match self.state.prompt.trim().is_empty() {
+            true => None,
+            false => Some(self.state.prompt.trim().to_string()),
+        };
+
+        // The fields found in are kept, as the shelf can't change meanwhile
+        self.state.entries.deselect();
+        self.list_entries();
+
+        if !self.state.entries.items.is_empty() {
+            self.state.entries.first();
+        }
+    }
+
+    /// Switches to the shelf named in the prompt, clearing the filters.
+    fn switch_shelf(&mut self) {
+        let name = self.state.prompt.trim().to_string();
+
+        match self.app.switch_shelf(&name) {
+            Ok(()) => {
+                self.state.tag_filter = None;
+                self.state.collection_filter = None;
+                self.state.query_filter = None;
+                self.state.collapsed_tags.clear();
+                self.state.focus = Focus::Entries;
+                self.update_filters();
+
+                self.state.prompt_title = "Success".to_string();
+                self.state.prompt = format!("Switched to the shelf '{}'", name);
+            },
+            Err(e) => {
+                self.state.prompt_title = "Error".to_string();
+                self.state.prompt =
+                    format!("Couldn't switch to the shelf '{}': {}", name, e);
+            },
+        }
+    }
+
+    /// Refreshes the entry list after the filters or the sort order change.
+    fn update_filters(&mut self) {
+        self.state.entries.deselect();
+
+        self.get_entry_list();
+    }
+
+    /// Sorts the entries by the next sort key, or stops sorting them after
+    /// the last key.
+    fn cycle_sort_key(&mut self) {
+        let next = match self.state.sort {
+            Some(sort) => Key::ALL
+                .iter()
+                .skip_while(|&&key| key != sort.key)
+                .nth(1)
+                .map(|&key| SortKey { key, ..sort }),
+            None => Key::ALL.first().map(|&key| SortKey::new(key)),
+        };
+
+        self.state.sort = next;
+        self.update_filters();
+    }
+
+    /// Reverses the order of the sort key, if the entries are sorted.
+    fn reverse_sort_key(&mut self) {
+        match self.state.sort {
+            Some(sort) => {
+                self.state.sort = Some(sort.reversed());
+                self.update_filters();
+            },
+            None => self.state.should_redraw = false,
+        }
+    }
+
+    /// Collapses the subtree of the selected tag in the tag tree, or expands
+    /// it if it is collapsed.
+    fn toggle_collapsed_tag(&mut self) {
+        let tag = match self.state.sidebar.selected() {
+            Some(SidebarItem::Tag(item)) if item.has_children => {
+                item.tag.clone()
+            },
+            _ => {
+                self.state.should_redraw = false;
+                return;
+            },
+        };
+
+        if !self.state.collapsed_tags.remove(&tag) {
+            self.state.collapsed_tags.insert(tag);
+        }
+
+        self.update_sidebar();
+    }
+
+    /// Rebuilds the sidebar from the collections, the series and the tags on
+    /// the shelf, counting the entries in each of the collections anew.
+    fn update_sidebar(&mut self) {
+        let shelf = &self.app.shelf;
+        let collections = shelf
+            .collections
+            .keys()
+            .map(|name| {
+                let count = match shelf.parse_query(&format!("@{}", name)) {
+                    Ok(query) => shelf
+                        .entries
+                        .iter()
+                        .filter(|entry| query.matches(entry))
+                        .count(),
+                    Err(_) => 0,
+                };
+
+                (name.clone(), count)
+            })
+            .collect();
+
+        let series = group(self.app.list_entries(), |(_, entry)| entry)
+            .into_iter()
+            .map(|(name, entries)| (name, entries.len()))
+            .collect();
+
+        self.state
+            .set_sidebar(collections, series, &shelf.tag_tree());
+    }
+
+    fn match_rsvp_inputs(&mut self, key: KeyCode) {
+        if let KeyCode::Esc | KeyCode::Char('q') = key {
+            return self.stop_rsvp();
+        }
+
+        let rsvp = match &mut self.state.mode {
+            Mode::Rsvp(rsvp) => rsvp,
+            _ => return,
+        };
+
+        match key {
+            KeyCode::Char(' ') => {
+                rsvp.toggle_pause();
+            },
+            KeyCode::Left | KeyCode::Char('h') => {
+                rsvp.rewind();
+            },
+            KeyCode::Right | KeyCode::Char('l') => {
+                rsvp.fast_forward();
+            },
+            KeyCode::Up | KeyCode::Char('k') | KeyCode::Char('+') => {
+                rsvp.speed_up();
+                self.event_loop.set_tick_rate(rsvp.interval());
+            },
+            KeyCode::Down | KeyCode::Char('j') | KeyCode::Char('-') => {
+                rsvp.slow_down();
+                self.event_loop.set_tick_rate(rsvp.interval());
+            },
+            _ => {
+                self.state.should_redraw = false;
+            },
+        }
+    }
+
+    /// Starts speed reading the selected entry.
+    fn start_rsvp(&mut self) {
+        if let Some(entry_index) = self.state.selected_entry() {
+            let entry = self.app.shelf.get_index(entry_index).unwrap();
+            let entry_name = format_entry(entry_index, entry);
+
+            let words = match Document::open(&entry.path) {
+                Ok(document) => document
+                    .words()
+                    .into_iter()
+                    .map(String::from)
+                    .collect::<Vec<String>>(),
+                Err(e) => {
+                    self.state.prompt_title = "Error".to_string();
+                    self.state.prompt =
+                        format!("Couldn't read '{}': {}", entry_name, e);
+                    return;
                 },
-                KeyCode::End | KeyCode::Char('J') => {
-                    self.state.entries.last();
+            };
+
+            if words.is_empty() {
+                self.state.prompt_title = "Error".to_string();
+                self.state.prompt = format!("'{}' has no text", entry_name);
+                return;
+            }
+
+            let rsvp = Rsvp::new(entry, words, self.app.config.rsvp_wpm());
+
+            self.event_loop.set_tick_rate(rsvp.interval());
+            self.state.mode = Mode::Rsvp(Box::new(rsvp));
+            self.state.prompt_title = "Speed reading".to_string();
+            self.state.prompt = format!("Reading '{}'", entry_name);
+        }
+    }
+
+    /// Stops speed reading, and saves the progress along with the reading
+    /// session.
+    fn stop_rsvp(&mut self) {
+        if let Mode::Rsvp(rsvp) = replace(&mut self.state.mode, Mode::Browse) {
+            self.event_loop.set_tick_rate(EventLoop::DEFAULT_TICK_RATE);
+
+            let progress = rsvp.progress();
+            let saved =
+                self.app.set_progress(&rsvp.entry, progress).and_then(|_| {
+                    self.app.record_session(&rsvp.entry, rsvp.session())
+                });
+
+            self.show_saved_progress(saved, progress);
+            self.get_entry_list();
+        }
+    }
+
+    fn match_reader_inputs(&mut self, key: KeyCode) {
+        if let KeyCode::Esc | KeyCode::Char('q') = key {
+            return self.close_reader();
+        }
+
+        if let KeyCode::Char('m') = key {
+            return self.toggle_bookmark();
+        }
+
+        let reader = match &mut self.state.mode {
+            Mode::Reader(reader) => reader,
+            _ => return,
+        };
+
+        match key {
+            KeyCode::Down | KeyCode::Char('j') => {
+                reader.scroll_down(1);
+            },
+            KeyCode::Up | KeyCode::Char('k') => {
+                reader.scroll_up(1);
+            },
+            KeyCode::PageDown
+            | KeyCode::Right
+            | KeyCode::Char('l')
+            | KeyCode::Char(' ') => {
+                reader.next_page();
+            },
+            KeyCode::PageUp | KeyCode::Left | KeyCode::Char('h') => {
+                reader.previous_page();
+            },
+            KeyCode::Home | KeyCode::Char('g') => {
+                reader.first_page();
+            },
+            KeyCode::End | KeyCode::Char('G') => {
+                reader.last_page();
+            },
+            KeyCode::Char(']') => {
+                reader.next_section();
+            },
+            KeyCode::Char('[') => {
+                reader.previous_section();
+            },
+            KeyCode::Char('n') => {
+                reader.find_next();
+            },
+            KeyCode::Char('N') => {
+                reader.find_previous();
+            },
+            KeyCode::Char('\'') => {
+                if !reader.next_bookmark() {
+                    self.state.prompt_title = "Bookmarks".to_string();
+                    self.state.prompt = "There are no bookmarks".to_string();
+                }
+            },
+            KeyCode::Char('/') => {
+                self.state.prompt_title = "Search".to_string();
+                self.state.prompt_action = Some(PromptAction::Search);
+                self.state.editing_prompt = true;
+                self.state.prompt.clear();
+            },
+            _ => {
+                self.state.should_redraw = false;
+            },
+        }
+    }
+
+    /// Opens the selected entry in the built-in reader if it is supported
+    /// and enabled, or in an external program otherwise.
+    fn open_entry(&mut self) {
+        if let Some(entry_index) = self.state.selected_entry() {
+            let entry = self.app.shelf.get_index(entry_index).unwrap();
+
+            match self.app.config.builtin_reader()
+                && Document::is_supported(&entry.path)
+            {
+                true => self.open_reader(),
+                false => self.open_entry_externally(),
+            }
+        }
+    }
+
+    /// Opens the selected entry in the built-in reader.
+    fn open_reader(&mut self) {
+        if let Some(entry_index) = self.state.selected_entry() {
+            let entry = self.app.shelf.get_index(entry_index).unwrap();
+            let entry_name = format_entry(entry_index, entry);
+
+            let document = match Document::open(&entry.path) {
+                Ok(document) if document.word_count() > 0 => document,
+                Ok(_) => {
+                    self.state.prompt_title = "Error".to_string();
+                    self.state.prompt = format!("'{}' has no text", entry_name);
+                    return;
                 },
-                KeyCode::Delete | KeyCode::Char('d') => {
-                    self.remove_entry();
+                Err(e) => {
+                    self.state.prompt_title = "Error".to_string();
+                    self.state.prompt =
+                        format!("Couldn't read '{}': {}", entry_name, e);
+                    return;
                 },
-                KeyCode::Char('a') => {
-                    self.state.prompt_title = "Add an entry".to_string();
-                    self.state.editing_prompt = true;
-                    self.state.prompt.clear();
+            };
+
+            let opened = self
+                .app
+                .mark_opened_index(entry_index)
+                .and_then(|()| Ok(self.app.shelf.get_index(entry_index)?));
+            let entry = match opened {
+                Ok(entry) => entry,
+                Err(e) => {
+                    self.state.prompt_title = "Error".to_string();
+                    self.state.prompt =
+                        format!("Couldn't open '{}': {}", entry_name, e);
+                    return;
                 },
-                KeyCode::Esc | KeyCode::Char('q') => {
-                    self.state.should_exit = true;
+            };
+
+            self.state.mode =
+                Mode::Reader(Box::new(Reader::new(entry, document)));
+            self.state.prompt_title = "Reading".to_string();
+            self.state.prompt = format!("Reading '{}'", entry_name);
+        }
+    }
+
+    /// Closes the built-in reader, and saves the progress along with the
+    /// reading session.
+    fn close_reader(&mut self) {
+        if let Mode::Reader(reader) =
+            replace(&mut self.state.mode, Mode::Browse)
+        {
+            let progress = reader.progress();
+            let saved = self
+                .app
+                .set_progress(&reader.entry, progress)
+                .and_then(|_| {
+                    self.app.record_session(&reader.entry, reader.session())
+                });
+
+            self.show_saved_progress(saved, progress);
+            self.get_entry_list();
+        }
+    }
+
+    /// Toggles a bookmark at the position of the built-in reader.
+    fn toggle_bookmark(&mut self) {
+        if let Mode::Reader(reader) = &mut self.state.mode {
+            let position = reader.position();
+
+            match self.app.toggle_bookmark(&reader.entry, position) {
+                Ok(added) => {
+                    reader.entry.toggle_bookmark(position);
+
+                    self.state.prompt_title = "Bookmarks".to_string();
+                    self.state.prompt = match added {
+                        true => "Added a bookmark".to_string(),
+                        false => "Removed the bookmark".to_string(),
+                    };
                 },
-                _ => {
-                    self.state.should_redraw = false;
+                Err(e) => {
+                    self.state.prompt_title = "Error".to_string();
+                    self.state.prompt =
+                        format!("Couldn't toggle the bookmark: {}", e);
                 },
             }
         }
     }
 
+    /// Searches for the text of the prompt in the built-in reader.
+    fn search(&mut self) {
+        if let Mode::Reader(reader) = &mut self.state.mode {
+            if !reader.search(&self.state.prompt) {
+                self.state.prompt_title = "Search".to_string();
+                self.state.prompt =
+                    format!("No matches for '{}'", self.state.prompt);
+            }
+        }
+    }
+
+    /// Shows the outcome of saving the reading progress in the prompt.
+    fn show_saved_progress(
+        &mut self,
+        saved: Result<(), Box<dyn std::error::Error>>,
+        progress: Progress,
+    ) {
+        match saved {
+            Ok(()) => {
+                self.state.prompt_title = "Success".to_string();
+                self.state.prompt = format!(
+                    "Saved the progress at {:.0}%",
+                    progress.fraction() * 100.0
+                );
+            },
+            Err(e) => {
+                self.state.prompt_title = "Error".to_string();
+                self.state.prompt =
+                    format!("Couldn't save the progress: {}", e);
+            },
+        }
+    }
+
+    /// Refreshes the entry list, keeping only the entries with the tag, in
+    /// the collection and matching the query filtered by in the sort order,
+    /// along with its title and the sidebar.
     fn get_entry_list(&mut self) {
-        self.state.entries.items = self
+        // The shelf may have changed, so the fields found in are read anew
+        self.state.find_fields.clear();
+
+        self.list_entries();
+    }
+
+    /// Refreshes the entry list like [`Tui::get_entry_list`], reusing the
+    /// fields of the entries read for finding them.
+    fn list_entries(&mut self) {
+        // The collection is looked up anew, as it may have changed or gone
+        let collection = match &self.state.collection_filter {
+            Some(name) => {
+                match self.app.shelf.parse_query(&format!("@{}", name)) {
+                    Ok(query) => Some((name.clone(), query)),
+                    Err(_) => None,
+                }
+            },
+            None => None,
+        };
+
+        if collection.is_none() {
+            self.state.collection_filter = None;
+        }
+
+        let tag_filter = self.state.tag_filter.as_ref();
+        let series_filter = self.state.series_filter.as_ref();
+        let query_filter = self.state.query_filter.as_ref();
+
+        let mut entries = self
             .app
             .list_entries()
-            .iter()
-            .map(|(_, entry)| {
-                entry
-                    .path
-                    .file_name()
-                    .unwrap()
-                    .to_str()
-                    .unwrap()
-                    .to_string()
+            .into_iter()
+            .filter(|(_, entry)| match tag_filter {
+                Some(tag) => entry.has_tag(tag),
+                None => true,
+            })
+            .filter(|(_, entry)| match &collection {
+                Some((_, query)) => query.matches(entry),
+                None => true,
+            })
+            .filter(|(_, entry)| match series_filter {
+                Some(name) => {
+                    let series = entry.series();
+
+                    matches!(series, Some(series) if &series.name == name)
+                },
+                None => true,
             })
+            .filter(|(_, entry)| match query_filter {
+                Some((_, query)) => query.matches(entry),
+                None => true,
+            })
+            .collect::<Vec<(usize, &Entry)>>();
+
+        // The entries found are ranked by how well they match instead
+        match &self.state.find {
+            Some(text) => {
+                let fields = &mut self.state.find_fields;
+
+                for (i, entry) in entries.iter() {
+                    fields.entry(*i).or_insert_with(|| Fields::of(entry));
+                }
+
+                rank(text, &mut entries, |(i, _)| &fields[i]);
+            },
+            None => {
+                // The entries of a series are in reading order unless sorted
+                let sort = match (self.state.sort, series_filter) {
+                    (None, Some(_)) => Some(SortKey::new(Key::Series)),
+                    (sort, _) => sort,
+                };
+
+                if let Some(sort) = sort {
+                    sort_by_keys(&mut entries, &[sort], |(_, entry)| entry);
+                }
+            },
+        }
+
+        self.state.entries.items = entries
+            .into_iter()
+            .map(|(i, entry)| (i + 1, list_item(entry)))
             .collect();
 
-        if self.state.entries.items.is_empty() {
-            self.state.entries.deselect();
+        let mut details = vec![];
+
+        if let Some(tag) = tag_filter {
+            details.push(tag.to_string());
+        }
+
+        if let Some((name, _)) = &collection {
+            details.push(format!("@{}", name));
+        }
+
+        if let Some(name) = series_filter {
+            details.push(name.clone());
+        }
+
+        if let Some((text, _)) = query_filter {
+            details.push(text.clone());
+        }
+
+        if let Some(text) = &self.state.find {
+            details.push(format!("/{}", text));
+        }
+
+        if let (Some(sort), None) = (self.state.sort, &self.state.find) {
+            details.push(format!("sorted by {}", sort));
+        }
+
+        // The shelf is named only if there are several to choose from
+        let title = match self.app.config.shelf_names().len() {
+            1 => "Bookshelf".to_string(),
+            _ => format!("Bookshelf '{}'", self.app.shelf_name),
+        };
+
+        self.state.title = match details.is_empty() {
+            true => title,
+            false => format!("{}: {}", title, details.join(" | ")),
+        };
+
+        match self.state.entries.state.selected() {
+            Some(_) if self.state.entries.items.is_empty() => {
+                self.state.entries.deselect()
+            },
+            // Last item was selected; select a new last item
+            Some(i) if i >= self.state.entries.items.len() => {
+                self.state.entries.last()
+            },
+            _ => {},
+        }
+
+        self.update_sidebar();
+
+        if self.state.sidebar.items.is_empty() {
+            self.state.focus = Focus::Entries;
         }
     }
 
-    fn open_entry(&mut self) {
-        if let Some(index) = self.state.entries.state.selected() {
-            let entry_index = index + 1;
-            let entry = self.app.shelf.get_index(entry_index).unwrap();
+    /// Opens the selected entry in an external program.
+    fn open_entry_externally(&mut self) {
+        if let Some(entry_index) = self.state.selected_entry() {
+            let entry_name = match self.app.shelf.get_index(entry_index) {
+                Ok(entry) => format_entry(entry_index, entry),
+                Err(_) => return,
+            };
+
+            let opened = match self.app.config.track_sessions() {
+                true => self.app.spawn_viewer_index(entry_index, None).map(
+                    |viewer| {
+                        let sessions_tx = self.sessions_tx.clone();
+
+                        // The viewer is waited on in the background, and the
+                        // session is recorded on one of the next ticks
+                        spawn_blocking(move || {
+                            if let Ok(session) = viewer.wait() {
+                                let _ = sessions_tx.send(session);
+                            }
+                        });
+                    },
+                ),
+                false => self.app.open_entry_index(entry_index, None),
+            };
 
-            self.app.open_entry(entry, None).unwrap();
-            self.state.prompt =
-                format!("Opened '{}'", format_entry(entry_index, entry));
+            match opened {
+                Ok(()) => {
+                    self.state.prompt_title = "Success".to_string();
+                    self.state.prompt = format!("Opened '{}'", entry_name);
+                },
+                Err(e) => {
+                    self.state.prompt_title = "Error".to_string();
+                    self.state.prompt =
+                        format!("Couldn't open '{}': {}", entry_name, e);
+                },
+            }
         }
     }
 
-    fn remove_entry(&mut self) {
-        if let Some(index) = self.state.entries.state.selected() {
-            let entry_index = index + 1;
+    /// Records the reading sessions of the viewers that have exited since
+    /// the last call. Returns whether any sessions were recorded.
+    fn record_sessions(&mut self) -> bool {
+        let mut recorded = false;
 
-            self.app.remove_entry_index(entry_index).unwrap();
+        while let Ok((entry, session)) = self.sessions_rx.try_recv() {
+            let duration = session.duration;
 
-            self.get_entry_list();
+            if duration < MIN_SESSION {
+                self.state.prompt_title = "Warning".to_string();
+                self.state.prompt = format!(
+                    "The viewer exited after {}, too soon to record a session",
+                    format_duration(duration)
+                );
+                continue;
+            }
 
-            // Last item was selected; select a new last item
-            if index == self.state.entries.items.len() {
-                self.state.entries.last();
+            if self.app.record_session(&entry, session).is_ok() {
+                self.state.prompt_title = "Success".to_string();
+                self.state.prompt = format!(
+                    "Recorded a reading session of {}",
+                    format_duration(duration)
+                );
+                recorded = true;
+            }
+        }
+
+        recorded
+    }
+
+    fn remove_entry(&mut self) {
+        if let Some(entry_index) = self.state.selected_entry() {
+            if let Err(e) = self.app.remove_entry_index(entry_index) {
+                self.state.prompt_title = "Error".to_string();
+                self.state.prompt = format!("Couldn't remove: {}", e);
+                return;
             }
+
+            self.get_entry_list();
         }
     }
+
+    /// Copies the BibTeX of the selected entry to the clipboard, or its
+    /// citation if `cite` is true.
+    fn copy_bib(&mut self, cite: bool) {
+        let entry = match self.state.selected_entry() {
+            Some(entry_index) => self.app.shelf.get_index(entry_index).unwrap(),
+            None => return,
+        };
+
+        let (what, text, error) = match cite {
+            true => ("citation", to_citation(entry), None),
+            false => {
+                let (bib_entry, error) = to_bib_entry(entry);
+                ("BibTeX", bib_entry, error)
+            },
+        };
+
+        match (copy(&text, self.app.config.clipboard_command()), error) {
+            (Ok(()), Some(e)) => {
+                self.state.prompt_title = "Warning".to_string();
+                self.state.prompt = format!(
+                    "Copied a @misc entry, the BibTeX couldn't be read: {}",
+                    e
+                );
+            },
+            (Ok(()), None) => {
+                self.state.prompt_title = "Success".to_string();
+                self.state.prompt = format!("Copied the {}", what);
+            },
+            (Err(e), _) => {
+                self.state.prompt_title = "Error".to_string();
+                self.state.prompt =
+                    format!("Couldn't copy the {}: {}", what, e);
+            },
+        }
+    }
+
+    /// Undoes the last change to the shelf, such as removing an entry, and
+    /// refreshes the entry list.
+    fn undo(&mut self) {
+        match self.app.undo() {
+            Ok(Some(change)) => {
+                self.state.prompt_title = "Success".to_string();
+                self.state.prompt = format!("Undid: {}", change);
+                self.get_entry_list();
+            },
+            Ok(None) => {
+                self.state.prompt_title = "Undo".to_string();
+                self.state.prompt = "There is nothing to undo".to_string();
+            },
+            Err(e) => {
+                self.state.prompt_title = "Error".to_string();
+                self.state.prompt = format!("Couldn't undo: {}", e);
+            },
+        }
+    }
+}
+
+/// Returns the text of an entry in the entry list: its file name, followed
+/// by its series, rating and reading progress, if any.
+fn list_item(entry: &Entry) -> String {
+    let mut item = entry
+        .path
+        .file_name()
+        .unwrap()
+        .to_str()
+        .unwrap()
+        .to_string();
+
+    if let Some(series) = entry.series() {
+        item.push_str(&format!(" ({})", series));
+    }
+
+    if let Some(rating) = entry.rating {
+        item.push_str(&format!(" {}", rating.to_star_string()));
+    }
+
+    if let Some(progress) = entry.progress {
+        item.push_str(&format!(" {:.0}%", progress.fraction() * 100.0));
+    }
+
+    item
 }
 
 pub(crate) fn setup_terminal(
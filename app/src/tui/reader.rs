@@ -0,0 +1,298 @@
+use std::time::Instant;
+
+use chrono::{DateTime, Utc};
+
+use lib::{
+    document::Document, entry::Entry, progress::Progress, session::Session,
+};
+
+use crate::utils::format::wrap;
+
+/// A line of a document laid out for the screen.
+#[derive(Clone)]
+pub(crate) struct Line {
+    /// The text of the line
+    pub(crate) text: String,
+    /// The index of the section the line belongs to
+    pub(crate) section: usize,
+    /// The number of words in the document before the line
+    pub(crate) word: usize,
+}
+
+/// A paginated view of an entry's document.
+#[derive(Clone)]
+pub(crate) struct Reader {
+    /// The entry being read
+    pub(crate) entry: Entry,
+    /// The document of the entry
+    document: Document,
+    /// The document laid out for the current width
+    pub(crate) lines: Vec<Line>,
+    /// The width the document is laid out for
+    width: usize,
+    /// The index of the first line shown
+    pub(crate) top: usize,
+    /// The number of lines that fit on the screen
+    pub(crate) page_height: usize,
+    /// The text searched for, lowercased
+    pub(crate) search: Option<String>,
+    /// The moment the reading started
+    started: DateTime<Utc>,
+    /// Measures the time spent reading
+    timer: Instant,
+}
+
+impl Reader {
+    /// Opens the document of the entry, resuming from the progress saved
+    /// for the entry, if any.
+    pub(crate) fn new(entry: &Entry, document: Document) -> Self {
+        let mut reader = Self {
+            entry: entry.clone(),
+            document,
+            lines: vec![],
+            width: 0,
+            top: 0,
+            page_height: 1,
+            search: None,
+            started: Utc::now(),
+            timer: Instant::now(),
+        };
+
+        reader.layout(80);
+
+        if let Some(progress) = entry.progress {
+            if !progress.is_complete() {
+                reader.scroll_to_word(progress.position);
+            }
+        }
+
+        reader
+    }
+
+    /// Lays the document out for the width provided, keeping the position.
+    pub(crate) fn layout(&mut self, width: usize) {
+        if width == self.width || width == 0 {
+            return;
+        }
+
+        let position = self.position();
+
+        self.width = width;
+        self.lines = lay_out(&self.document, width);
+        self.scroll_to_word(position);
+    }
+
+    /// Returns the number of words before the first line shown.
+    pub(crate) fn position(&self) -> usize {
+        self.lines.get(self.top).map_or(0, |line| line.word)
+    }
+
+    /// Returns the lines shown on the screen.
+    pub(crate) fn page(&self) -> &[Line] {
+        let end = (self.top + self.page_height).min(self.lines.len());
+
+        &self.lines[self.top..end]
+    }
+
+    /// Returns whether the last line of the document is shown.
+    pub(crate) fn is_at_end(&self) -> bool {
+        self.top + self.page_height >= self.lines.len()
+    }
+
+    /// Returns the title of the section shown, along with its number.
+    pub(crate) fn section_title(&self) -> String {
+        let section = self.lines.get(self.top).map_or(0, |line| line.section);
+        let count = self.document.sections.len();
+
+        match &self.document.sections[section].title {
+            Some(title) => format!("{} ({}/{})", title, section + 1, count),
+            None => format!("Section {}/{}", section + 1, count),
+        }
+    }
+
+    pub(crate) fn scroll_down(&mut self, lines: usize) {
+        let last_top = self.lines.len().saturating_sub(self.page_height);
+
+        self.top = (self.top + lines).min(last_top.max(self.top));
+    }
+
+    pub(crate) fn scroll_up(&mut self, lines: usize) {
+        self.top = self.top.saturating_sub(lines);
+    }
+
+    pub(crate) fn next_page(&mut self) {
+        self.scroll_down(self.page_height);
+    }
+
+    pub(crate) fn previous_page(&mut self) {
+        self.scroll_up(self.page_height);
+    }
+
+    pub(crate) fn first_page(&mut self) {
+        self.top = 0;
+    }
+
+    pub(crate) fn last_page(&mut self) {
+        self.top = self.lines.len().saturating_sub(self.page_height);
+    }
+
+    /// Goes to the beginning of the next section, if any.
+    pub(crate) fn next_section(&mut self) {
+        let section = self.lines.get(self.top).map_or(0, |line| line.section);
+
+        if let Some(top) =
+            self.lines.iter().position(|line| line.section > section)
+        {
+            self.top = top;
+        }
+    }
+
+    /// Goes to the beginning of the section shown, or to the beginning of
+    /// the previous one if the section shown starts at the top.
+    pub(crate) fn previous_section(&mut self) {
+        let section = self.lines.get(self.top).map_or(0, |line| line.section);
+        let start = self
+            .lines
+            .iter()
+            .position(|line| line.section == section)
+            .unwrap_or(0);
+
+        self.top = match start < self.top || section == 0 {
+            true => start,
+            false => self
+                .lines
+                .iter()
+                .position(|line| line.section == section - 1)
+                .unwrap_or(0),
+        };
+    }
+
+    /// Starts searching for the text provided, and goes to its first
+    /// occurrence from the top of the page. Returns whether it was found.
+    pub(crate) fn search(&mut self, text: &str) -> bool {
+        self.search = Some(text.to_lowercase());
+
+        self.find(self.top, true)
+    }
+
+    /// Goes to the next occurrence of the text searched for, wrapping around
+    /// the end of the document. Returns whether it was found.
+    pub(crate) fn find_next(&mut self) -> bool {
+        self.find(self.top + 1, true)
+    }
+
+    /// Goes to the previous occurrence of the text searched for, wrapping
+    /// around the beginning of the document. Returns whether it was found.
+    pub(crate) fn find_previous(&mut self) -> bool {
+        self.find(self.top + self.lines.len() - 1, false)
+    }
+
+    /// Finds the closest line containing the text searched for, starting
+    /// from the line provided (modulo the number of lines), and moves it to
+    /// the top of the page.
+    fn find(&mut self, from: usize, forward: bool) -> bool {
+        let search = match &self.search {
+            Some(search) if !search.is_empty() => search,
+            _ => return false,
+        };
+        let count = self.lines.len();
+
+        let found = (0..count)
+            .map(|i| match forward {
+                true => (from + i) % count,
+                false => (from + count - i) % count,
+            })
+            .find(|&i| self.lines[i].text.to_lowercase().contains(search));
+
+        if let Some(top) = found {
+            self.top = top;
+        }
+
+        found.is_some()
+    }
+
+    /// Goes to the first bookmark after the position, wrapping around the
+    /// end of the document. Returns whether there are any bookmarks.
+    pub(crate) fn next_bookmark(&mut self) -> bool {
+        let position = self.position();
+        let bookmarks = &self.entry.bookmarks;
+
+        let bookmark = bookmarks
+            .iter()
+            .find(|bookmark| bookmark.position > position)
+            .or_else(|| bookmarks.first());
+
+        match bookmark {
+            Some(bookmark) => {
+                self.scroll_to_word(bookmark.position);
+                true
+            },
+            None => false,
+        }
+    }
+
+    /// Returns whether the position is bookmarked.
+    pub(crate) fn is_bookmarked(&self) -> bool {
+        let position = self.position();
+
+        self.entry
+            .bookmarks
+            .iter()
+            .any(|bookmark| bookmark.position == position)
+    }
+
+    /// Returns the progress through the entry. The whole entry counts as
+    /// read once its last page is shown.
+    pub(crate) fn progress(&self) -> Progress {
+        let total = self.document.word_count();
+
+        match self.is_at_end() {
+            true => Progress::new(total, total),
+            false => Progress::new(self.position(), total),
+        }
+    }
+
+    /// Returns the reading session from the moment the entry was opened.
+    pub(crate) fn session(&self) -> Session {
+        Session::new(self.started, self.timer.elapsed())
+    }
+
+    /// Moves the line containing the word provided to the top of the page.
+    fn scroll_to_word(&mut self, word: usize) {
+        self.top = self
+            .lines
+            .partition_point(|line| line.word <= word)
+            .saturating_sub(1);
+    }
+}
+
+/// Wraps the lines of every section of the document to the width provided,
+/// keeping track of the words before each line. Sections are separated by
+/// blank lines.
+fn lay_out(document: &Document, width: usize) -> Vec<Line> {
+    let mut lines = vec![];
+    let mut word = 0;
+
+    for (section, contents) in document.sections.iter().enumerate() {
+        if section > 0 {
+            lines.push(Line {
+                text: String::new(),
+                section: section - 1,
+                word,
+            });
+        }
+
+        for text in wrap(&contents.text, width) {
+            let words = text.split_whitespace().count();
+
+            lines.push(Line {
+                text,
+                section,
+                word,
+            });
+            word += words;
+        }
+    }
+
+    lines
+}
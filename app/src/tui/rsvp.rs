@@ -0,0 +1,119 @@
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, Utc};
+
+use lib::{entry::Entry, progress::Progress, session::Session};
+
+/// The number of words skipped when rewinding or fast-forwarding
+const SKIP_WORDS: usize = 10;
+
+/// The change of speed per key press, in words per minute
+const WPM_STEP: u32 = 25;
+
+/// The slowest and the fastest speeds, in words per minute
+const WPM_RANGE: (u32, u32) = (50, 1500);
+
+/// Rapid serial visual presentation of an entry: its words are flashed one
+/// by one at a constant speed.
+#[derive(Clone)]
+pub(crate) struct Rsvp {
+    /// The entry being read
+    pub(crate) entry: Entry,
+    /// The words of the entry
+    pub(crate) words: Vec<String>,
+    /// The index of the word shown
+    pub(crate) position: usize,
+    /// The speed in words per minute
+    pub(crate) wpm: u32,
+    /// Whether the presentation is paused
+    pub(crate) paused: bool,
+    /// The moment the reading started
+    started: DateTime<Utc>,
+    /// Measures the time spent reading
+    timer: Instant,
+}
+
+impl Rsvp {
+    /// Starts a paused presentation of the words provided, resuming from
+    /// the progress saved for the entry, if any.
+    pub(crate) fn new(entry: &Entry, words: Vec<String>, wpm: u32) -> Self {
+        let position = match entry.progress {
+            Some(progress) if !progress.is_complete() => progress.position,
+            _ => 0,
+        };
+
+        Self {
+            entry: entry.clone(),
+            position: position.min(words.len().saturating_sub(1)),
+            words,
+            wpm: wpm.clamp(WPM_RANGE.0, WPM_RANGE.1),
+            paused: true,
+            started: Utc::now(),
+            timer: Instant::now(),
+        }
+    }
+
+    /// Returns the word shown.
+    pub(crate) fn word(&self) -> &str {
+        self.words.get(self.position).map_or("", String::as_str)
+    }
+
+    /// Returns how long each word is shown for.
+    pub(crate) fn interval(&self) -> Duration {
+        Duration::from_millis(60_000 / self.wpm as u64)
+    }
+
+    /// Shows the next word, pausing at the last one. Returns whether the
+    /// word shown has changed.
+    pub(crate) fn advance(&mut self) -> bool {
+        if self.paused {
+            return false;
+        }
+
+        if self.position + 1 >= self.words.len() {
+            self.paused = true;
+            return false;
+        }
+
+        self.position += 1;
+
+        true
+    }
+
+    pub(crate) fn toggle_pause(&mut self) {
+        self.paused = !self.paused;
+    }
+
+    pub(crate) fn rewind(&mut self) {
+        self.position = self.position.saturating_sub(SKIP_WORDS);
+    }
+
+    pub(crate) fn fast_forward(&mut self) {
+        self.position = (self.position + SKIP_WORDS)
+            .min(self.words.len().saturating_sub(1));
+    }
+
+    pub(crate) fn speed_up(&mut self) {
+        self.wpm = (self.wpm + WPM_STEP).min(WPM_RANGE.1);
+    }
+
+    pub(crate) fn slow_down(&mut self) {
+        self.wpm = self.wpm.saturating_sub(WPM_STEP).max(WPM_RANGE.0);
+    }
+
+    /// Returns the progress through the entry. The last word counts as read
+    /// once it has been shown.
+    pub(crate) fn progress(&self) -> Progress {
+        let read = match self.position + 1 == self.words.len() {
+            true => self.words.len(),
+            false => self.position,
+        };
+
+        Progress::new(read, self.words.len())
+    }
+
+    /// Returns the reading session from the start of the presentation.
+    pub(crate) fn session(&self) -> Session {
+        Session::new(self.started, self.timer.elapsed())
+    }
+}
@@ -1,12 +1,104 @@
+use std::collections::{HashMap, HashSet};
+
 use tui::widgets::ListState;
 
+use lib::{
+    fuzzy::Fields,
+    query::Query,
+    sort::SortKey,
+    tag::{Tag, TagTree},
+};
+
+use super::{reader::Reader, rsvp::Rsvp};
+
+/// What the TUI is showing at the moment.
+#[derive(Clone)]
+pub(crate) enum Mode {
+    /// Browsing the entries on the shelf
+    Browse,
+    /// Speed reading an entry
+    Rsvp(Box<Rsvp>),
+    /// Reading an entry in the built-in reader
+    Reader(Box<Reader>),
+}
+
+/// What to do with the text of the prompt once it is entered.
+#[derive(Clone, Copy, PartialEq)]
+pub(crate) enum PromptAction {
+    /// Search for the text in the reader
+    Search,
+    /// Filter the entries by the query
+    Filter,
+    /// Switch to the named shelf
+    SwitchShelf,
+    /// Narrow the entries down to the ones matching the text as it's typed
+    Find,
+}
+
+/// Which list receives the navigation keys while browsing.
+#[derive(Clone, Copy, PartialEq)]
+pub(crate) enum Focus {
+    /// The list of the entries
+    Entries,
+    /// The collections and the tag tree in the sidebar
+    Sidebar,
+}
+
+/// A row of the sidebar.
+#[derive(Clone)]
+pub(crate) enum SidebarItem {
+    /// A saved collection, along with the number of entries in it
+    Collection(String, usize),
+    /// A series, along with the number of entries in it
+    Series(String, usize),
+    /// A row of the tag tree
+    Tag(TagItem),
+}
+
+/// A row of the tag tree in the sidebar.
+#[derive(Clone)]
+pub(crate) struct TagItem {
+    /// The tag of the row
+    pub(crate) tag: Tag,
+    /// The number of entries with the tag or any of its subtags
+    pub(crate) count: usize,
+    /// How deep the tag is nested in the tree
+    pub(crate) depth: usize,
+    /// Whether the tag has any subtags
+    pub(crate) has_children: bool,
+}
+
 #[derive(Clone)]
 pub(crate) struct State {
     pub(crate) title: String,
+    pub(crate) mode: Mode,
+    pub(crate) focus: Focus,
     pub(crate) should_exit: bool,
     pub(crate) should_redraw: bool,
-    pub(crate) entries: StatefulList<String>,
+    /// The entries shown, along with their indices on the shelf
+    pub(crate) entries: StatefulList<(usize, String)>,
+    /// The collections and the series, followed by the visible rows of the
+    /// tag tree
+    pub(crate) sidebar: StatefulList<SidebarItem>,
+    /// The tags whose subtrees are collapsed in the tag tree
+    pub(crate) collapsed_tags: HashSet<Tag>,
+    /// The tag which the entries are filtered by
+    pub(crate) tag_filter: Option<Tag>,
+    /// The name of the collection which the entries are filtered by
+    pub(crate) collection_filter: Option<String>,
+    /// The name of the series which the entries are filtered by
+    pub(crate) series_filter: Option<String>,
+    /// The query which the entries are filtered by, along with its text
+    pub(crate) query_filter: Option<(String, Query)>,
+    /// The text the entries are fuzzy-matched against and ranked by
+    pub(crate) find: Option<String>,
+    /// The fields of the entries matched against the text found, by their
+    /// indices, kept while the text is typed
+    pub(crate) find_fields: HashMap<usize, Fields>,
+    /// The key the entries are sorted by, if not in the order of the shelf
+    pub(crate) sort: Option<SortKey>,
     pub(crate) editing_prompt: bool,
+    pub(crate) prompt_action: Option<PromptAction>,
     pub(crate) prompt_title: String,
     pub(crate) prompt: String,
 }
@@ -15,23 +107,101 @@ impl Default for State {
     fn default() -> Self {
         Self {
             title: String::from("Bookshelf"),
+            mode: Mode::Browse,
+            focus: Focus::Entries,
             should_exit: false,
             should_redraw: false,
             entries: StatefulList::default(),
+            sidebar: StatefulList::default(),
+            collapsed_tags: HashSet::new(),
+            tag_filter: None,
+            collection_filter: None,
+            series_filter: None,
+            query_filter: None,
+            find: None,
+            find_fields: HashMap::new(),
+            sort: None,
             editing_prompt: false,
+            prompt_action: None,
             prompt_title: "Prompt".to_string(),
             prompt: "".to_string(),
         }
     }
 }
 
-#[derive(Default, Clone)]
+impl State {
+    /// Returns the shelf index of the selected entry, if any.
+    pub(crate) fn selected_entry(&self) -> Option<usize> {
+        self.entries.selected().map(|(index, _)| *index)
+    }
+
+    /// Rebuilds the rows of the sidebar: the collections and the series,
+    /// along with the number of entries in them, followed by the tag tree,
+    /// skipping the subtrees of the collapsed tags.
+    pub(crate) fn set_sidebar(
+        &mut self,
+        collections: Vec<(String, usize)>,
+        series: Vec<(String, usize)>,
+        tree: &[TagTree],
+    ) {
+        fn flatten(
+            tree: &[TagTree],
+            collapsed: &HashSet<Tag>,
+            items: &mut Vec<SidebarItem>,
+        ) {
+            for node in tree {
+                items.push(SidebarItem::Tag(TagItem {
+                    tag: node.tag.clone(),
+                    count: node.count,
+                    depth: node.tag.depth(),
+                    has_children: !node.children.is_empty(),
+                }));
+
+                if !collapsed.contains(&node.tag) {
+                    flatten(&node.children, collapsed, items);
+                }
+            }
+        }
+
+        self.sidebar.items = collections
+            .into_iter()
+            .map(|(name, count)| SidebarItem::Collection(name, count))
+            .chain(
+                series
+                    .into_iter()
+                    .map(|(name, count)| SidebarItem::Series(name, count)),
+            )
+            .collect();
+        flatten(tree, &self.collapsed_tags, &mut self.sidebar.items);
+
+        match self.sidebar.state.selected() {
+            Some(_) if self.sidebar.items.is_empty() => self.sidebar.deselect(),
+            Some(i) if i >= self.sidebar.items.len() => self.sidebar.last(),
+            _ => {},
+        }
+    }
+}
+
+#[derive(Clone)]
 pub struct StatefulList<T> {
     pub state: ListState,
     pub items: Vec<T>,
 }
 
+impl<T> Default for StatefulList<T> {
+    fn default() -> Self {
+        Self {
+            state: ListState::default(),
+            items: vec![],
+        }
+    }
+}
+
 impl<T> StatefulList<T> {
+    pub fn selected(&self) -> Option<&T> {
+        self.state.selected().and_then(|i| self.items.get(i))
+    }
+
     pub fn deselect(&mut self) {
         self.state.select(None);
     }
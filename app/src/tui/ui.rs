@@ -2,13 +2,18 @@ use std::ops::Deref;
 
 use tui::{
     backend::Backend,
-    layout::{Constraint, Direction, Layout},
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
-    widgets::{Block, Borders, List, ListItem, Paragraph},
+    text::{Span, Spans},
+    widgets::{Block, Borders, Gauge, List, ListItem, Paragraph},
     Frame,
 };
 
-use super::state::State;
+use super::{
+    reader::Reader,
+    rsvp::Rsvp,
+    state::{Focus, Mode, SidebarItem, State},
+};
 
 pub(crate) fn ui<B: Backend>(f: &mut Frame<B>, state: &mut State) {
     let chunks = Layout::default()
@@ -16,11 +21,49 @@ pub(crate) fn ui<B: Backend>(f: &mut Frame<B>, state: &mut State) {
         .constraints([Constraint::Min(0), Constraint::Length(3)].as_ref())
         .split(f.size());
 
+    match &mut state.mode {
+        Mode::Browse => entries_ui(f, chunks[0], state),
+        Mode::Rsvp(rsvp) => rsvp_ui(f, chunks[0], rsvp),
+        Mode::Reader(reader) => reader_ui(f, chunks[0], reader),
+    }
+
+    let prompt = Paragraph::new(state.prompt.as_ref())
+        .style(match state.editing_prompt {
+            false => Style::default(),
+            true => Style::default().fg(Color::Yellow),
+        })
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(state.prompt_title.clone()),
+        );
+
+    f.render_widget(prompt, chunks[1]);
+}
+
+fn entries_ui<B: Backend>(f: &mut Frame<B>, area: Rect, state: &mut State) {
+    // The sidebar is shown if there are any collections or tagged entries
+    let area = match state.sidebar.items.is_empty() {
+        true => area,
+        false => {
+            let chunks = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints(
+                    [Constraint::Percentage(25), Constraint::Min(0)].as_ref(),
+                )
+                .split(area);
+
+            sidebar_ui(f, chunks[0], state);
+
+            chunks[1]
+        },
+    };
+
     let items = state
         .entries
         .items
         .iter()
-        .map(|entry| ListItem::new(entry.deref()))
+        .map(|(_, entry)| ListItem::new(entry.deref()))
         .collect::<Vec<ListItem>>();
     let list = List::new(items)
         .block(
@@ -36,17 +79,240 @@ pub(crate) fn ui<B: Backend>(f: &mut Frame<B>, state: &mut State) {
                 .fg(Color::Black),
         );
 
-    let prompt = Paragraph::new(state.prompt.as_ref())
-        .style(match state.editing_prompt {
-            false => Style::default(),
-            true => Style::default().fg(Color::Yellow),
+    f.render_stateful_widget(list, area, &mut state.entries.state);
+}
+
+fn sidebar_ui<B: Backend>(f: &mut Frame<B>, area: Rect, state: &mut State) {
+    let items = state
+        .sidebar
+        .items
+        .iter()
+        .map(|item| match item {
+            SidebarItem::Collection(name, count) => {
+                let style = match state.collection_filter.as_ref() == Some(name)
+                {
+                    true => Style::default().fg(Color::LightGreen),
+                    false => Style::default().fg(Color::Cyan),
+                };
+
+                ListItem::new(format!("@{} ({})", name, count)).style(style)
+            },
+            SidebarItem::Series(name, count) => {
+                let style = match state.series_filter.as_ref() == Some(name) {
+                    true => Style::default().fg(Color::LightGreen),
+                    false => Style::default().fg(Color::Magenta),
+                };
+
+                ListItem::new(format!("{} ({})", name, count)).style(style)
+            },
+            SidebarItem::Tag(item) => {
+                let marker = match item.has_children {
+                    false => " ",
+                    true if state.collapsed_tags.contains(&item.tag) => "▸",
+                    true => "▾",
+                };
+                let style = match state.tag_filter.as_ref() == Some(&item.tag) {
+                    true => Style::default().fg(Color::LightGreen),
+                    false => Style::default(),
+                };
+
+                ListItem::new(format!(
+                    "{}{} {} ({})",
+                    "  ".repeat(item.depth),
+                    marker,
+                    item.tag.name(),
+                    item.count
+                ))
+                .style(style)
+            },
         })
+        .collect::<Vec<ListItem>>();
+    let list = List::new(items)
         .block(
             Block::default()
+                .title("Filters")
                 .borders(Borders::ALL)
-                .title(state.prompt_title.clone()),
+                .border_style(match state.focus {
+                    Focus::Sidebar => Style::default().fg(Color::Yellow),
+                    Focus::Entries => Style::default(),
+                }),
+        )
+        .style(Style::default().fg(Color::White))
+        .highlight_style(
+            Style::default()
+                .add_modifier(Modifier::BOLD)
+                .bg(Color::LightGreen)
+                .fg(Color::Black),
         );
 
-    f.render_stateful_widget(list, chunks[0], &mut state.entries.state);
-    f.render_widget(prompt, chunks[1]);
+    f.render_stateful_widget(list, area, &mut state.sidebar.state);
+}
+
+fn rsvp_ui<B: Backend>(f: &mut Frame<B>, area: Rect, rsvp: &Rsvp) {
+    let title = rsvp
+        .entry
+        .path
+        .file_name()
+        .unwrap()
+        .to_string_lossy()
+        .into_owned();
+    let block = Block::default().title(title).borders(Borders::ALL);
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(
+            [
+                Constraint::Min(0),
+                Constraint::Length(1),
+                Constraint::Length(1),
+            ]
+            .as_ref(),
+        )
+        .split(block.inner(area));
+
+    f.render_widget(block, area);
+
+    // The word is shown in the middle, with its pivot letter highlighted
+    let word = rsvp.word();
+    let pivot = pivot_index(word);
+    let mut lines = vec![Spans::default(); chunks[0].height as usize / 2];
+    lines.push(Spans::from(vec![
+        Span::raw(word.chars().take(pivot).collect::<String>()),
+        Span::styled(
+            word.chars().skip(pivot).take(1).collect::<String>(),
+            Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+        ),
+        Span::raw(word.chars().skip(pivot + 1).collect::<String>()),
+    ]));
+
+    let word = Paragraph::new(lines).alignment(Alignment::Center);
+
+    let progress = rsvp.progress();
+    let gauge = Gauge::default()
+        .gauge_style(Style::default().fg(Color::LightGreen).bg(Color::Black))
+        .ratio(progress.fraction())
+        .label(format!(
+            "{}/{} words, {} WPM{}",
+            progress.position,
+            progress.total,
+            rsvp.wpm,
+            if rsvp.paused { ", paused" } else { "" }
+        ));
+
+    let help = Paragraph::new(
+        "space: pause, h/l: rewind/skip, k/j: faster/slower, q: back",
+    )
+    .alignment(Alignment::Center)
+    .style(Style::default().fg(Color::DarkGray));
+
+    f.render_widget(word, chunks[0]);
+    f.render_widget(gauge, chunks[1]);
+    f.render_widget(help, chunks[2]);
+}
+
+fn reader_ui<B: Backend>(f: &mut Frame<B>, area: Rect, reader: &mut Reader) {
+    let title = reader
+        .entry
+        .path
+        .file_name()
+        .unwrap()
+        .to_string_lossy()
+        .into_owned();
+    let block = Block::default().title(title).borders(Borders::ALL);
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(
+            [
+                Constraint::Min(0),
+                Constraint::Length(1),
+                Constraint::Length(1),
+            ]
+            .as_ref(),
+        )
+        .split(block.inner(area));
+
+    f.render_widget(block, area);
+
+    // The page is laid out for the space available
+    reader.layout(chunks[0].width.saturating_sub(2) as usize);
+    reader.page_height = (chunks[0].height as usize).max(1);
+
+    let lines = reader
+        .page()
+        .iter()
+        .map(|line| highlight(&line.text, reader.search.as_deref()))
+        .collect::<Vec<Spans>>();
+    let page = Paragraph::new(lines);
+
+    let status = format!(
+        "{}, {:.0}%{}, {} bookmarks",
+        reader.section_title(),
+        reader.progress().fraction() * 100.0,
+        if reader.is_bookmarked() {
+            ", bookmarked"
+        } else {
+            ""
+        },
+        reader.entry.bookmarks.len()
+    );
+    let status = Paragraph::new(status)
+        .alignment(Alignment::Center)
+        .style(Style::default().fg(Color::LightGreen));
+
+    let help = Paragraph::new(
+        "space/h/l: page, [/]: chapter, /: search, n/N: next/previous match, \
+         m: bookmark, ': next bookmark, q: back",
+    )
+    .alignment(Alignment::Center)
+    .style(Style::default().fg(Color::DarkGray));
+
+    let page_area = Rect {
+        x: chunks[0].x + 1,
+        width: chunks[0].width.saturating_sub(2),
+        ..chunks[0]
+    };
+
+    f.render_widget(page, page_area);
+    f.render_widget(status, chunks[1]);
+    f.render_widget(help, chunks[2]);
+}
+
+/// Splits a line into spans, highlighting the case-insensitive occurrences
+/// of the text searched for, if any.
+fn highlight<'a>(line: &'a str, search: Option<&str>) -> Spans<'a> {
+    let lowercase = line.to_lowercase();
+
+    let search = match search {
+        // Byte offsets only match if lowercasing keeps the length
+        Some(search) if !search.is_empty() && lowercase.len() == line.len() => {
+            search
+        },
+        _ => return Spans::from(line),
+    };
+
+    let style = Style::default().bg(Color::Yellow).fg(Color::Black);
+    let mut spans = vec![];
+    let mut start = 0;
+
+    for (offset, found) in lowercase.match_indices(search) {
+        spans.push(Span::raw(&line[start..offset]));
+        spans.push(Span::styled(&line[offset..offset + found.len()], style));
+        start = offset + found.len();
+    }
+    spans.push(Span::raw(&line[start..]));
+
+    Spans::from(spans)
+}
+
+/// Returns the index of the letter the eye should focus on in a word,
+/// slightly left of its middle.
+fn pivot_index(word: &str) -> usize {
+    match word.chars().count() {
+        0..=1 => 0,
+        2..=5 => 1,
+        6..=9 => 2,
+        10..=13 => 3,
+        _ => 4,
+    }
 }
@@ -0,0 +1,97 @@
+use std::{
+    env::var_os,
+    fs::{File, OpenOptions},
+    io::Write,
+    process::{Command, Stdio},
+};
+
+/// The error when there is neither a terminal nor a command to copy with
+const NO_TERMINAL: &str = "There is no terminal to copy with, set \
+                           clipboard_command in the config instead";
+
+/// Copies the text to the clipboard: by asking the terminal to with an OSC
+/// 52 escape sequence, which works over SSH and in tmux as well, or by
+/// piping it into the command provided if there is no terminal.
+///
+/// # Errors
+///
+/// This function will return an error if writing the escape sequence or
+/// the command fails, or if there is neither a terminal nor a command.
+pub(crate) fn copy(
+    text: &str,
+    command: Option<&str>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    match (OpenOptions::new().write(true).open("/dev/tty"), command) {
+        (Ok(terminal), _) => copy_with_terminal(text, terminal),
+        (Err(_), Some(command)) => copy_with_command(text, command),
+        (Err(_), None) => Err(NO_TERMINAL.into()),
+    }
+}
+
+/// Pipes the text into the command, which may come with arguments, e.g.
+/// `xclip -selection clipboard`, and waits for it to exit.
+fn copy_with_command(
+    text: &str,
+    command: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut words = command.split_whitespace();
+    let program = words.next().ok_or("The clipboard command is empty")?;
+
+    let mut child = Command::new(program)
+        .args(words)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .spawn()
+        .map_err(|e| format!("Couldn't run '{}': {}", command, e))?;
+
+    // The pipe is closed once written to, so that the command can exit
+    child.stdin.take().unwrap().write_all(text.as_bytes())?;
+    let status = child.wait()?;
+
+    if !status.success() {
+        return Err(format!("'{}' exited with {}", command, status).into());
+    }
+
+    Ok(())
+}
+
+/// Writes the OSC 52 escape sequence setting the clipboard to the text
+/// straight to the terminal, so that the output of the program is left
+/// alone.
+fn copy_with_terminal(
+    text: &str,
+    mut terminal: File,
+) -> Result<(), Box<dyn std::error::Error>> {
+    terminal.write_all(osc52(text, var_os("TMUX").is_some()).as_bytes())?;
+    terminal.flush()?;
+
+    Ok(())
+}
+
+/// Builds the OSC 52 escape sequence setting the clipboard to the text. In
+/// tmux, the sequence is wrapped to be passed through to the terminal
+/// outside it.
+fn osc52(text: &str, tmux: bool) -> String {
+    let sequence = format!("\x1b]52;c;{}\x07", base64::encode(text));
+
+    match tmux {
+        true => {
+            format!("\x1bPtmux;{}\x1b\\", sequence.replace('\x1b', "\x1b\x1b"))
+        },
+        false => sequence,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn osc52_sequences() {
+        assert_eq!(osc52("Dune", false), "\x1b]52;c;RHVuZQ==\x07");
+        assert_eq!(
+            osc52("Dune", true),
+            "\x1bPtmux;\x1b\x1b]52;c;RHVuZQ==\x07\x1b\\"
+        );
+    }
+}
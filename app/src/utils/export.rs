@@ -0,0 +1,109 @@
+use lib::entry::{Entry, Error as EntryError};
+
+/// Serializes the entries provided into a BibLaTeX bibliography. Entries
+/// with a linked BibTeX file are exported as is, the rest are exported as
+/// `@misc` entries. The rating and the review are added as custom fields.
+/// Also returns the entries whose BibTeX files couldn't be read, which are
+/// exported as `@misc` entries as well, along with the errors.
+pub(crate) fn to_bib<'a>(
+    entries: &[&'a Entry],
+) -> (String, Vec<(&'a Entry, EntryError)>) {
+    let mut failed = vec![];
+
+    let bib = entries
+        .iter()
+        .map(|entry| {
+            let (bib_entry, error) = to_bib_entry(entry);
+
+            if let Some(e) = error {
+                failed.push((*entry, e));
+            }
+
+            // Strip the closing brace to append the custom fields
+            let mut bib_entry = bib_entry
+                .trim_end()
+                .strip_suffix('}')
+                .unwrap_or_default()
+                .trim_end()
+                .to_string();
+
+            if let Some(rating) = entry.rating {
+                bib_entry.push_str(&format!("\nrating = {{{}}},", rating));
+            }
+
+            if let Some(review) = &entry.review {
+                bib_entry.push_str(&format!(
+                    "\nreview = {{{}}},",
+                    escape_bib_value(review)
+                ));
+            }
+
+            bib_entry.push_str("\n}\n");
+
+            bib_entry
+        })
+        .collect::<Vec<String>>()
+        .join("\n");
+
+    (bib, failed)
+}
+
+/// Serializes the BibLaTeX entry of an entry as is if it has a linked
+/// BibTeX file, or a minimal `@misc` entry otherwise. If the BibTeX file
+/// can't be read, the `@misc` entry is returned along with the error.
+pub(crate) fn to_bib_entry(entry: &Entry) -> (String, Option<EntryError>) {
+    match entry.try_get_bib_entry() {
+        Ok(Some(bib_entry)) => (bib_entry.to_biblatex_string(), None),
+        Ok(None) => (misc_bib_entry(entry), None),
+        Err(e) => (misc_bib_entry(entry), Some(e)),
+    }
+}
+
+/// Formats a citation of an entry from its authors, year and title, e.g.
+/// `Harold Abelson and Gerald Jay Sussman (1996). Structure and
+/// Interpretation of Computer Programs.`
+pub(crate) fn to_citation(entry: &Entry) -> String {
+    let mut citation = match entry.authors().as_slice() {
+        [] => String::new(),
+        [author] => author.clone(),
+        [authors @ .., last] => format!("{} and {}", authors.join(", "), last),
+    };
+
+    if let Some(year) = entry.year() {
+        citation = format!("{} ({})", citation, year).trim_start().to_string();
+    }
+
+    if !citation.is_empty() {
+        citation.push_str(". ");
+    }
+
+    citation + entry.title().trim_end_matches('.') + "."
+}
+
+/// Builds a minimal `@misc` BibLaTeX entry for an entry without a linked
+/// BibTeX file, using its file name as the cite key and the title.
+fn misc_bib_entry(entry: &Entry) -> String {
+    let cite_key = entry
+        .path
+        .file_stem()
+        .expect("The file has been validated and must have a name")
+        .to_string_lossy()
+        .replace(char::is_whitespace, "_");
+    let title = entry
+        .path
+        .file_name()
+        .expect("The file has been validated and must have a name")
+        .to_string_lossy();
+
+    format!(
+        "@misc{{{},\ntitle = {{{}}},\nfile = {{{}}},\n}}",
+        cite_key,
+        escape_bib_value(&title),
+        entry.path.to_string_lossy()
+    )
+}
+
+/// Escapes the braces in a BibLaTeX field value.
+fn escape_bib_value(value: &str) -> String {
+    value.replace('{', "\\{").replace('}', "\\}")
+}
@@ -1,19 +1,149 @@
+use std::time::Duration;
+
+use chrono::{DateTime, Local, Utc};
+
 use lib::entry::Entry;
 
+/// Names an entry by its index and its title, as by [`format_title`].
 pub(crate) fn format_entry(entry_index: usize, entry: &Entry) -> String {
-    let bib_entry = entry.get_bib_entry();
+    format!("{} - {}", entry_index, format_title(entry))
+}
 
-    let entry_file_name = if bib_entry.is_some() {
-        // TODO: make use of the BibTeX file to name the entry
-        todo!()
-    } else {
-        entry
+/// Names an entry by its title if it has a BibTeX file or a title set by
+/// hand, or its file name otherwise.
+pub(crate) fn format_title(entry: &Entry) -> String {
+    match entry.title.is_some() || entry.bib_path.is_some() {
+        true => entry.title(),
+        false => entry
             .path
             .file_name()
             .expect("The file has been validated and must have a name")
             .to_str()
             .unwrap()
-    };
+            .to_string(),
+    }
+}
+
+/// Describes the entry in a few lines for a verbose listing: the identifier,
+/// the file, the BibTeX file, the key bibliographic fields, the tags, the
+/// reading status along with the progress, and the rating. The fields the
+/// entry doesn't have are left out.
+pub(crate) fn format_details(entry: &Entry) -> Vec<String> {
+    let mut lines = vec![];
+
+    if !entry.id.is_empty() {
+        lines.push(format!("ID: {}", entry.id));
+    }
+
+    lines.push(format!("Path: {}", entry.path.display()));
+
+    if let Some(bib_path) = &entry.bib_path {
+        lines.push(format!("BibTeX: {}", bib_path.display()));
+    }
+
+    let authors = entry.authors();
+    if !authors.is_empty() {
+        lines.push(format!("Authors: {}", authors.join(", ")));
+    }
+
+    if let Some(year) = entry.year() {
+        lines.push(format!("Year: {}", year));
+    }
+
+    if let Some(series) = entry.series() {
+        lines.push(format!("Series: {}", series));
+    }
+
+    let tags = entry
+        .tags
+        .iter()
+        .flatten()
+        .map(|tag| tag.to_string())
+        .collect::<Vec<String>>();
+    if !tags.is_empty() {
+        lines.push(format!("Tags: {}", tags.join(", ")));
+    }
+
+    match entry.progress {
+        Some(progress) => lines.push(format!(
+            "Status: {} ({:.0}% read)",
+            entry.status,
+            progress.fraction() * 100.0
+        )),
+        None => lines.push(format!("Status: {}", entry.status)),
+    }
+
+    if let Some(rating) = entry.rating {
+        lines.push(format!("Rating: {}", rating.to_star_string()));
+    }
+
+    lines
+}
+
+/// Formats a number of bytes in the largest binary unit it reaches, e.g.
+/// `1.5 MiB`.
+pub(crate) fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+
+    let mut size = bytes as f64;
+    let mut unit = "B";
+
+    for next_unit in UNITS {
+        if size < 1024.0 {
+            break;
+        }
+
+        size /= 1024.0;
+        unit = next_unit;
+    }
+
+    match unit {
+        "B" => format!("{} B", bytes),
+        unit => format!("{:.1} {}", size, unit),
+    }
+}
+
+pub(crate) fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+
+    match (secs / 3600, secs / 60 % 60, secs % 60) {
+        (0, 0, s) => format!("{}s", s),
+        (0, m, s) => format!("{}m {:02}s", m, s),
+        (h, m, _) => format!("{}h {:02}m", h, m),
+    }
+}
+
+/// Formats a moment in the local time zone, to the minute.
+pub(crate) fn format_date(date: DateTime<Utc>) -> String {
+    date.with_timezone(&Local)
+        .format("%Y-%m-%d %H:%M")
+        .to_string()
+}
+
+/// Wraps the text into lines no longer than `width` characters, breaking
+/// only between words. Paragraphs (separated by newlines) start on new lines.
+pub(crate) fn wrap(text: &str, width: usize) -> Vec<String> {
+    let mut lines = vec![];
+
+    for paragraph in text.lines() {
+        let mut line = String::new();
+
+        for word in paragraph.split_whitespace() {
+            let line_width = line.chars().count();
+
+            if line_width > 0 && line_width + 1 + word.chars().count() > width {
+                lines.push(line);
+                line = String::new();
+            }
+
+            if !line.is_empty() {
+                line.push(' ');
+            }
+            line.push_str(word);
+        }
+
+        lines.push(line);
+    }
 
-    format!("{} - {}", entry_index, entry_file_name)
+    lines
 }
@@ -1 +1,4 @@
+pub(crate) mod clipboard;
+pub(crate) mod export;
 pub(crate) mod format;
+pub(crate) mod output;
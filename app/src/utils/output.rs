@@ -0,0 +1,385 @@
+use std::{fmt::Display, str::FromStr};
+
+use csv::Writer;
+use serde::{ser::SerializeMap, Serialize, Serializer};
+use serde_json::{json, Value};
+
+use lib::entry::Entry;
+
+/// A way of printing the entries listed.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) enum Format {
+    /// Aligned columns with a header, for reading
+    Table,
+    /// A pretty-printed JSON array of objects
+    Json,
+    /// One compact JSON object per line
+    Ndjson,
+    /// Comma-separated values with a header record
+    Csv,
+    /// Tab-separated values with a header line, without any quoting
+    Tsv,
+}
+
+impl Format {
+    /// All of the formats
+    pub(crate) const ALL: [Format; 5] = [
+        Format::Table,
+        Format::Json,
+        Format::Ndjson,
+        Format::Csv,
+        Format::Tsv,
+    ];
+}
+
+impl Display for Format {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Format::Table => write!(f, "table"),
+            Format::Json => write!(f, "json"),
+            Format::Ndjson => write!(f, "ndjson"),
+            Format::Csv => write!(f, "csv"),
+            Format::Tsv => write!(f, "tsv"),
+        }
+    }
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Format::ALL
+            .into_iter()
+            .find(|format| format.to_string() == s.to_lowercase())
+            .ok_or_else(|| {
+                let formats = Format::ALL.map(|f| f.to_string()).join(", ");
+
+                format!("Unknown format '{}', expected one of {}", s, formats)
+            })
+    }
+}
+
+/// A property of an entry which can be printed. The names and the values of
+/// the fields are kept stable for scripts to rely on.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) enum Field {
+    /// The index on the shelf, starting from 1
+    Index,
+    /// The identifier, which doesn't change as the entry moves on the shelf
+    Id,
+    /// The title, from the BibTeX file or the file name
+    Title,
+    /// The authors, each as "First Last"
+    Authors,
+    /// The year of publication
+    Year,
+    /// The full path to the file
+    Path,
+    /// The full path to the BibTeX file
+    Bib,
+    /// The series along with the number, e.g. `The Expanse #3`
+    Series,
+    /// The reading status
+    Status,
+    /// The rating, from 1 to 5 in half-star steps
+    Rating,
+    /// The share of the entry read, in percent
+    Progress,
+    /// The tags
+    Tags,
+    /// The date the entry was added, in RFC 3339
+    Added,
+    /// The date the entry was last opened, in RFC 3339
+    Opened,
+    /// The time spent reading, in seconds
+    Read,
+    /// The review
+    Review,
+}
+
+impl Field {
+    /// All of the fields
+    pub(crate) const ALL: [Field; 16] = [
+        Field::Index,
+        Field::Id,
+        Field::Title,
+        Field::Authors,
+        Field::Year,
+        Field::Path,
+        Field::Bib,
+        Field::Series,
+        Field::Status,
+        Field::Rating,
+        Field::Progress,
+        Field::Tags,
+        Field::Added,
+        Field::Opened,
+        Field::Read,
+        Field::Review,
+    ];
+
+    /// The fields printed unless others are chosen
+    pub(crate) const DEFAULT: [Field; 8] = [
+        Field::Index,
+        Field::Title,
+        Field::Authors,
+        Field::Year,
+        Field::Status,
+        Field::Rating,
+        Field::Tags,
+        Field::Path,
+    ];
+
+    /// Parses a comma-separated list of fields, e.g. `index,title`.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if any of the fields is unknown.
+    pub(crate) fn parse_list(s: &str) -> Result<Vec<Self>, String> {
+        s.split(',').map(str::trim).map(str::parse).collect()
+    }
+
+    /// Returns the value of this field for the entry with the **1-based**
+    /// index provided, or null if the entry doesn't have it.
+    fn value(&self, index: usize, entry: &Entry) -> Value {
+        match self {
+            Field::Index => json!(index),
+            Field::Id => json!(entry.id),
+            Field::Title => json!(entry.title()),
+            Field::Authors => json!(entry.authors()),
+            Field::Year => json!(entry.year()),
+            Field::Path => json!(entry.path),
+            Field::Bib => json!(entry.bib_path),
+            Field::Series => json!(entry.series().map(|s| s.to_string())),
+            Field::Status => json!(entry.status.to_string()),
+            Field::Rating => json!(entry.rating.map(|rating| rating.stars())),
+            Field::Progress => json!(entry.progress.map(|progress| (progress
+                .fraction()
+                * 100.0)
+                .round()
+                as u8)),
+            Field::Tags => json!(entry
+                .tags
+                .iter()
+                .flatten()
+                .map(|tag| tag.to_string())
+                .collect::<Vec<String>>()),
+            Field::Added => json!(entry.added.map(|date| date.to_rfc3339())),
+            Field::Opened => {
+                json!(entry.last_opened.map(|date| date.to_rfc3339()))
+            },
+            Field::Read => json!(entry.time_read().as_secs()),
+            Field::Review => json!(entry.review),
+        }
+    }
+}
+
+impl Display for Field {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Field::Index => write!(f, "index"),
+            Field::Id => write!(f, "id"),
+            Field::Title => write!(f, "title"),
+            Field::Authors => write!(f, "authors"),
+            Field::Year => write!(f, "year"),
+            Field::Path => write!(f, "path"),
+            Field::Bib => write!(f, "bib"),
+            Field::Series => write!(f, "series"),
+            Field::Status => write!(f, "status"),
+            Field::Rating => write!(f, "rating"),
+            Field::Progress => write!(f, "progress"),
+            Field::Tags => write!(f, "tags"),
+            Field::Added => write!(f, "added"),
+            Field::Opened => write!(f, "opened"),
+            Field::Read => write!(f, "read"),
+            Field::Review => write!(f, "review"),
+        }
+    }
+}
+
+impl FromStr for Field {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Field::ALL
+            .into_iter()
+            .find(|field| field.to_string() == s.to_lowercase())
+            .ok_or_else(|| {
+                let fields = Field::ALL.map(|f| f.to_string()).join(", ");
+
+                format!("Unknown field '{}', expected one of {}", s, fields)
+            })
+    }
+}
+
+/// The values of the fields of an entry, serialized as an object with the
+/// fields in the order chosen.
+struct Record<'a>(&'a [Field], Vec<Value>);
+
+impl Serialize for Record<'_> {
+    fn serialize<S: Serializer>(
+        &self,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(self.0.len()))?;
+
+        for (field, value) in self.0.iter().zip(&self.1) {
+            map.serialize_entry(&field.to_string(), value)?;
+        }
+
+        map.end()
+    }
+}
+
+/// Writes the fields of the entries provided, along with their **0-based**
+/// indices, in the format provided. Lists, such as the authors and the tags,
+/// are separated with semicolons in every format but JSON.
+///
+/// # Errors
+///
+/// This function will return an error if serializing the entries fails.
+pub(crate) fn format_entries(
+    entries: &[(usize, &Entry)],
+    format: Format,
+    fields: &[Field],
+) -> Result<String, Box<dyn std::error::Error>> {
+    let records = entries
+        .iter()
+        .map(|(i, entry)| {
+            let values = fields
+                .iter()
+                .map(|field| field.value(i + 1, entry))
+                .collect();
+
+            Record(fields, values)
+        })
+        .collect::<Vec<Record>>();
+    let header = fields.iter().map(Field::to_string);
+
+    let output = match format {
+        Format::Json => serde_json::to_string_pretty(&records)? + "\n",
+        Format::Ndjson => records
+            .iter()
+            .map(|record| Ok(serde_json::to_string(record)? + "\n"))
+            .collect::<Result<String, serde_json::Error>>()?,
+        Format::Csv => {
+            let mut writer = Writer::from_writer(vec![]);
+
+            writer.write_record(header)?;
+            for Record(_, values) in &records {
+                writer.write_record(values.iter().map(to_text))?;
+            }
+
+            String::from_utf8(writer.into_inner()?)?
+        },
+        Format::Tsv => {
+            let header = header.collect::<Vec<String>>();
+            let lines = records.iter().map(|Record(_, values)| {
+                values
+                    .iter()
+                    // Tabs and line breaks would split the values
+                    .map(|value| to_text(value).replace(['\t', '\n'], " "))
+                    .collect::<Vec<String>>()
+            });
+
+            std::iter::once(header)
+                .chain(lines)
+                .map(|line| line.join("\t") + "\n")
+                .collect()
+        },
+        Format::Table => {
+            let header = header.map(|f| f.to_uppercase()).collect();
+            let rows = records.iter().map(|Record(_, values)| {
+                values
+                    .iter()
+                    .map(|value| to_text(value).replace('\n', " "))
+                    .collect()
+            });
+
+            to_table(std::iter::once(header).chain(rows).collect())
+        },
+    };
+
+    Ok(output)
+}
+
+/// Writes a value as plain text: null as nothing, and lists separated with
+/// semicolons.
+fn to_text(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(s) => s.clone(),
+        Value::Array(values) => values
+            .iter()
+            .map(to_text)
+            .collect::<Vec<String>>()
+            .join(";"),
+        value => value.to_string(),
+    }
+}
+
+/// Aligns the cells of the rows into columns, two spaces apart.
+fn to_table(rows: Vec<Vec<String>>) -> String {
+    let columns = rows.first().map_or(0, Vec::len);
+    let widths = (0..columns)
+        .map(|column| {
+            rows.iter()
+                .map(|row| row[column].chars().count())
+                .max()
+                .unwrap_or_default()
+        })
+        .collect::<Vec<usize>>();
+
+    rows.iter()
+        .map(|row| {
+            let line = row
+                .iter()
+                .zip(&widths)
+                .map(|(cell, width)| format!("{:width$}", cell, width = width))
+                .collect::<Vec<String>>()
+                .join("  ");
+
+            line.trim_end().to_string() + "\n"
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use utils::test::setup;
+
+    use lib::{entry::Entry, tag::Tag};
+
+    use super::*;
+
+    #[test]
+    fn formatted_entries() {
+        let dir = setup();
+        let mut entry =
+            Entry::new(dir.path().join("book.txt").to_str().unwrap())
+                .with_tags(&[Tag::new("cs"), Tag::new("lisp")])
+                .unwrap();
+        entry.title = Some("SICP,\tthe\nWizard Book".to_string());
+        let entries = [(0, &entry)];
+        let fields = Field::parse_list("tags,index,year,title").unwrap();
+
+        let json = format_entries(&entries, Format::Ndjson, &fields).unwrap();
+        assert_eq!(
+            json,
+            "{\"tags\":[\"cs\",\"lisp\"],\"index\":1,\"year\":null,\
+             \"title\":\"SICP,\\tthe\\nWizard Book\"}\n"
+        );
+
+        let tsv = format_entries(&entries, Format::Tsv, &fields).unwrap();
+        assert_eq!(
+            tsv,
+            "tags\tindex\tyear\ttitle\ncs;lisp\t1\t\tSICP, the Wizard Book\n"
+        );
+
+        let csv = format_entries(&entries, Format::Csv, &fields).unwrap();
+        assert_eq!(csv.lines().nth(1), Some("cs;lisp,1,,\"SICP,\tthe"));
+
+        assert!(Field::parse_list("title,pages").is_err());
+        assert_eq!("NDJSON".parse::<Format>(), Ok(Format::Ndjson));
+    }
+}
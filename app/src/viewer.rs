@@ -0,0 +1,104 @@
+use std::{
+    io,
+    path::Path,
+    process::{Child, Command, Stdio},
+    time::{Duration, Instant},
+};
+
+use chrono::{DateTime, Utc};
+
+use lib::{entry::Entry, session::Session};
+
+/// The shortest reading session recorded. A viewer exiting sooner most
+/// likely handed the file off to another program, as `xdg-open` does, so
+/// the time it took says nothing about the reading.
+pub(crate) const MIN_SESSION: Duration = Duration::from_secs(10);
+
+/// An external program viewing an entry, which can be waited on to time
+/// a reading session.
+pub(crate) struct Viewer {
+    /// The entry being viewed
+    entry: Entry,
+    /// The viewer process
+    child: Child,
+    /// The moment the viewer was spawned
+    started: DateTime<Utc>,
+    /// Measures the time the viewer has been running for
+    timer: Instant,
+}
+
+impl Viewer {
+    /// Spawns a default or specified executable viewing the entry.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the executable could not be
+    /// spawned.
+    pub(crate) fn spawn(
+        entry: &Entry,
+        exe: Option<String>,
+    ) -> io::Result<Self> {
+        let child = viewer_command(&entry.path, exe)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()?;
+
+        Ok(Self {
+            entry: entry.clone(),
+            child,
+            started: Utc::now(),
+            timer: Instant::now(),
+        })
+    }
+
+    /// Blocks until the viewer exits, and returns the entry viewed along with
+    /// the reading session it took. Sessions shorter than [`MIN_SESSION`]
+    /// are not to be recorded.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if waiting for the viewer failed.
+    pub(crate) fn wait(mut self) -> io::Result<(Entry, Session)> {
+        self.child.wait()?;
+
+        let session = Session::new(self.started, self.timer.elapsed());
+
+        Ok((self.entry, session))
+    }
+}
+
+/// Builds the command used to view a file, either with the executable
+/// provided, or with the platform-specific default program.
+fn viewer_command(path: &Path, exe: Option<String>) -> Command {
+    match exe {
+        Some(exe) => {
+            let mut command = Command::new(exe);
+            command.arg(path);
+            command
+        },
+        None => default_viewer_command(path),
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn default_viewer_command(path: &Path) -> Command {
+    // -W makes `open` wait for the application to exit
+    let mut command = Command::new("open");
+    command.arg("-W").arg(path);
+    command
+}
+
+#[cfg(target_os = "windows")]
+fn default_viewer_command(path: &Path) -> Command {
+    let mut command = Command::new("cmd");
+    command.args(["/C", "start", "/WAIT", ""]).arg(path);
+    command
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+fn default_viewer_command(path: &Path) -> Command {
+    let mut command = Command::new("xdg-open");
+    command.arg(path);
+    command
+}
@@ -0,0 +1,21 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// A bookmarked position in an entry, measured in words.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Bookmark {
+    /// The number of words before the bookmarked position
+    pub position: usize,
+    /// The moment the bookmark was added
+    pub added: DateTime<Utc>,
+}
+
+impl Bookmark {
+    /// Creates a new [`Bookmark`] at the position provided.
+    pub fn new(position: usize) -> Self {
+        Self {
+            position,
+            added: Utc::now(),
+        }
+    }
+}
@@ -0,0 +1,260 @@
+use std::{
+    collections::HashMap,
+    fmt::Display,
+    fs::{metadata, File},
+    io,
+    path::Path,
+};
+
+use sha2::{Digest, Sha256};
+use strsim::normalized_levenshtein;
+
+use super::entry::Entry;
+
+/// How similar the titles of two entries have to be, from 0 to 1, for the
+/// entries to be considered duplicates
+const TITLE_SIMILARITY: f64 = 0.9;
+
+/// Why entries are considered duplicates of each other.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Reason {
+    /// The files of the entries are the same byte for byte
+    SameContent,
+    /// The entries have the same ISBN
+    SameIsbn(String),
+    /// The entries have the same DOI
+    SameDoi(String),
+    /// The titles of the entries are nearly the same
+    SimilarTitle,
+}
+
+impl Display for Reason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Reason::SameContent => write!(f, "the same content"),
+            Reason::SameIsbn(isbn) => write!(f, "the same ISBN {}", isbn),
+            Reason::SameDoi(doi) => write!(f, "the same DOI {}", doi),
+            Reason::SimilarTitle => write!(f, "similar titles"),
+        }
+    }
+}
+
+/// A group of entries which are likely duplicates of each other.
+#[derive(Debug, PartialEq)]
+pub struct Group {
+    /// The positions of the entries in the order they were provided in
+    pub entries: Vec<usize>,
+    /// Why the entries are considered duplicates
+    pub reasons: Vec<Reason>,
+}
+
+/// Finds the groups of entries which are likely duplicates of each other:
+/// the entries whose files have the same content, the entries with the same
+/// ISBN or DOI in their BibTeX metadata, and the entries with nearly the
+/// same title, ignoring case, punctuation and anything in brackets, such as
+/// `SICP (2nd ed)`. An entry is in a group if it is a duplicate of any
+/// other entry in it. Empty files and files which can't be read are never
+/// considered to have the same content.
+pub fn find_duplicates<'a, I>(entries: I) -> Vec<Group>
+where
+    I: IntoIterator<Item = &'a Entry>,
+{
+    let entries = entries.into_iter().collect::<Vec<&Entry>>();
+    let mut pairs = vec![];
+
+    let mut pair_by_key =
+        |keys: Vec<Option<String>>, reason: &dyn Fn(String) -> Reason| {
+            let mut first = HashMap::new();
+
+            for (i, key) in keys.into_iter().enumerate() {
+                if let Some(key) = key {
+                    match first.get(&key) {
+                        Some(&j) => pairs.push((j, i, reason(key))),
+                        None => {
+                            first.insert(key, i);
+                        },
+                    }
+                }
+            }
+        };
+
+    pair_by_key(content_hashes(&entries), &|_| Reason::SameContent);
+    pair_by_key(
+        entries.iter().map(|entry| entry.isbn()).collect(),
+        &Reason::SameIsbn,
+    );
+    pair_by_key(
+        entries.iter().map(|entry| entry.doi()).collect(),
+        &Reason::SameDoi,
+    );
+
+    let titles = entries
+        .iter()
+        .map(|entry| normalize_title(&entry.title()))
+        .collect::<Vec<String>>();
+
+    for i in 0..titles.len() {
+        for j in i + 1..titles.len() {
+            let is_similar = !titles[i].is_empty()
+                && normalized_levenshtein(&titles[i], &titles[j])
+                    >= TITLE_SIMILARITY;
+
+            if is_similar {
+                pairs.push((i, j, Reason::SimilarTitle));
+            }
+        }
+    }
+
+    // The pairs are joined into groups with a disjoint-set forest
+    let mut parents = (0..entries.len()).collect::<Vec<usize>>();
+
+    fn root(parents: &mut [usize], i: usize) -> usize {
+        let mut i = i;
+
+        while parents[i] != i {
+            parents[i] = parents[parents[i]];
+            i = parents[i];
+        }
+
+        i
+    }
+
+    for &(i, j, _) in pairs.iter() {
+        let (i, j) = (root(&mut parents, i), root(&mut parents, j));
+        parents[i.max(j)] = i.min(j);
+    }
+
+    let mut groups = HashMap::<usize, Group>::new();
+
+    for (i, j, reason) in pairs {
+        let group = groups.entry(root(&mut parents, i)).or_insert(Group {
+            entries: vec![],
+            reasons: vec![],
+        });
+
+        group.entries.extend([i, j]);
+
+        if !group.reasons.contains(&reason) {
+            group.reasons.push(reason);
+        }
+    }
+
+    let mut groups = groups.into_values().collect::<Vec<Group>>();
+
+    for group in groups.iter_mut() {
+        group.entries.sort_unstable();
+        group.entries.dedup();
+    }
+
+    groups.sort_by_key(|group| group.entries[0]);
+
+    groups
+}
+
+/// Returns the hashes of the contents of the files of the entries. Only the
+/// files of the same size as some other file are hashed.
+fn content_hashes(entries: &[&Entry]) -> Vec<Option<String>> {
+    let sizes = entries
+        .iter()
+        .map(|entry| metadata(&entry.path).map(|metadata| metadata.len()).ok())
+        .collect::<Vec<Option<u64>>>();
+
+    let mut counts = HashMap::new();
+    for size in sizes.iter().flatten() {
+        *counts.entry(*size).or_insert(0) += 1;
+    }
+
+    entries
+        .iter()
+        .zip(sizes)
+        .map(|(entry, size)| match size {
+            Some(size) if size > 0 && counts[&size] > 1 => {
+                hash_file(&entry.path).ok()
+            },
+            _ => None,
+        })
+        .collect()
+}
+
+/// Returns the SHA-256 hash of the contents of a file in hexadecimal.
+fn hash_file(path: &Path) -> io::Result<String> {
+    let mut hasher = Sha256::new();
+    io::copy(&mut File::open(path)?, &mut hasher)?;
+
+    Ok(hasher
+        .finalize()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect())
+}
+
+/// Makes a title comparable to the other versions of it: lowercases it, and
+/// drops the punctuation and anything in brackets.
+fn normalize_title(title: &str) -> String {
+    let mut depth = 0usize;
+    let mut normalized = String::with_capacity(title.len());
+
+    for c in title.chars() {
+        match c {
+            '(' | '[' | '{' => depth += 1,
+            ')' | ']' | '}' => depth = depth.saturating_sub(1),
+            _ if depth > 0 => {},
+            c if c.is_alphanumeric() => normalized.extend(c.to_lowercase()),
+            _ => normalized.push(' '),
+        }
+    }
+
+    normalized
+        .split_whitespace()
+        .collect::<Vec<&str>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::write;
+
+    use utils::test::setup;
+
+    use crate::{dedupe::*, entry::*};
+
+    #[test]
+    fn normalized_titles() {
+        assert_eq!(normalize_title("SICP (2nd ed)"), "sicp");
+        assert_eq!(
+            normalize_title("Structure & Interpretation [draft]: Vol. 1"),
+            "structure interpretation vol 1"
+        );
+    }
+
+    #[test]
+    fn duplicate_groups() {
+        let dir = setup();
+        let file = |name: &str, contents: &str| {
+            let path = dir.path().join(name);
+            write(&path, contents).unwrap();
+
+            Entry::new(path.to_str().unwrap())
+        };
+
+        let entries = [
+            file("sicp.pdf", "lambda"),
+            file("notes.txt", "unrelated"),
+            file("SICP (2nd ed).pdf", "lambda, revised"),
+            file("copy.epub", "lambda"),
+            // Empty files are not the same content
+            file("empty.txt", ""),
+            file("another_empty.txt", ""),
+        ];
+
+        let groups = find_duplicates(&entries);
+
+        assert_eq!(
+            groups,
+            [Group {
+                entries: vec![0, 2, 3],
+                reasons: vec![Reason::SameContent, Reason::SimilarTitle],
+            }]
+        );
+    }
+}
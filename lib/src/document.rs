@@ -0,0 +1,622 @@
+use std::{
+    collections::HashMap,
+    fmt::Display,
+    fs::{read, File},
+    io::Read,
+    path::{Component, Path, PathBuf},
+    process::Command,
+};
+
+use serde::{Deserialize, Serialize};
+use zip::ZipArchive;
+
+/// Errors associated with reading [`Document`]s.
+#[derive(Debug)]
+pub enum Error {
+    /// The file format is not supported.
+    UnsupportedFormat,
+    /// Reading the file failed.
+    Read,
+    /// The file is malformed.
+    Malformed,
+    /// Extracting the text with an external tool failed.
+    Extract,
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::UnsupportedFormat => {
+                write!(f, "The file format is not supported")
+            },
+            Error::Read => write!(f, "Reading the file failed"),
+            Error::Malformed => write!(f, "The file is malformed"),
+            Error::Extract => write!(
+                f,
+                "Extracting the text failed; is pdftotext from Poppler \
+                installed?"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// A section of a [`Document`], such as a chapter of a book or a page.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Section {
+    /// Optional title of the section
+    pub title: Option<String>,
+    /// Plain text of the section; paragraphs are separated by blank lines
+    pub text: String,
+}
+
+/// The plain text contents of a file, split into sections.
+///
+/// Plain text and Markdown files consist of a single section, while EPUB
+/// books are split into chapters (stripped of their XHTML markup), and PDF
+/// files into pages.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Document {
+    /// The sections of the document in reading order
+    pub sections: Vec<Section>,
+}
+
+impl Document {
+    /// Returns whether the file at the path provided can be read as
+    /// a [`Document`] in the built-in reader, judging by its extension. PDF
+    /// files are not, as the text extracted from them loses its layout.
+    pub fn is_supported<P>(path: P) -> bool
+    where
+        P: AsRef<Path>,
+    {
+        matches!(
+            extension(path.as_ref()).as_deref(),
+            Some("txt" | "md" | "epub")
+        )
+    }
+
+    /// Returns whether the text of the file at the path provided can be
+    /// extracted, judging by its extension.
+    pub fn has_text<P>(path: P) -> bool
+    where
+        P: AsRef<Path>,
+    {
+        Self::is_supported(path.as_ref())
+            || extension(path.as_ref()).as_deref() == Some("pdf")
+    }
+
+    /// Reads a [`Document`] from a plain text, Markdown, EPUB or PDF file.
+    /// The text of PDF files is extracted with `pdftotext` from Poppler.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the file format is not
+    /// supported, if reading the file fails, if the file is malformed, or if
+    /// extracting the text of a PDF file fails.
+    pub fn open<P>(path: P) -> Result<Document, Error>
+    where
+        P: AsRef<Path>,
+    {
+        let path = path.as_ref();
+
+        match extension(path).as_deref() {
+            Some("txt" | "md") => {
+                let bytes = read(path).map_err(|_| Error::Read)?;
+
+                Ok(Document {
+                    sections: vec![Section {
+                        title: None,
+                        text: String::from_utf8_lossy(&bytes).into_owned(),
+                    }],
+                })
+            },
+            Some("epub") => read_epub(path),
+            Some("pdf") => read_pdf(path),
+            _ => Err(Error::UnsupportedFormat),
+        }
+    }
+
+    /// Returns the words of the document in reading order.
+    pub fn words(&self) -> Vec<&str> {
+        self.sections
+            .iter()
+            .flat_map(|section| section.text.split_whitespace())
+            .collect()
+    }
+
+    /// Returns the number of words in the document.
+    pub fn word_count(&self) -> usize {
+        self.sections
+            .iter()
+            .map(|section| section.text.split_whitespace().count())
+            .sum()
+    }
+}
+
+/// Returns the lowercase extension of a file.
+fn extension(path: &Path) -> Option<String> {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ext.to_lowercase())
+}
+
+/// Reads the chapters of an EPUB book in the order of its spine.
+fn read_epub(path: &Path) -> Result<Document, Error> {
+    let file = File::open(path).map_err(|_| Error::Read)?;
+    let mut archive = ZipArchive::new(file).map_err(|_| Error::Malformed)?;
+
+    let container = read_archive_file(&mut archive, "META-INF/container.xml")?;
+    let package_path = elements(&container, "rootfile")
+        .into_iter()
+        .find_map(|mut attributes| attributes.remove("full-path"))
+        .ok_or(Error::Malformed)?;
+    let package = read_archive_file(&mut archive, &package_path)?;
+
+    // Hrefs in the package are relative to the package itself
+    let package_dir = Path::new(&package_path)
+        .parent()
+        .map(Path::to_path_buf)
+        .unwrap_or_default();
+
+    let manifest = elements(&package, "item")
+        .into_iter()
+        .filter_map(|mut attributes| {
+            Some((attributes.remove("id")?, attributes.remove("href")?))
+        })
+        .collect::<HashMap<String, String>>();
+
+    let mut sections = vec![];
+
+    for mut itemref in elements(&package, "itemref") {
+        let href = match itemref.remove("idref") {
+            Some(idref) => manifest.get(&idref).ok_or(Error::Malformed)?,
+            None => continue,
+        };
+
+        let chapter_path = normalize(&package_dir.join(percent_decode(href)));
+        let xhtml = read_archive_file(
+            &mut archive,
+            &chapter_path.to_string_lossy().replace('\\', "/"),
+        )?;
+        let section = strip_markup(&xhtml);
+
+        if !section.text.trim().is_empty() {
+            sections.push(section);
+        }
+    }
+
+    Ok(Document { sections })
+}
+
+/// Reads the pages of a PDF file with `pdftotext`, which separates them by
+/// form feeds.
+fn read_pdf(path: &Path) -> Result<Document, Error> {
+    let output = Command::new("pdftotext")
+        .args(["-q", "-enc", "UTF-8"])
+        .arg(path)
+        .arg("-")
+        .output()
+        .map_err(|_| Error::Extract)?;
+
+    if !output.status.success() {
+        return Err(Error::Extract);
+    }
+
+    let text = String::from_utf8_lossy(&output.stdout);
+    let mut pages = text
+        .split('\u{c}')
+        .map(|page| Section {
+            title: None,
+            text: page.to_string(),
+        })
+        .collect::<Vec<Section>>();
+
+    // The last page is followed by a form feed as well
+    if matches!(pages.last(), Some(page) if page.text.trim().is_empty()) {
+        pages.pop();
+    }
+
+    Ok(Document { sections: pages })
+}
+
+/// Reads a file from a zip archive into a string.
+fn read_archive_file(
+    archive: &mut ZipArchive<File>,
+    name: &str,
+) -> Result<String, Error> {
+    let mut file = archive.by_name(name).map_err(|_| Error::Malformed)?;
+
+    let mut bytes = vec![];
+    file.read_to_end(&mut bytes).map_err(|_| Error::Read)?;
+
+    Ok(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+/// Resolves `.` and `..` components of a relative path.
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+
+    for component in path.components() {
+        match component {
+            Component::CurDir => {},
+            Component::ParentDir => {
+                normalized.pop();
+            },
+            component => normalized.push(component),
+        }
+    }
+
+    normalized
+}
+
+/// Decodes `%XX` escapes in a URL path.
+fn percent_decode(href: &str) -> String {
+    // Fragments point inside of a file, which is read as a whole
+    let href = href.split('#').next().unwrap_or_default();
+
+    let mut bytes = vec![];
+    let mut rest = href.as_bytes();
+
+    while let Some((&byte, tail)) = rest.split_first() {
+        let escaped = tail
+            .get(..2)
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+
+        match escaped {
+            Some(escaped) if byte == b'%' => {
+                bytes.push(escaped);
+                rest = &tail[2..];
+            },
+            _ => {
+                bytes.push(byte);
+                rest = tail;
+            },
+        }
+    }
+
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+/// Returns the attributes of every element with the local name provided
+/// (ignoring namespace prefixes) in an XML document.
+fn elements(xml: &str, name: &str) -> Vec<HashMap<String, String>> {
+    let mut elements = vec![];
+    let mut rest = xml;
+
+    while let Some(start) = rest.find('<') {
+        rest = &rest[start + 1..];
+
+        let end = match rest.find('>') {
+            Some(end) => end,
+            None => break,
+        };
+        let tag = &rest[..end];
+        rest = &rest[end + 1..];
+
+        if local_name(tag) == name {
+            elements.push(attributes(tag));
+        }
+    }
+
+    elements
+}
+
+/// Returns the local name of an element from the contents of its tag,
+/// e.g. `item` for `opf:item id="ch1"`.
+fn local_name(tag: &str) -> String {
+    let name = tag
+        .split(|c: char| c.is_whitespace() || c == '/')
+        .find(|part| !part.is_empty())
+        .unwrap_or_default();
+
+    name.rsplit(':').next().unwrap_or_default().to_lowercase()
+}
+
+/// Parses the `key="value"` attributes from the contents of a tag.
+fn attributes(tag: &str) -> HashMap<String, String> {
+    let mut attributes = HashMap::new();
+    let mut rest = tag;
+
+    while let Some(eq) = rest.find('=') {
+        let key = rest[..eq].split_whitespace().last().unwrap_or_default();
+        let value = rest[eq + 1..].trim_start();
+
+        let quote = match value.chars().next() {
+            Some(quote @ ('"' | '\'')) => quote,
+            _ => break,
+        };
+        let value = &value[1..];
+        let end = match value.find(quote) {
+            Some(end) => end,
+            None => break,
+        };
+
+        attributes.insert(key.to_string(), decode_entities(&value[..end]));
+        rest = &value[end + 1..];
+    }
+
+    attributes
+}
+
+/// Strips the markup from an XHTML document, keeping the text of its body.
+/// Block elements become paragraphs, and the first heading (or the title
+/// of the document) becomes the title of the section.
+fn strip_markup(xhtml: &str) -> Section {
+    const BLOCKS: &[&str] = &[
+        "p",
+        "div",
+        "br",
+        "h1",
+        "h2",
+        "h3",
+        "h4",
+        "h5",
+        "h6",
+        "li",
+        "tr",
+        "blockquote",
+        "section",
+        "hr",
+        "pre",
+        "dt",
+        "dd",
+    ];
+    const HEADINGS: &[&str] = &["h1", "h2", "h3", "h4", "h5", "h6"];
+    const SKIPPED: &[&str] = &["head", "script", "style"];
+
+    let mut text = String::new();
+    let mut title = None;
+    let mut document_title = String::new();
+    let mut heading: Option<String> = None;
+    let mut skipped: Option<String> = None;
+    let mut in_title = false;
+    let mut rest = xhtml;
+
+    while !rest.is_empty() {
+        let (content, tag) = match rest.find('<') {
+            Some(start) => {
+                let end = rest[start..]
+                    .find('>')
+                    .map_or(rest.len(), |end| start + end + 1);
+                let tag = &rest[start + 1..end.max(start + 2) - 1];
+                let content = &rest[..start];
+                rest = &rest[end..];
+
+                (content, Some(tag))
+            },
+            None => {
+                let content = rest;
+                rest = "";
+
+                (content, None)
+            },
+        };
+
+        let content = decode_entities(content);
+
+        if in_title {
+            document_title.push_str(&content);
+        }
+
+        if skipped.is_none() {
+            text.push_str(&content);
+
+            if let Some(heading) = heading.as_mut() {
+                heading.push_str(&content);
+            }
+        }
+
+        let tag = match tag {
+            // Comments, processing instructions and doctypes
+            Some(tag) if tag.starts_with(['!', '?']) => continue,
+            Some(tag) => tag,
+            None => break,
+        };
+
+        let closing = tag.starts_with('/');
+        let name = local_name(tag.trim_start_matches('/'));
+
+        if name == "title" {
+            in_title = !closing;
+        }
+
+        match &skipped {
+            Some(skipped_name) if closing && *skipped_name == name => {
+                skipped = None;
+                continue;
+            },
+            Some(_) => continue,
+            None if !closing && SKIPPED.contains(&name.as_str()) => {
+                if !tag.ends_with('/') {
+                    skipped = Some(name);
+                }
+                continue;
+            },
+            None => {},
+        }
+
+        if HEADINGS.contains(&name.as_str()) {
+            if closing {
+                if let Some(heading) = heading.take() {
+                    let heading = collapse_whitespace(&heading);
+
+                    if title.is_none() && !heading.is_empty() {
+                        title = Some(heading);
+                    }
+                }
+            } else {
+                heading = Some(String::new());
+            }
+        }
+
+        if BLOCKS.contains(&name.as_str()) {
+            text.push_str("\n\n");
+        }
+    }
+
+    let document_title = collapse_whitespace(&document_title);
+    if title.is_none() && !document_title.is_empty() {
+        title = Some(document_title);
+    }
+
+    let text = text
+        .split("\n\n")
+        .map(collapse_whitespace)
+        .filter(|paragraph| !paragraph.is_empty())
+        .collect::<Vec<String>>()
+        .join("\n\n");
+
+    Section { title, text }
+}
+
+/// Replaces all runs of whitespace with single spaces, and trims the text.
+fn collapse_whitespace(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<&str>>().join(" ")
+}
+
+/// Decodes the predefined XML entities, `&nbsp;`, and numeric character
+/// references.
+fn decode_entities(text: &str) -> String {
+    let mut decoded = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(start) = rest.find('&') {
+        decoded.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        let end = match rest.find(';') {
+            Some(end) if end <= 10 => end,
+            _ => {
+                decoded.push('&');
+                rest = &rest[1..];
+                continue;
+            },
+        };
+
+        let character = match &rest[1..end] {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            "nbsp" => Some(' '),
+            entity => entity
+                .strip_prefix("#x")
+                .or_else(|| entity.strip_prefix("#X"))
+                .map(|hex| u32::from_str_radix(hex, 16))
+                .or_else(|| entity.strip_prefix('#').map(str::parse))
+                .and_then(Result::ok)
+                .and_then(char::from_u32),
+        };
+
+        match character {
+            Some(character) => {
+                decoded.push(character);
+                rest = &rest[end + 1..];
+            },
+            None => {
+                decoded.push('&');
+                rest = &rest[1..];
+            },
+        }
+    }
+
+    decoded.push_str(rest);
+
+    decoded
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs::write, io::Write};
+
+    use utils::test::setup;
+    use zip::{write::FileOptions, ZipWriter};
+
+    use crate::document::*;
+
+    #[test]
+    fn plain_text() {
+        let dir = setup();
+        let path = dir.path().join("book.txt");
+        write(&path, "Call me Ishmael.\n\nSome years ago...").unwrap();
+
+        let document = Document::open(&path).unwrap();
+
+        assert_eq!(document.sections.len(), 1);
+        assert_eq!(document.word_count(), 6);
+        assert_eq!(document.words()[2], "Ishmael.");
+    }
+
+    #[test]
+    fn unsupported_format() {
+        let dir = setup();
+
+        assert!(!Document::is_supported(dir.path().join("book.bib")));
+        assert!(matches!(
+            Document::open(dir.path().join("book.bib")),
+            Err(Error::UnsupportedFormat)
+        ));
+    }
+
+    #[test]
+    fn markup_stripped() {
+        let section = strip_markup(
+            "<?xml version=\"1.0\"?><html><head><title>Ignored</title>\
+             <style>p { color: red; }</style></head><body>\
+             <h1>Chapter&#160;1</h1><p>Tom &amp; <em>Jerry</em></p>\
+             <p>The&nbsp;end</p></body></html>",
+        );
+
+        assert_eq!(section.title.as_deref(), Some("Chapter 1"));
+        assert_eq!(section.text, "Chapter 1\n\nTom & Jerry\n\nThe end");
+    }
+
+    #[test]
+    fn epub_chapters() {
+        let dir = setup();
+        let path = dir.path().join("book.epub");
+
+        let mut epub = ZipWriter::new(File::create(&path).unwrap());
+        let files = [
+            (
+                "META-INF/container.xml",
+                "<container><rootfiles><rootfile \
+                 full-path=\"OEBPS/content.opf\"/></rootfiles></container>",
+            ),
+            (
+                "OEBPS/content.opf",
+                "<package><manifest>\
+                 <item id=\"two\" href=\"text/ch%202.xhtml\"/>\
+                 <item id=\"one\" href=\"text/ch1.xhtml\"/>\
+                 </manifest><spine>\
+                 <itemref idref=\"one\"/><itemref idref=\"two\"/>\
+                 </spine></package>",
+            ),
+            (
+                "OEBPS/text/ch1.xhtml",
+                "<html><body><h2>One</h2><p>First chapter</p></body></html>",
+            ),
+            (
+                "OEBPS/text/ch 2.xhtml",
+                "<html><head><title>Two</title></head>\
+                 <body><p>Second chapter</p></body></html>",
+            ),
+        ];
+        for (name, contents) in files {
+            epub.start_file(name, FileOptions::default()).unwrap();
+            epub.write_all(contents.as_bytes()).unwrap();
+        }
+        epub.finish().unwrap();
+
+        let document = Document::open(&path).unwrap();
+
+        assert_eq!(document.sections.len(), 2);
+        assert_eq!(document.sections[0].title.as_deref(), Some("One"));
+        assert_eq!(document.sections[1].title.as_deref(), Some("Two"));
+        assert_eq!(document.sections[1].text, "Second chapter");
+        assert_eq!(document.word_count(), 5);
+    }
+}
@@ -0,0 +1,384 @@
+use std::{
+    fmt::Display,
+    fs::read_to_string,
+    path::{Path, PathBuf},
+};
+
+use biblatex::Bibliography;
+use serde::{Deserialize, Serialize};
+
+use super::{
+    entry::Entry, rating::Rating, series::Series, status::Status, tag::Tag,
+};
+
+/// Errors associated with editing the fields of an entry.
+#[derive(Debug, PartialEq)]
+pub enum Error {
+    /// The document is not valid TOML, or its keys or values are wrong.
+    Parse(String),
+    /// There is no field with the name provided.
+    UnknownField(String),
+    /// The field provided can't be unset.
+    Required(String),
+    /// The field provided has an invalid value.
+    InvalidValue(String, String),
+    /// The file provided does not exist.
+    NoSuchFile(PathBuf),
+    /// The file provided is not a BibTeX file with an entry named after it.
+    InvalidBib(PathBuf),
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Parse(e) => write!(f, "Invalid document: {}", e),
+            Error::UnknownField(key) => write!(
+                f,
+                "Unknown field '{}', expected one of {}",
+                key,
+                Fields::KEYS.join(", ")
+            ),
+            Error::Required(key) => write!(f, "The {} can't be unset", key),
+            Error::InvalidValue(key, e) => write!(f, "Invalid {}: {}", key, e),
+            Error::NoSuchFile(path) => {
+                write!(f, "The file '{}' does not exist", path.display())
+            },
+            Error::InvalidBib(path) => write!(
+                f,
+                "'{}' is not a BibTeX file with an entry named after it",
+                path.display()
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// The fields of an [`Entry`] which can be edited by hand, written as a TOML
+/// document. The title, the authors and the year override the ones from the
+/// BibTeX metadata.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct Fields {
+    /// The path to the file
+    pub path: PathBuf,
+    /// The path to the BibTeX file
+    pub bib: Option<PathBuf>,
+    /// The title
+    pub title: Option<String>,
+    /// The authors, each as "First Last"
+    pub authors: Option<Vec<String>>,
+    /// The year of publication
+    pub year: Option<i32>,
+    /// The series along with the number, e.g. `The Expanse #3`
+    pub series: Option<String>,
+    /// The reading status
+    #[serde(default)]
+    pub status: Status,
+    /// The rating, from 1 to 5 in half-star steps
+    pub rating: Option<Rating>,
+    /// The tags
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// The review
+    pub review: Option<String>,
+}
+
+impl Fields {
+    /// The names of the fields, in the order they are written in
+    pub const KEYS: [&'static str; 10] = [
+        "path", "bib", "title", "authors", "year", "series", "status",
+        "rating", "tags", "review",
+    ];
+
+    /// Gathers the fields of the [`Entry`] provided.
+    pub fn of(entry: &Entry) -> Self {
+        Self {
+            path: entry.path.clone(),
+            bib: entry.bib_path.clone(),
+            title: entry.title.clone(),
+            authors: entry.authors.clone(),
+            year: entry.year,
+            series: entry.series.as_ref().map(Series::to_string),
+            status: entry.status,
+            rating: entry.rating,
+            tags: entry.tags.iter().flatten().map(Tag::to_string).collect(),
+            review: entry.review.clone(),
+        }
+    }
+
+    /// Parses the fields from a TOML document. The fields left out are
+    /// unset.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the document is not valid
+    /// TOML, or if it has unknown fields or values of the wrong types.
+    pub fn from_document(document: &str) -> Result<Self, Error> {
+        toml::from_str(document).map_err(|e| Error::Parse(e.to_string()))
+    }
+
+    /// Writes the fields as a TOML document, with examples of the fields
+    /// which are unset commented out.
+    pub fn to_document(&self) -> String {
+        let mut document =
+            toml::to_string(self).expect("The fields are valid TOML");
+
+        for key in Fields::KEYS {
+            let is_set = document
+                .lines()
+                .any(|line| line.split(" = ").next() == Some(key));
+
+            if !is_set {
+                let example = match key {
+                    "bib" => "\"/path/to/the/file.bib\"",
+                    "authors" => "[\"First Last\"]",
+                    "year" => "2022",
+                    "series" => "\"Name #1\"",
+                    "rating" => "4.5",
+                    _ => "\"\"",
+                };
+
+                document.push_str(&format!("# {} = {}\n", key, example));
+            }
+        }
+
+        document
+    }
+
+    /// Sets a field to the value provided, e.g. `title` to `SICP`. The
+    /// authors are separated with ` and `, and the tags with commas.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if there is no such field, or if
+    /// the value of a number, a status or a rating is invalid.
+    pub fn set(&mut self, key: &str, value: &str) -> Result<(), Error> {
+        let invalid = |e: String| Error::InvalidValue(key.to_string(), e);
+
+        match key {
+            "path" => self.path = PathBuf::from(value),
+            "bib" => self.bib = Some(PathBuf::from(value)),
+            "title" => self.title = Some(value.to_string()),
+            "authors" => {
+                self.authors = Some(
+                    value
+                        .split(" and ")
+                        .map(str::trim)
+                        .filter(|author| !author.is_empty())
+                        .map(String::from)
+                        .collect(),
+                )
+            },
+            "year" => {
+                self.year =
+                    Some(value.parse().map_err(|e| invalid(format!("{}", e)))?)
+            },
+            "series" => self.series = Some(value.to_string()),
+            "status" => self.status = value.parse().map_err(invalid)?,
+            "rating" => self.rating = Some(value.parse().map_err(invalid)?),
+            "tags" => {
+                self.tags = value
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|tag| !tag.is_empty())
+                    .map(String::from)
+                    .collect()
+            },
+            "review" => self.review = Some(value.to_string()),
+            _ => return Err(Error::UnknownField(key.to_string())),
+        }
+
+        Ok(())
+    }
+
+    /// Unsets a field. The title, the authors and the year then come from
+    /// the BibTeX metadata again, if any.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if there is no such field, or if
+    /// the field is the path or the status, which are always set.
+    pub fn unset(&mut self, key: &str) -> Result<(), Error> {
+        match key {
+            "path" | "status" => return Err(Error::Required(key.to_string())),
+            "bib" => self.bib = None,
+            "title" => self.title = None,
+            "authors" => self.authors = None,
+            "year" => self.year = None,
+            "series" => self.series = None,
+            "rating" => self.rating = None,
+            "tags" => self.tags.clear(),
+            "review" => self.review = None,
+            _ => return Err(Error::UnknownField(key.to_string())),
+        }
+
+        Ok(())
+    }
+
+    /// Applies the fields to the [`Entry`] provided. Everything else about
+    /// the entry, such as its reading sessions and progress, is kept.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the file or the BibTeX file
+    /// doesn't exist, if the BibTeX file has no entry named after it, or if
+    /// the series or the tags are invalid. The entry is left untouched in
+    /// that case.
+    pub fn apply(&self, entry: &mut Entry) -> Result<(), Error> {
+        let path = self
+            .path
+            .canonicalize()
+            .map_err(|_| Error::NoSuchFile(self.path.clone()))?;
+
+        let bib_path = match &self.bib {
+            Some(bib) => Some(check_bib(bib)?),
+            None => None,
+        };
+
+        let series = match &self.series {
+            Some(series) => {
+                Some(series.parse::<Series>().map_err(|e| {
+                    Error::InvalidValue("series".to_string(), e)
+                })?)
+            },
+            None => None,
+        };
+
+        let tags = self
+            .tags
+            .iter()
+            .map(|tag| Tag::new(tag))
+            .collect::<Vec<Tag>>();
+
+        let mut edited = Entry {
+            tags: None,
+            ..entry.clone()
+        };
+
+        if !tags.is_empty() {
+            edited = edited.with_tags(&tags).map_err(|e| {
+                Error::InvalidValue("tags".to_string(), e.to_string())
+            })?;
+        }
+
+        // Empty text is the same as no text at all
+        let text = |text: &Option<String>| {
+            text.clone().filter(|text| !text.trim().is_empty())
+        };
+
+        *entry = Entry {
+            path,
+            bib_path,
+            title: text(&self.title),
+            authors: self.authors.clone().filter(|a| !a.is_empty()),
+            year: self.year,
+            series,
+            status: self.status,
+            rating: self.rating,
+            review: text(&self.review),
+            ..edited
+        };
+
+        Ok(())
+    }
+}
+
+/// Checks that the file is a BibTeX file with an entry whose cite key is
+/// the name of the file, and returns its full path.
+fn check_bib(path: &Path) -> Result<PathBuf, Error> {
+    let invalid = || Error::InvalidBib(path.to_path_buf());
+
+    let full_path = path
+        .canonicalize()
+        .map_err(|_| Error::NoSuchFile(path.to_path_buf()))?;
+
+    if full_path.extension().and_then(|ext| ext.to_str()) != Some("bib") {
+        return Err(invalid());
+    }
+
+    let cite_key = full_path
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .ok_or_else(invalid)?;
+    let bib_str = read_to_string(&full_path).map_err(|_| invalid())?;
+    let bibliography = Bibliography::parse(&bib_str).map_err(|_| invalid())?;
+
+    bibliography.get(cite_key).ok_or_else(invalid)?;
+
+    Ok(full_path)
+}
+
+#[cfg(test)]
+mod tests {
+    use utils::test::setup;
+
+    use crate::{edit::*, entry::Entry, status::Status, tag::Tag};
+
+    #[test]
+    fn document_round_trip() {
+        let dir = setup();
+        let mut entry =
+            Entry::new(dir.path().join("book.txt").to_str().unwrap())
+                .with_tags(&[Tag::new("cs/lisp")])
+                .unwrap();
+        entry.review = Some("Dense,\nbut \"worth it\"".to_string());
+
+        let document = Fields::of(&entry).to_document();
+        assert!(document.contains("tags = [\"cs/lisp\"]"));
+        assert!(document.contains("# title = \"\""));
+
+        let fields = Fields::from_document(&document).unwrap();
+        assert_eq!(fields, Fields::of(&entry));
+
+        assert!(matches!(
+            Fields::from_document("path = \"a\"\npages = 3"),
+            Err(Error::Parse(_))
+        ));
+    }
+
+    #[test]
+    fn applied_fields() {
+        let dir = setup();
+        let mut entry =
+            Entry::new(dir.path().join("book.txt").to_str().unwrap());
+        let mut fields = Fields::of(&entry);
+
+        fields.set("title", "SICP").unwrap();
+        fields
+            .set("authors", "Harold Abelson and Gerald Sussman")
+            .unwrap();
+        fields.set("status", "reading").unwrap();
+        fields.set("tags", "cs, lisp").unwrap();
+        fields
+            .set("bib", dir.path().join("book.bib").to_str().unwrap())
+            .unwrap();
+        assert!(fields.set("year", "soon").is_err());
+        assert!(fields.set("pages", "3").is_err());
+        assert_eq!(fields.unset("path"), Err(Error::Required("path".into())));
+
+        fields.apply(&mut entry).unwrap();
+
+        assert_eq!(entry.title(), "SICP");
+        assert_eq!(entry.authors().len(), 2);
+        assert_eq!(entry.status, Status::Reading);
+        assert_eq!(entry.tags, Some(vec![Tag::new("cs"), Tag::new("lisp")]));
+        // The year still comes from the BibTeX file
+        assert_eq!(entry.year(), Some(2022));
+
+        // Invalid fields leave the entry untouched
+        fields.set("path", "missing.pdf").unwrap();
+        fields.set("title", "Other").unwrap();
+        assert!(fields.apply(&mut entry).is_err());
+        assert_eq!(entry.title(), "SICP");
+
+        let book = dir.path().join("book.txt");
+        fields.set("path", book.to_str().unwrap()).unwrap();
+        let empty = dir.path().join("empty.bib");
+        fields.set("bib", empty.to_str().unwrap()).unwrap();
+        assert!(matches!(
+            fields.apply(&mut entry),
+            Err(Error::InvalidBib(_))
+        ));
+    }
+}
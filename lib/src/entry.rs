@@ -1,8 +1,57 @@
-use super::tag::Tag;
+use super::{
+    bookmark::Bookmark, progress::Progress, rating::Rating, series::Series,
+    session::Session, status::Status, tag::Tag,
+};
 
-use biblatex::{Bibliography, Entry as BibEntry};
+use biblatex::{Bibliography, ChunksExt, Entry as BibEntry};
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use std::{fs::read_to_string, hash::Hash, path::PathBuf};
+use sha2::{Digest, Sha256};
+use std::{
+    fmt::Display,
+    fs::read_to_string,
+    hash::Hash,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+/// Errors associated with [`Entry`] operations.
+#[derive(Debug, PartialEq)]
+pub enum Error {
+    /// A tag is given more than once.
+    DuplicateTag(Tag),
+    /// A tag has no keyword.
+    EmptyTag,
+    /// The BibTeX file can't be read.
+    BibRead(String),
+    /// The BibTeX file can't be parsed.
+    BibParse(String),
+    /// The BibTeX file has no entry with the cite key of its name.
+    BibCiteKey,
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::DuplicateTag(tag) => {
+                write!(f, "The tag '{}' is given more than once", tag)
+            },
+            Error::EmptyTag => write!(f, "A tag can't be empty"),
+            Error::BibRead(e) => {
+                write!(f, "Failed to read the contents of the file: {}", e)
+            },
+            Error::BibParse(e) => {
+                write!(f, "Failed to parse the bibliographic string: {}", e)
+            },
+            Error::BibCiteKey => write!(
+                f,
+                "Failed to get the bibliographic entry: invalid cite key"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
 
 /// [`Entry`] is any file that can be contained in your bookshelf.
 ///
@@ -18,6 +67,49 @@ pub struct Entry {
     pub bib_path: Option<PathBuf>,
     /// Optional list of tags
     pub tags: Option<Vec<Tag>>,
+    /// Reading status of the entry
+    #[serde(default)]
+    pub status: Status,
+    /// The last time the entry was opened
+    #[serde(default)]
+    pub last_opened: Option<DateTime<Utc>>,
+    /// Reading sessions recorded for the entry
+    #[serde(default)]
+    pub sessions: Vec<Session>,
+    /// Optional rating of the entry
+    #[serde(default)]
+    pub rating: Option<Rating>,
+    /// Optional free-text review of the entry
+    #[serde(default)]
+    pub review: Option<String>,
+    /// Reading progress, if the entry has been read inside bookshelf
+    #[serde(default)]
+    pub progress: Option<Progress>,
+    /// Bookmarked positions, in the order of their position
+    #[serde(default)]
+    pub bookmarks: Vec<Bookmark>,
+    /// The time the entry was created
+    #[serde(default)]
+    pub added: Option<DateTime<Utc>>,
+    /// Other files of the same work, e.g. in other formats
+    #[serde(default)]
+    pub formats: Vec<PathBuf>,
+    /// The series the entry belongs to, if set by hand
+    #[serde(default)]
+    pub series: Option<Series>,
+    /// The title, if set by hand
+    #[serde(default)]
+    pub title: Option<String>,
+    /// The authors, each as "First Last", if set by hand
+    #[serde(default)]
+    pub authors: Option<Vec<String>>,
+    /// The year of publication, if set by hand
+    #[serde(default)]
+    pub year: Option<i32>,
+    /// A short identifier in hex which stays the same as the entry moves
+    /// around the shelf, e.g. `3fa9c2e01b7d`
+    #[serde(default)]
+    pub id: String,
 }
 
 impl Hash for Entry {
@@ -37,10 +129,28 @@ impl Hash for Entry {
 
 impl PartialEq for Entry {
     fn eq(&self, other: &Self) -> bool {
-        self.path.canonicalize().unwrap() == other.path.canonicalize().unwrap()
+        match (self.path.canonicalize(), other.path.canonicalize()) {
+            (Ok(path), Ok(other_path)) => path == other_path,
+            // The file may have been moved or deleted since it was added
+            _ => self.path == other.path,
+        }
     }
 }
 
+/// The metadata of an [`Entry`] which may come from its BibTeX file, see
+/// [`Entry::metadata`].
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Metadata {
+    /// The title
+    pub title: String,
+    /// The authors, each as "First Last"
+    pub authors: Vec<String>,
+    /// The year of publication
+    pub year: Option<i32>,
+    /// The series along with the number
+    pub series: Option<Series>,
+}
+
 impl Entry {
     /// Creates a new [`Entry`] form a path string.
     ///
@@ -55,12 +165,25 @@ impl Entry {
             Err(_) => panic!("The file provided does not exist"),
         }
 
+        let added = Utc::now();
+
         Entry {
+            id: new_id(&path, added),
             path,
+            added: Some(added),
             ..Default::default()
         }
     }
 
+    /// Gives the [`Entry`] a new id if it has none, e.g. when it was saved
+    /// before the ids were introduced.
+    pub(crate) fn backfill_id(&mut self) {
+        if self.id.is_empty() {
+            let added = self.added.unwrap_or_else(Utc::now);
+            self.id = new_id(&self.path, added);
+        }
+    }
+
     /// Links a BibTeX bibliography to this [`Entry`].
     ///
     /// # Panics
@@ -88,21 +211,377 @@ impl Entry {
 
     /// Adds optional tags to this [`Entry`].
     ///
-    /// # Panics
+    /// # Errors
     ///
-    /// The function panics if there duplicates in the tags array.
-    pub fn with_tags(mut self, tags: &[Tag]) -> Self {
+    /// This function will return an error if there are duplicates in the
+    /// tags array, or if any of the tags is empty.
+    pub fn with_tags(mut self, tags: &[Tag]) -> Result<Self, Error> {
+        check_tags(tags)?;
+
         let mut tags_vec = tags.to_vec();
         tags_vec.sort();
-        tags_vec.dedup();
 
-        if tags_vec.len() != tags.len() {
-            panic!("The tags array contains duplicate tags!");
+        self.tags = Some(tags_vec);
+
+        Ok(self)
+    }
+
+    /// Adds the tags to this [`Entry`], skipping the ones it already has.
+    /// Returns the number of tags added.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if there are duplicates in the
+    /// tags array, or if any of the tags is empty. No tags are added in that
+    /// case.
+    pub fn add_tags(&mut self, tags: &[Tag]) -> Result<usize, Error> {
+        check_tags(tags)?;
+
+        let own_tags = self.tags.get_or_insert_with(Vec::new);
+        let before = own_tags.len();
+
+        for tag in tags {
+            if !own_tags.contains(tag) {
+                own_tags.push(tag.clone());
+            }
         }
 
-        self.tags = Some(tags_vec);
+        own_tags.sort();
+        let added = own_tags.len() - before;
 
-        self
+        if own_tags.is_empty() {
+            self.tags = None;
+        }
+
+        Ok(added)
+    }
+
+    /// Removes the tags from this [`Entry`]. Only the tags themselves are
+    /// removed, not their subtags. Returns the number of tags removed.
+    pub fn remove_tags(&mut self, tags: &[Tag]) -> usize {
+        let own_tags = match self.tags.as_mut() {
+            Some(own_tags) => own_tags,
+            None => return 0,
+        };
+        let before = own_tags.len();
+
+        own_tags.retain(|tag| !tags.contains(tag));
+        let removed = before - own_tags.len();
+
+        if own_tags.is_empty() {
+            self.tags = None;
+        }
+
+        removed
+    }
+
+    /// Returns whether this [`Entry`] is tagged with the tag provided or any
+    /// of its descendants.
+    pub fn has_tag(&self, tag: &Tag) -> bool {
+        self.tags
+            .iter()
+            .flatten()
+            .any(|own_tag| own_tag.is_within(tag))
+    }
+
+    /// Moves the tags of this [`Entry`] from the subtree of `from` to the
+    /// subtree of `to`, merging the tags which become duplicates. Returns
+    /// whether any tags were moved.
+    pub fn retag(&mut self, from: &Tag, to: &Tag) -> bool {
+        let tags = match self.tags.as_mut() {
+            Some(tags) if tags.iter().any(|tag| tag.is_within(from)) => tags,
+            _ => return false,
+        };
+
+        for tag in tags.iter_mut() {
+            if let Some(rebased) = tag.rebase(from, to) {
+                *tag = rebased;
+            }
+        }
+
+        tags.sort();
+        tags.dedup();
+
+        true
+    }
+
+    /// Marks this [`Entry`] as opened right now. An entry that was yet to be
+    /// read is considered to be in progress from then on.
+    pub fn mark_opened(&mut self) {
+        self.last_opened = Some(Utc::now());
+
+        if self.status == Status::Want {
+            self.status = Status::Reading;
+        }
+    }
+
+    /// Records a reading session for this [`Entry`].
+    pub fn add_session(&mut self, session: Session) {
+        self.sessions.push(session);
+    }
+
+    /// Updates the reading progress of this [`Entry`]. Starting to read the
+    /// entry marks it as being read, and reading it to the end marks it as
+    /// finished.
+    pub fn set_progress(&mut self, progress: Progress) {
+        self.progress = Some(progress);
+
+        if progress.is_complete() {
+            self.status = Status::Finished;
+        } else if progress.position > 0 && self.status == Status::Want {
+            self.status = Status::Reading;
+        }
+    }
+
+    /// Toggles a bookmark at the position provided: adds one if there was
+    /// none, and removes it otherwise. Returns whether a bookmark was added.
+    pub fn toggle_bookmark(&mut self, position: usize) -> bool {
+        match self
+            .bookmarks
+            .binary_search_by_key(&position, |bookmark| bookmark.position)
+        {
+            Ok(index) => {
+                self.bookmarks.remove(index);
+                false
+            },
+            Err(index) => {
+                self.bookmarks.insert(index, Bookmark::new(position));
+                true
+            },
+        }
+    }
+
+    /// Rates this [`Entry`], replacing the review if a new one is provided.
+    pub fn rate(&mut self, rating: Rating, review: Option<String>) {
+        self.rating = Some(rating);
+
+        if review.is_some() {
+            self.review = review;
+        }
+    }
+
+    /// Returns the total time spent reading this [`Entry`].
+    pub fn time_read(&self) -> Duration {
+        self.sessions.iter().map(|session| session.duration).sum()
+    }
+
+    /// Returns the title of this [`Entry`]: the one set by hand, the one from
+    /// its BibTeX metadata, or the name of its file without the extension if
+    /// there is none.
+    pub fn title(&self) -> String {
+        self.title_with(&|field| self.get_bib_field(field))
+    }
+
+    /// Returns the authors of this [`Entry`] set by hand, or else the ones
+    /// from its BibTeX metadata, each as "First Last".
+    pub fn authors(&self) -> Vec<String> {
+        self.authors_with(&|field| self.get_bib_field(field))
+    }
+
+    /// Returns the year of publication of this [`Entry`] set by hand, or
+    /// else the one from its BibTeX metadata, either from the `year` or the
+    /// `date` field.
+    pub fn year(&self) -> Option<i32> {
+        self.year_with(&|field| self.get_bib_field(field))
+    }
+
+    /// Returns the series this [`Entry`] belongs to: the one set by hand,
+    /// or the one from the `series` and `number` fields of its BibTeX
+    /// metadata.
+    pub fn series(&self) -> Option<Series> {
+        self.series_with(&|field| self.get_bib_field(field))
+    }
+
+    /// Returns the title, the authors, the year and the series of this
+    /// [`Entry`] at once, reading its BibTeX file only once for all of them.
+    pub fn metadata(&self) -> Metadata {
+        let bib_entry = self.try_get_bib_entry().ok().flatten();
+        let field = |field: &str| bib_field(bib_entry.as_ref(), field);
+
+        Metadata {
+            title: self.title_with(&field),
+            authors: self.authors_with(&field),
+            year: self.year_with(&field),
+            series: self.series_with(&field),
+        }
+    }
+
+    /// Returns the ISBN of this [`Entry`] from its BibTeX metadata, as
+    /// ISBN-13 without hyphens, so that the ISBNs of the same book compare
+    /// equal.
+    pub fn isbn(&self) -> Option<String> {
+        let isbn = self
+            .get_bib_field("isbn")?
+            .chars()
+            .filter(|c| c.is_ascii_digit() || *c == 'X' || *c == 'x')
+            .collect::<String>()
+            .to_uppercase();
+
+        match isbn.len() {
+            13 => Some(isbn),
+            // ISBN-10 is converted by prefixing it with 978 and computing
+            // the check digit anew
+            10 => {
+                let digits = format!("978{}", &isbn[..9]);
+                let sum = digits
+                    .chars()
+                    .filter_map(|c| c.to_digit(10))
+                    .enumerate()
+                    .map(|(i, digit)| digit * if i % 2 == 0 { 1 } else { 3 })
+                    .sum::<u32>();
+
+                Some(format!("{}{}", digits, (10 - sum % 10) % 10))
+            },
+            _ => None,
+        }
+    }
+
+    /// Returns the DOI of this [`Entry`] from its BibTeX metadata, in lower
+    /// case and without the resolver prefix, e.g. `10.1000/xyz123`.
+    pub fn doi(&self) -> Option<String> {
+        let doi = self.get_bib_field("doi")?.trim().to_lowercase();
+        let doi = ["https://doi.org/", "http://dx.doi.org/", "doi:"]
+            .iter()
+            .find_map(|prefix| doi.strip_prefix(prefix))
+            .unwrap_or(&doi);
+
+        (!doi.is_empty()).then(|| doi.to_string())
+    }
+
+    /// Merges another [`Entry`] of the same work into this one. The file of
+    /// the other entry is kept as another format of this one, the tags and
+    /// the reading sessions are combined, and the reviews are joined. The
+    /// reading status is the furthest one of the two, while the rating, the
+    /// series, the metadata set by hand, the bibliography and the dates are
+    /// taken from the other entry only where this one has none. The progress
+    /// and the bookmarks stay this entry's, as they are positions in its own
+    /// file.
+    pub fn merge(&mut self, other: Entry) {
+        for path in std::iter::once(other.path).chain(other.formats) {
+            if path != self.path && !self.formats.contains(&path) {
+                self.formats.push(path);
+            }
+        }
+
+        let mut tags = self.tags.take().unwrap_or_default();
+        tags.extend(other.tags.unwrap_or_default());
+        tags.sort();
+        tags.dedup();
+        self.tags = (!tags.is_empty()).then_some(tags);
+
+        self.sessions.extend(other.sessions);
+        self.sessions.sort_by_key(|session| session.started);
+
+        self.review = match (self.review.take(), other.review) {
+            (Some(review), Some(other)) if review != other => {
+                Some(format!("{}\n\n{}", review, other))
+            },
+            (review, other) => review.or(other),
+        };
+
+        let furthest = |status: Status| match status {
+            Status::Want => 0,
+            Status::Reading => 1,
+            Status::Finished => 2,
+        };
+        if furthest(other.status) > furthest(self.status) {
+            self.status = other.status;
+        }
+
+        self.rating = self.rating.or(other.rating);
+        self.series = self.series.take().or(other.series);
+        self.title = self.title.take().or(other.title);
+        self.authors = self.authors.take().or(other.authors);
+        self.year = self.year.or(other.year);
+        self.bib_path = self.bib_path.take().or(other.bib_path);
+        self.last_opened = self.last_opened.max(other.last_opened);
+        self.added = match (self.added, other.added) {
+            (Some(added), Some(other)) => Some(added.min(other)),
+            (added, other) => added.or(other),
+        };
+    }
+
+    /// Returns the title of this [`Entry`], taking the fields of its BibTeX
+    /// metadata from the function provided.
+    fn title_with(&self, field: &dyn Fn(&str) -> Option<String>) -> String {
+        if let Some(title) = &self.title {
+            return title.clone();
+        }
+
+        field("title").unwrap_or_else(|| {
+            self.path
+                .file_stem()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .into_owned()
+        })
+    }
+
+    /// Returns the authors of this [`Entry`], taking the fields of its
+    /// BibTeX metadata from the function provided.
+    fn authors_with(
+        &self,
+        field: &dyn Fn(&str) -> Option<String>,
+    ) -> Vec<String> {
+        if let Some(authors) = &self.authors {
+            return authors.clone();
+        }
+
+        let authors = match field("author") {
+            Some(authors) => authors,
+            None => return vec![],
+        };
+
+        authors
+            .split(" and ")
+            .map(|author| match author.split_once(',') {
+                Some((last, first)) => {
+                    format!("{} {}", first.trim(), last.trim())
+                },
+                None => author.trim().to_string(),
+            })
+            .filter(|author| !author.is_empty())
+            .collect()
+    }
+
+    /// Returns the year of publication of this [`Entry`], taking the fields
+    /// of its BibTeX metadata from the function provided.
+    fn year_with(&self, field: &dyn Fn(&str) -> Option<String>) -> Option<i32> {
+        if self.year.is_some() {
+            return self.year;
+        }
+
+        let date = field("year").or_else(|| field("date"))?;
+
+        date.trim().get(..4)?.parse().ok()
+    }
+
+    /// Returns the series of this [`Entry`], taking the fields of its BibTeX
+    /// metadata from the function provided.
+    fn series_with(
+        &self,
+        field: &dyn Fn(&str) -> Option<String>,
+    ) -> Option<Series> {
+        if self.series.is_some() {
+            return self.series.clone();
+        }
+
+        let name = field("series")?;
+        let number =
+            field("number").and_then(|number| number.trim().parse().ok());
+
+        (!name.trim().is_empty()).then(|| Series {
+            name: name.trim().to_string(),
+            number,
+        })
+    }
+
+    /// Returns the verbatim value of a field of the BibTeX metadata for this
+    /// [`Entry`], if there is any. A BibTeX file which can't be read is taken
+    /// for no metadata, so that it doesn't stand in the way of listing,
+    /// sorting or finding the entry.
+    fn get_bib_field(&self, field: &str) -> Option<String> {
+        bib_field(self.try_get_bib_entry().ok().flatten().as_ref(), field)
     }
 
     /// Returns the BibTeX metadata for this [`Entry`], and None if
@@ -112,36 +591,80 @@ impl Entry {
     ///
     /// Panics if accessing or parsing the BibTeX file has failed.
     pub fn get_bib_entry(&self) -> Option<BibEntry> {
-        // Check if the entry has a BibTeX file associated with it
-        self.bib_path.as_ref()?;
+        self.try_get_bib_entry().unwrap_or_else(|e| panic!("{}", e))
+    }
 
-        let bib_path = self.bib_path.clone().unwrap();
+    /// Returns the BibTeX metadata for this [`Entry`], and None if
+    /// the bib_path is None.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if reading or parsing the BibTeX
+    /// file fails, or if it has no entry with the cite key of its name.
+    pub fn try_get_bib_entry(&self) -> Result<Option<BibEntry>, Error> {
+        // Check if the entry has a BibTeX file associated with it
+        let bib_path = match &self.bib_path {
+            Some(bib_path) => bib_path,
+            None => return Ok(None),
+        };
 
         // Parsing the file and saving the bibliographical entry
-        let bib_str = read_to_string(bib_path.clone()).unwrap_or_else(|e| {
-            panic!("Failed to read the contents of the file: {}", e)
-        });
+        let bib_str = read_to_string(bib_path)
+            .map_err(|e| Error::BibRead(e.to_string()))?;
 
-        let bibliography = Bibliography::parse(&bib_str).unwrap_or_else(|e| {
-            panic!("Failed to parse the bibliographic string: {}", e)
-        });
+        let bibliography = Bibliography::parse(&bib_str)
+            .map_err(|e| Error::BibParse(e.to_string()))?;
 
         let cite_key = bib_path
             .file_stem()
-            .unwrap_or_else(|| panic!("Failed to extract the cite key"));
-        let cite_key = cite_key.to_str().unwrap();
+            .and_then(|cite_key| cite_key.to_str())
+            .ok_or(Error::BibCiteKey)?;
+
+        let bib_entry = bibliography.get(cite_key).ok_or(Error::BibCiteKey)?;
+
+        Ok(Some(bib_entry.clone()))
+    }
+}
+
+/// Returns the verbatim value of a field of the BibTeX metadata, if there
+/// is any.
+fn bib_field(bib_entry: Option<&BibEntry>, field: &str) -> Option<String> {
+    bib_entry?.get(field).map(|chunks| chunks.format_verbatim())
+}
+
+/// Generates the identifier of a new entry from its path and the time it
+/// was added, so that adding the same file again yields another one.
+fn new_id(path: &Path, added: DateTime<Utc>) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(path.to_string_lossy().as_bytes());
+    hasher.update(added.to_rfc3339().as_bytes());
+
+    hasher.finalize()[..6]
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
 
-        let bib_entry = bibliography.get(cite_key).unwrap_or_else(|| {
-            panic!("Failed to get the bibliographic entry: invalid cite key")
-        });
+/// Checks that none of the tags is empty or given more than once.
+fn check_tags(tags: &[Tag]) -> Result<(), Error> {
+    for (i, tag) in tags.iter().enumerate() {
+        if tag.keyword().is_empty() {
+            return Err(Error::EmptyTag);
+        }
 
-        Some(bib_entry.clone())
+        if tags[..i].contains(tag) {
+            return Err(Error::DuplicateTag(tag.clone()));
+        }
     }
+
+    Ok(())
 }
 
 #[cfg(test)]
 mod tests {
-    use biblatex::{ChunksExt, EntryType};
+    use std::fs::write;
+
+    use biblatex::EntryType;
 
     use utils::test::setup;
 
@@ -162,7 +685,8 @@ mod tests {
 
         let entry1 = Entry::new(dir.path().join("link1.txt").to_str().unwrap());
         let entry2 = Entry::new(dir.path().join("link2.txt").to_str().unwrap())
-            .with_tags(&[Tag::new("fiction")]);
+            .with_tags(&[Tag::new("fiction")])
+            .unwrap();
 
         assert!(entry1 == entry2);
     }
@@ -188,6 +712,22 @@ mod tests {
         let _bib_entry = book.get_bib_entry().unwrap();
     }
 
+    #[test]
+    fn unreadable_bib_file() {
+        let dir = setup();
+
+        let book = Entry::new(dir.path().join("book.txt").to_str().unwrap())
+            .with_bib(dir.path().join("empty.bib").to_str().unwrap());
+        assert!(matches!(book.try_get_bib_entry(), Err(Error::BibCiteKey)));
+
+        let mut moved = book.clone();
+        moved.bib_path = Some(dir.path().join("moved.bib"));
+        assert!(matches!(moved.try_get_bib_entry(), Err(Error::BibRead(_))));
+
+        let book = book.with_bib(dir.path().join("book.bib").to_str().unwrap());
+        assert!(book.try_get_bib_entry().unwrap().is_some());
+    }
+
     #[test]
     fn correct_bib_file() {
         let dir = setup();
@@ -201,11 +741,189 @@ mod tests {
     }
 
     #[test]
-    #[should_panic]
+    fn bib_metadata() {
+        let dir = setup();
+
+        let book = Entry::new(dir.path().join("book.txt").to_str().unwrap());
+        assert_eq!(book.title(), "book");
+        assert!(book.authors().is_empty());
+        assert_eq!(book.year(), None);
+
+        let book = book.with_bib(dir.path().join("book.bib").to_str().unwrap());
+        assert_eq!(book.title(), "A Good Book");
+        assert_eq!(book.authors(), vec!["Writer Good"]);
+        assert_eq!(book.year(), Some(2022));
+
+        let metadata = book.metadata();
+        assert_eq!(metadata.title, book.title());
+        assert_eq!(metadata.authors, book.authors());
+        assert_eq!(metadata.year, book.year());
+    }
+
+    #[test]
+    fn first_open_starts_reading() {
+        let dir = setup();
+
+        let mut book =
+            Entry::new(dir.path().join("book.txt").to_str().unwrap());
+        assert_eq!(book.status, Status::Want);
+        assert!(book.last_opened.is_none());
+
+        book.mark_opened();
+        assert_eq!(book.status, Status::Reading);
+        assert!(book.last_opened.is_some());
+
+        book.status = Status::Finished;
+        book.mark_opened();
+        assert_eq!(book.status, Status::Finished);
+    }
+
+    #[test]
+    fn progress_changes_status() {
+        let dir = setup();
+
+        let mut book =
+            Entry::new(dir.path().join("book.txt").to_str().unwrap());
+
+        // An empty document isn't finished just by opening it
+        book.set_progress(Progress::new(0, 0));
+        assert_eq!(book.status, Status::Want);
+
+        book.set_progress(Progress::new(0, 100));
+        assert_eq!(book.status, Status::Want);
+
+        book.set_progress(Progress::new(50, 100));
+        assert_eq!(book.status, Status::Reading);
+
+        book.set_progress(Progress::new(100, 100));
+        assert_eq!(book.status, Status::Finished);
+    }
+
+    #[test]
+    fn bookmarks_sorted() {
+        let dir = setup();
+
+        let mut book =
+            Entry::new(dir.path().join("book.txt").to_str().unwrap());
+
+        assert!(book.toggle_bookmark(300));
+        assert!(book.toggle_bookmark(100));
+        assert!(book.toggle_bookmark(200));
+        assert!(!book.toggle_bookmark(300));
+
+        let positions = book
+            .bookmarks
+            .iter()
+            .map(|bookmark| bookmark.position)
+            .collect::<Vec<usize>>();
+
+        assert_eq!(positions, vec![100, 200]);
+    }
+
+    #[test]
+    fn retagged_subtree() {
+        let dir = setup();
+
+        let mut book =
+            Entry::new(dir.path().join("book.txt").to_str().unwrap())
+                .with_tags(&[
+                    Tag::new("algorithms"),
+                    Tag::new("cs/algo"),
+                    Tag::new("cs/algo/graphs"),
+                ])
+                .unwrap();
+
+        assert!(book.has_tag(&Tag::new("cs")));
+        assert!(!book.has_tag(&Tag::new("cs/algorithms")));
+
+        assert!(book.retag(&Tag::new("cs/algo"), &Tag::new("algorithms")));
+        assert!(!book.retag(&Tag::new("math"), &Tag::new("maths")));
+
+        assert_eq!(
+            book.tags,
+            Some(vec![Tag::new("algorithms"), Tag::new("algorithms/graphs")])
+        );
+    }
+
+    #[test]
+    fn identifiers() {
+        let dir = setup();
+        let bib = dir.path().join("sicp.bib");
+        write(
+            &bib,
+            "@book{sicp, title = {SICP}, isbn = {0-262-51087-1}, \
+             doi = {https://doi.org/10.1000/XYZ}}",
+        )
+        .unwrap();
+
+        let book = Entry::new(dir.path().join("book.txt").to_str().unwrap())
+            .with_bib(bib.to_str().unwrap());
+
+        assert_eq!(book.isbn().as_deref(), Some("9780262510875"));
+        assert_eq!(book.doi().as_deref(), Some("10.1000/xyz"));
+    }
+
+    #[test]
+    fn merged_entries() {
+        let dir = setup();
+
+        let mut book =
+            Entry::new(dir.path().join("book.txt").to_str().unwrap())
+                .with_tags(&[Tag::new("cs")])
+                .unwrap();
+        book.review = Some("Dense".to_string());
+
+        let mut other =
+            Entry::new(dir.path().join("another_book.txt").to_str().unwrap())
+                .with_tags(&[Tag::new("cs"), Tag::new("lisp")])
+                .unwrap();
+        other.status = Status::Finished;
+        other.rating = Some(Rating::try_from(4.0).unwrap());
+        other.review = Some("Classic".to_string());
+        other.add_session(Session::new(Utc::now(), Duration::from_secs(60)));
+        let other_path = other.path.clone();
+
+        book.merge(other);
+
+        assert_eq!(book.formats, vec![other_path]);
+        assert_eq!(book.tags, Some(vec![Tag::new("cs"), Tag::new("lisp")]));
+        assert_eq!(book.status, Status::Finished);
+        assert!(book.rating.is_some());
+        assert_eq!(book.review.as_deref(), Some("Dense\n\nClassic"));
+        assert_eq!(book.sessions.len(), 1);
+    }
+
+    #[test]
     fn duplicate_tags() {
         let dir = setup();
 
-        let _ = Entry::new(dir.path().join("book.txt").to_str().unwrap())
+        let entry = Entry::new(dir.path().join("book.txt").to_str().unwrap())
             .with_tags(&[Tag::new("fiction"), Tag::new("fiction")]);
+
+        assert_eq!(
+            entry.unwrap_err(),
+            Error::DuplicateTag(Tag::new("fiction"))
+        );
+    }
+
+    #[test]
+    fn edited_tags() {
+        let dir = setup();
+        let mut entry =
+            Entry::new(dir.path().join("book.txt").to_str().unwrap());
+
+        assert_eq!(entry.add_tags(&[Tag::new("cs"), Tag::new("lisp")]), Ok(2));
+        assert_eq!(entry.add_tags(&[Tag::new("cs"), Tag::new("sicp")]), Ok(1));
+        assert_eq!(entry.add_tags(&[Tag::new("/")]), Err(Error::EmptyTag));
+        assert_eq!(
+            entry.add_tags(&[Tag::new("ml"), Tag::new("ml")]),
+            Err(Error::DuplicateTag(Tag::new("ml")))
+        );
+
+        assert_eq!(entry.remove_tags(&[Tag::new("cs"), Tag::new("ml")]), 1);
+        assert_eq!(entry.tags, Some(vec![Tag::new("lisp"), Tag::new("sicp")]));
+
+        assert_eq!(entry.remove_tags(&[Tag::new("lisp"), Tag::new("sicp")]), 2);
+        assert_eq!(entry.tags, None);
     }
 }
@@ -0,0 +1,209 @@
+use super::entry::Entry;
+
+/// The score of every character of the pattern matched
+const MATCH: i64 = 16;
+/// The bonus for a match at the start of a word
+const WORD_START: i64 = 10;
+/// The bonus for a match right after the previous one
+const CONSECUTIVE: i64 = 8;
+/// The penalty for every character skipped between two matches
+const GAP: i64 = 1;
+
+/// Returns how well the pattern matches the text, or None if it doesn't.
+///
+/// The text matches if it has all of the characters of the pattern in the
+/// same order, ignoring case, e.g. `sicp` matches `Structure and
+/// Interpretation of Computer Programs`. The matches at the starts of words
+/// and the runs of consecutive matches score higher, while the characters
+/// skipped between the matches lower the score.
+pub fn score(pattern: &str, text: &str) -> Option<i64> {
+    let pattern = pattern
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .flat_map(char::to_lowercase)
+        .collect::<Vec<char>>();
+    let original = text.chars().collect::<Vec<char>>();
+    let text = original
+        .iter()
+        .map(|c| c.to_lowercase().next().unwrap_or(*c))
+        .collect::<Vec<char>>();
+
+    if pattern.is_empty() {
+        return Some(0);
+    }
+
+    let bonus = |i: usize| match i.checked_sub(1).map(|i| original[i]) {
+        None => WORD_START,
+        Some(prev) if !prev.is_alphanumeric() => WORD_START,
+        Some(prev) if prev.is_lowercase() && original[i].is_uppercase() => {
+            WORD_START
+        },
+        Some(_) => 0,
+    };
+
+    // The best scores of the pattern up to the current character, with the
+    // character matched at each position of the text
+    let mut previous = text
+        .iter()
+        .enumerate()
+        .map(|(i, &c)| (c == pattern[0]).then(|| MATCH + bonus(i)))
+        .collect::<Vec<Option<i64>>>();
+
+    for &expected in pattern.iter().skip(1) {
+        let mut current = vec![None; text.len()];
+        // The best of the scores before the previous position, each raised
+        // by the penalty the gap from it to the start would have
+        let mut best_before: Option<i64> = None;
+
+        for i in 1..text.len() {
+            if i >= 2 {
+                if let Some(score) = previous[i - 2] {
+                    let raised = score + GAP * (i as i64 - 2);
+                    best_before = best_before.max(Some(raised));
+                }
+            }
+
+            if text[i] != expected {
+                continue;
+            }
+
+            let after_gap = best_before.map(|best| best - GAP * (i as i64 - 1));
+            let consecutive = previous[i - 1].map(|score| score + CONSECUTIVE);
+
+            current[i] = after_gap
+                .max(consecutive)
+                .map(|score| score + MATCH + bonus(i));
+        }
+
+        previous = current;
+    }
+
+    previous.into_iter().flatten().max()
+}
+
+/// The texts of an entry a pattern is matched against: its title, its
+/// authors, its tags and its file name. Reading them may take parsing the
+/// BibTeX file of the entry, so they are best kept while the pattern
+/// changes.
+#[derive(Clone, Debug)]
+pub struct Fields(Vec<String>);
+
+impl Fields {
+    /// Reads the texts of the entry which are matched against a pattern.
+    pub fn of(entry: &Entry) -> Self {
+        let mut fields = vec![entry.title()];
+        fields.extend(entry.authors());
+        fields.extend(entry.tags.iter().flatten().map(|tag| tag.to_string()));
+        fields.extend(
+            entry
+                .path
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned()),
+        );
+
+        Self(fields)
+    }
+
+    /// Returns how well the pattern matches the fields, or None if it
+    /// doesn't.
+    ///
+    /// Every word of the pattern has to match one of the fields, and the
+    /// score is the sum of the best scores of the words.
+    pub fn score(&self, pattern: &str) -> Option<i64> {
+        pattern
+            .split_whitespace()
+            .map(|word| {
+                self.0.iter().filter_map(|field| score(word, field)).max()
+            })
+            .sum()
+    }
+}
+
+/// Returns how well the pattern matches the entry, or None if it doesn't.
+///
+/// Every word of the pattern has to match the title, one of the authors,
+/// one of the tags or the file name of the entry, and the score is the sum
+/// of the best scores of the words.
+pub fn score_entry(pattern: &str, entry: &Entry) -> Option<i64> {
+    Fields::of(entry).score(pattern)
+}
+
+/// Keeps only the items whose fields match the pattern, ranked by how well
+/// they match, the best first. The items matching equally well stay in
+/// their order.
+pub fn rank<T, F>(pattern: &str, items: &mut Vec<T>, fields: F)
+where
+    F: Fn(&T) -> &Fields,
+{
+    let mut scored = items
+        .drain(..)
+        .filter_map(|item| {
+            fields(&item).score(pattern).map(|score| (score, item))
+        })
+        .collect::<Vec<(i64, T)>>();
+
+    scored.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+
+    items.extend(scored.into_iter().map(|(_, item)| item));
+}
+
+#[cfg(test)]
+mod tests {
+    use utils::test::setup;
+
+    use crate::{entry::*, fuzzy::*, tag::*};
+
+    #[test]
+    fn scoring() {
+        let title = "Structure and Interpretation of Computer Programs";
+
+        assert!(score("sicp", title).is_some());
+        assert!(score("SICP", "sicp.pdf") > score("sicp", title));
+        assert!(score("pics", title).is_some());
+        assert_eq!(score("sicpx", title), None);
+
+        // Runs of matches and the starts of words are preferred
+        assert!(score("prog", title) > score("pgrm", title));
+        assert!(score("dr", "DarkRoom") > score("dr", "daredevil"));
+    }
+
+    #[test]
+    fn ranked_entries() {
+        let dir = setup();
+        let entry =
+            |file: &str| Entry::new(dir.path().join(file).to_str().unwrap());
+
+        let book = entry("book.txt")
+            .with_bib(dir.path().join("book.bib").to_str().unwrap());
+        let article = entry("article.txt")
+            .with_tags(&[Tag::new("books/to-read")])
+            .unwrap();
+        let another = entry("another_book.txt");
+
+        let fields =
+            [&article, &another, &book].map(|entry| (entry, Fields::of(entry)));
+
+        let mut entries = fields.iter().collect::<Vec<_>>();
+        rank("good writer", &mut entries, |(_, fields)| fields);
+        assert_eq!(entries[0].0, &book);
+        assert_eq!(entries.len(), 1);
+
+        let mut entries = fields.iter().collect::<Vec<_>>();
+        rank("anbook", &mut entries, |(_, fields)| fields);
+        assert_eq!(entries[0].0, &another);
+        assert_eq!(entries.len(), 1);
+
+        // The entries matching equally well stay in their order
+        let mut entries = fields.iter().collect::<Vec<_>>();
+        rank("book", &mut entries, |(_, fields)| fields);
+        let entries =
+            entries.iter().map(|(entry, _)| *entry).collect::<Vec<_>>();
+        assert_eq!(entries, [&article, &another, &book]);
+
+        // A BibTeX file which can't be read is taken for no metadata
+        let broken = entry("book.txt")
+            .with_bib(dir.path().join("empty.bib").to_str().unwrap());
+        assert!(score_entry("book", &broken).is_some());
+        assert!(score_entry("good writer", &broken).is_none());
+    }
+}
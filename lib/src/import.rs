@@ -0,0 +1,221 @@
+use std::{
+    fmt::Display,
+    fs::read_to_string,
+    io,
+    path::{Path, PathBuf},
+};
+
+use biblatex::Bibliography;
+use glob::{Pattern, PatternError};
+use walkdir::WalkDir;
+
+/// Errors associated with importing the files.
+#[derive(Debug)]
+pub enum Error {
+    /// An exclusion pattern is malformed.
+    InvalidPattern(String, PatternError),
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::InvalidPattern(pattern, e) => {
+                write!(f, "Invalid pattern '{}': {}", pattern, e)
+            },
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// Which of the files in a directory tree are imported.
+#[derive(Clone, Debug, Default)]
+pub struct Filter {
+    /// The extensions of the files imported, in lower case and without the
+    /// dot, or all of the files if empty
+    extensions: Vec<String>,
+    /// The patterns of the files and the directories left out
+    exclude: Vec<Pattern>,
+}
+
+impl Filter {
+    /// Creates a new [`Filter`] importing the files with the extensions
+    /// provided, e.g. `pdf`, or all of the files if there are none, except
+    /// for the ones matching any of the glob patterns, e.g. `*draft*`. The
+    /// patterns are matched against the names of the files and the
+    /// directories, as well as against their paths relative to the root.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if any of the patterns is
+    /// malformed.
+    pub fn new(extensions: &[&str], exclude: &[&str]) -> Result<Self, Error> {
+        let exclude = exclude
+            .iter()
+            .map(|pattern| {
+                Pattern::new(pattern)
+                    .map_err(|e| Error::InvalidPattern(pattern.to_string(), e))
+            })
+            .collect::<Result<Vec<Pattern>, Error>>()?;
+
+        Ok(Self {
+            extensions: extensions
+                .iter()
+                .map(|ext| ext.trim_start_matches('.').to_lowercase())
+                .collect(),
+            exclude,
+        })
+    }
+
+    /// Returns whether the file or the directory, relative to the root, is
+    /// left out.
+    fn excludes(&self, relative: &Path) -> bool {
+        let name = relative.file_name().unwrap_or_default().to_string_lossy();
+
+        self.exclude.iter().any(|pattern| {
+            pattern.matches(&name) || pattern.matches_path(relative)
+        })
+    }
+
+    /// Returns whether the file has one of the extensions imported.
+    fn has_extension(&self, path: &Path) -> bool {
+        let ext = path
+            .extension()
+            .map(|ext| ext.to_string_lossy().to_lowercase());
+
+        match ext {
+            // BibTeX files are only ever paired with the files next to them
+            Some(ext) if ext == "bib" => false,
+            Some(ext) => {
+                self.extensions.is_empty() || self.extensions.contains(&ext)
+            },
+            None => self.extensions.is_empty(),
+        }
+    }
+}
+
+/// A file found to be imported.
+#[derive(Debug, PartialEq)]
+pub struct Found {
+    /// The path to the file
+    pub path: PathBuf,
+    /// The BibTeX file next to it, with the same name and an entry of the
+    /// same cite key, if there is one
+    pub bib_path: Option<PathBuf>,
+}
+
+/// Walks the directory tree under the root, following the symbolic links,
+/// and returns the files passing the filter in the order of their paths.
+/// A file `name.ext` is paired with the BibTeX file `name.bib` next to it,
+/// as long as it has an entry with the cite key `name`. Also returns the
+/// paths which couldn't be read, along with the errors.
+pub fn find_files(
+    root: &Path,
+    filter: &Filter,
+) -> (Vec<Found>, Vec<(PathBuf, io::Error)>) {
+    let mut found = vec![];
+    let mut failed = vec![];
+
+    let walker = WalkDir::new(root)
+        .follow_links(true)
+        .sort_by(|a, b| a.file_name().cmp(b.file_name()))
+        .into_iter()
+        .filter_entry(|dir_entry| {
+            let relative =
+                dir_entry.path().strip_prefix(root).unwrap_or(Path::new(""));
+
+            relative.as_os_str().is_empty() || !filter.excludes(relative)
+        });
+
+    for dir_entry in walker {
+        let dir_entry = match dir_entry {
+            Ok(dir_entry) => dir_entry,
+            Err(e) => {
+                let path = e.path().unwrap_or(root).to_path_buf();
+                failed.push((path, e.into()));
+                continue;
+            },
+        };
+
+        let path = dir_entry.path();
+
+        if dir_entry.file_type().is_file() && filter.has_extension(path) {
+            found.push(Found {
+                path: path.to_path_buf(),
+                bib_path: sibling_bib(path),
+            });
+        }
+    }
+
+    (found, failed)
+}
+
+/// Returns the BibTeX file next to the file provided with the same name, if
+/// it has an entry with the name of the file as its cite key.
+fn sibling_bib(path: &Path) -> Option<PathBuf> {
+    let bib_path = path.with_extension("bib");
+    let cite_key = path.file_stem()?.to_str()?;
+
+    let bib_str = read_to_string(&bib_path).ok()?;
+    Bibliography::parse(&bib_str).ok()?.get(cite_key)?;
+
+    Some(bib_path)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::{create_dir, write};
+
+    use utils::test::setup;
+
+    use crate::import::*;
+
+    #[test]
+    fn filtered_files() {
+        let dir = setup();
+        let root = dir.path().join("library");
+        let file = |path: &str, contents: &str| {
+            let path = root.join(path);
+            write(&path, contents).unwrap();
+
+            path
+        };
+
+        create_dir(&root).unwrap();
+        create_dir(root.join("drafts")).unwrap();
+        create_dir(root.join("sf")).unwrap();
+
+        let sicp = file("sicp.pdf", "");
+        let sicp_bib = file("sicp.bib", "@book{sicp, title = {SICP}}");
+        let dune = file("sf/Dune.EPUB", "");
+        // The cite key doesn't match the name of the file
+        file("sf/Dune.bib", "@book{dune, title = {Dune}}");
+        file("notes.txt", "");
+        file("sicp-draft.pdf", "");
+        file("drafts/book.pdf", "");
+
+        let filter = Filter::new(&["pdf", ".epub"], &["*draft*"]).unwrap();
+        let (found, failed) = find_files(&root, &filter);
+
+        assert!(failed.is_empty());
+        assert_eq!(
+            found,
+            [
+                Found {
+                    path: dune,
+                    bib_path: None,
+                },
+                Found {
+                    path: sicp,
+                    bib_path: Some(sicp_bib),
+                },
+            ]
+        );
+
+        // Every file but the BibTeX ones is found without a filter
+        let (found, _) = find_files(&root, &Filter::default());
+        assert_eq!(found.len(), 5);
+
+        assert!(Filter::new(&[], &["[draft"]).is_err());
+    }
+}
@@ -0,0 +1,350 @@
+use std::{
+    fmt::Display,
+    fs::{copy, create_dir_all, read, remove_file, write},
+    mem::take,
+    path::{Path, PathBuf},
+};
+
+use bincode::{deserialize, serialize};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::shelf::Shelf;
+
+/// The number of operations which can be undone
+pub const LENGTH: usize = 50;
+
+/// The name of the file in the directory of the [`Journal`] listing the
+/// operations
+const INDEX: &str = "index";
+
+/// Errors associated with [`Journal`] operations.
+#[derive(Debug)]
+pub enum Error {
+    /// Writing the [`Journal`] to the directory specified failed.
+    Write,
+    /// Reading the [`Journal`] from the directory specified failed.
+    Read,
+    /// A [`Shelf`] recorded in the [`Journal`] couldn't be restored.
+    Snapshot,
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Write => write!(f, "Writing to the journal failed"),
+            Error::Read => write!(f, "Reading the journal failed"),
+            Error::Snapshot => {
+                write!(f, "The shelf recorded in the journal is corrupted")
+            },
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// An operation on a [`Shelf`], along with the shelf as it was on the other
+/// side of it: before the operation if it can be undone, and after it if it
+/// can be redone.
+#[derive(Deserialize, Serialize)]
+pub struct Record {
+    /// The moment the operation was done
+    pub date: DateTime<Utc>,
+    /// What the operation did, e.g. `Removed 'Dune'`
+    pub description: String,
+    /// The name of the file in the directory of the [`Journal`] holding the
+    /// [`Shelf`], as it is saved
+    snapshot: String,
+}
+
+/// A history of the operations on a [`Shelf`], which can be undone and
+/// redone by restoring the shelf as it was. Only the last [`LENGTH`]
+/// operations are kept.
+///
+/// Is kept in a directory: the operations are listed in a file of their
+/// own, and each of the shelves recorded is a copy of the file the shelf
+/// was saved to, so that recording an operation doesn't rewrite the rest.
+#[derive(Default, Deserialize, Serialize)]
+pub struct Journal {
+    /// The directory the journal is kept in
+    #[serde(skip)]
+    dir: PathBuf,
+    /// The operations which can be undone, from oldest to newest
+    done: Vec<Record>,
+    /// The operations which can be redone, from newest to oldest undone
+    undone: Vec<Record>,
+    /// The number in the name of the next snapshot file
+    next: u64,
+}
+
+impl Journal {
+    /// Creates an empty [`Journal`] kept in the directory provided. Nothing
+    /// is written until it is saved.
+    pub fn new<P>(dir: P) -> Self
+    where
+        P: AsRef<Path>,
+    {
+        Self {
+            dir: dir.as_ref().to_path_buf(),
+            ..Default::default()
+        }
+    }
+
+    /// Records an operation, given the file the [`Shelf`] was saved to
+    /// before it, which is copied. A shelf which hasn't been saved yet is
+    /// recorded as an empty one. The operations undone until then can no
+    /// longer be redone.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if copying the file fails.
+    pub fn record<P>(
+        &mut self,
+        description: &str,
+        before: P,
+    ) -> Result<(), Error>
+    where
+        P: AsRef<Path>,
+    {
+        create_dir_all(&self.dir).map_err(|_| Error::Write)?;
+
+        let snapshot = self.next_snapshot();
+        let path = self.dir.join(&snapshot);
+
+        match before.as_ref().exists() {
+            true => {
+                copy(before, &path).map_err(|_| Error::Write)?;
+            },
+            false => {
+                let bytes =
+                    Shelf::default().to_bytes().map_err(|_| Error::Write)?;
+                write(&path, bytes).map_err(|_| Error::Write)?;
+            },
+        }
+
+        self.done.push(Record {
+            date: Utc::now(),
+            description: description.to_string(),
+            snapshot,
+        });
+
+        for record in take(&mut self.undone) {
+            self.forget(record);
+        }
+
+        if self.done.len() > LENGTH {
+            let dropped = self.done.len() - LENGTH;
+
+            for record in self.done.drain(..dropped).collect::<Vec<_>>() {
+                self.forget(record);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Undoes the last operation, given the [`Shelf`] as it is now. Returns
+    /// the shelf as it was before the operation along with its description,
+    /// or None if there is nothing to undo.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if either of the shelves can't be
+    /// read or written. The journal is unchanged in that case.
+    pub fn undo(
+        &mut self,
+        current: &Shelf,
+    ) -> Result<Option<(Shelf, String)>, Error> {
+        self.step(true, current)
+    }
+
+    /// Redoes the last operation undone, given the [`Shelf`] as it is now.
+    /// Returns the shelf as it was after the operation along with its
+    /// description, or None if there is nothing to redo.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if either of the shelves can't be
+    /// read or written. The journal is unchanged in that case.
+    pub fn redo(
+        &mut self,
+        current: &Shelf,
+    ) -> Result<Option<(Shelf, String)>, Error> {
+        self.step(false, current)
+    }
+
+    /// Restores the shelf of the last record done (or undone), and moves the
+    /// record to the other list with the current shelf in its place.
+    fn step(
+        &mut self,
+        undo: bool,
+        current: &Shelf,
+    ) -> Result<Option<(Shelf, String)>, Error> {
+        let from = match undo {
+            true => &self.done,
+            false => &self.undone,
+        };
+        let record = match from.last() {
+            Some(record) => record,
+            None => return Ok(None),
+        };
+
+        let path = self.dir.join(&record.snapshot);
+        let bytes = read(&path).map_err(|_| Error::Snapshot)?;
+        let shelf = Shelf::from_bytes(&bytes).map_err(|_| Error::Snapshot)?;
+
+        let snapshot = self.next_snapshot();
+        let bytes = current.to_bytes().map_err(|_| Error::Write)?;
+        write(self.dir.join(&snapshot), bytes).map_err(|_| Error::Write)?;
+        let _ = remove_file(path);
+
+        let (from, to) = match undo {
+            true => (&mut self.done, &mut self.undone),
+            false => (&mut self.undone, &mut self.done),
+        };
+        let record = from.pop().unwrap();
+        let description = record.description.clone();
+        to.push(Record { snapshot, ..record });
+
+        Ok(Some((shelf, description)))
+    }
+
+    /// Returns the operations which can be undone, from newest to oldest.
+    pub fn done(&self) -> impl Iterator<Item = &Record> {
+        self.done.iter().rev()
+    }
+
+    /// Returns the operations which can be redone, from the next one to be
+    /// redone to the last.
+    pub fn undone(&self) -> impl Iterator<Item = &Record> {
+        self.undone.iter().rev()
+    }
+
+    /// Returns the name of a new snapshot file.
+    fn next_snapshot(&mut self) -> String {
+        self.next += 1;
+
+        format!("{}.db", self.next)
+    }
+
+    /// Removes the snapshot file of a record which is no longer kept.
+    fn forget(&self, record: Record) {
+        let _ = remove_file(self.dir.join(record.snapshot));
+    }
+
+    /// Writes the list of the operations into the directory of the
+    /// [`Journal`], in binary format.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if creating the directory,
+    /// serializing the list, or writing it to the file fails.
+    pub fn save(&self) -> Result<(), Error> {
+        create_dir_all(&self.dir).map_err(|_| Error::Write)?;
+
+        let binary_data = serialize(&self).map_err(|_| Error::Write)?;
+        write(self.dir.join(INDEX), binary_data).map_err(|_| Error::Write)
+    }
+
+    /// Reads a [`Journal`] from the directory provided, or returns an empty
+    /// [`Journal`] if it has not been saved yet.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the list of the operations
+    /// exists, but reading and deserializing it fails.
+    pub fn open_or_default<P>(dir: P) -> Result<Journal, Error>
+    where
+        P: AsRef<Path>,
+    {
+        let index = dir.as_ref().join(INDEX);
+
+        if !index.exists() {
+            return Ok(Journal::new(dir));
+        }
+
+        let binary_data = read(index).map_err(|_| Error::Read)?;
+        let journal: Journal =
+            deserialize(&binary_data).map_err(|_| Error::Read)?;
+
+        Ok(Journal {
+            dir: dir.as_ref().to_path_buf(),
+            ..journal
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::read_dir;
+
+    use utils::test::setup;
+
+    use crate::{entry::Entry, journal::*, shelf::Shelf};
+
+    #[test]
+    fn undone_and_redone_operations() {
+        let dir = setup();
+        let path = |file: &str| dir.path().join(file).display().to_string();
+        let db = dir.path().join("db");
+        let journal_dir = dir.path().join("db.journal");
+
+        let mut shelf = Shelf::default();
+        let mut journal = Journal::new(&journal_dir);
+        assert!(journal.undo(&shelf).unwrap().is_none());
+
+        journal.record("Added 'book'", &db).unwrap();
+        shelf.add(&Entry::new(&path("book.txt"))).unwrap();
+        shelf.save(&db).unwrap();
+        journal.record("Added 'article'", &db).unwrap();
+        shelf.add(&Entry::new(&path("article.txt"))).unwrap();
+        shelf.save(&db).unwrap();
+
+        let (shelf, description) = journal.undo(&shelf).unwrap().unwrap();
+        assert_eq!(description, "Added 'article'");
+        assert_eq!(shelf.entries.len(), 1);
+
+        let (shelf, _) = journal.undo(&shelf).unwrap().unwrap();
+        assert!(shelf.entries.is_empty());
+        assert!(journal.undo(&shelf).unwrap().is_none());
+
+        let (shelf, description) = journal.redo(&shelf).unwrap().unwrap();
+        assert_eq!(description, "Added 'book'");
+        assert_eq!(shelf.entries.len(), 1);
+        assert_eq!(journal.done().count(), 1);
+        assert_eq!(journal.undone().count(), 1);
+
+        // A new operation can't be followed by the ones undone before it
+        journal.record("Removed 'book'", &db).unwrap();
+        assert!(journal.redo(&shelf).unwrap().is_none());
+
+        journal.save().unwrap();
+
+        let journal = Journal::open_or_default(&journal_dir).unwrap();
+        let descriptions = journal
+            .done()
+            .map(|record| record.description.as_str())
+            .collect::<Vec<&str>>();
+        assert_eq!(descriptions, ["Removed 'book'", "Added 'book'"]);
+
+        // Only the list and the shelves of the operations kept are left
+        assert_eq!(read_dir(&journal_dir).unwrap().count(), 3);
+    }
+
+    #[test]
+    fn journal_length() {
+        let dir = setup();
+        let journal_dir = dir.path().join("db.journal");
+        let mut journal = Journal::new(&journal_dir);
+
+        for i in 0..LENGTH + 5 {
+            journal
+                .record(&i.to_string(), dir.path().join("db"))
+                .unwrap();
+        }
+
+        assert_eq!(journal.done().count(), LENGTH);
+        assert_eq!(journal.done().last().unwrap().description, "5");
+        assert_eq!(read_dir(&journal_dir).unwrap().count(), LENGTH);
+    }
+}
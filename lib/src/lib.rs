@@ -9,3 +9,57 @@ pub mod tag;
 
 /// The storage for the entries
 pub mod shelf;
+
+/// Reading status definition
+pub mod status;
+
+/// Reading session definition
+pub mod session;
+
+/// Rating definition
+pub mod rating;
+
+/// Plain text contents of the entries
+pub mod document;
+
+/// Speed-reading practice
+pub mod speed;
+
+/// Reading progress definition
+pub mod progress;
+
+/// Bookmark definition
+pub mod bookmark;
+
+/// Query language for filtering the entries
+pub mod query;
+
+/// Sorting the entries
+pub mod sort;
+
+/// Organizing the entries into directories of links
+pub mod organize;
+
+/// Full-text search in the files of the entries
+pub mod search;
+
+/// Fuzzy matching of the entries
+pub mod fuzzy;
+
+/// Finding duplicate entries
+pub mod dedupe;
+
+/// Series of entries
+pub mod series;
+
+/// Importing the files in directory trees
+pub mod import;
+
+/// Editing the entries by hand
+pub mod edit;
+
+/// Selecting the entries by their indices, identifiers or queries
+pub mod selector;
+
+/// Journal of the operations on a shelf, for undoing and redoing them
+pub mod journal;
@@ -0,0 +1,348 @@
+use std::{
+    collections::BTreeMap,
+    fmt::Display,
+    fs::{
+        create_dir_all, read_dir, read_link, read_to_string, remove_dir,
+        remove_file, write,
+    },
+    io,
+    path::{Component, Path, PathBuf},
+    str::FromStr,
+};
+
+use super::entry::Entry;
+
+/// The name of the file in the root directory listing the links made, so
+/// that only those are ever pruned
+pub const MANIFEST: &str = ".bookshelf-links";
+
+/// How the links to the entries are laid out in directories.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Layout {
+    /// A directory per tag, nested as the tags are
+    Tag,
+    /// A directory per author, as "Last, First"
+    Author,
+    /// A directory per year of publication
+    Year,
+}
+
+impl Display for Layout {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Layout::Tag => write!(f, "tag"),
+            Layout::Author => write!(f, "author"),
+            Layout::Year => write!(f, "year"),
+        }
+    }
+}
+
+impl FromStr for Layout {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "tag" => Ok(Layout::Tag),
+            "author" => Ok(Layout::Author),
+            "year" => Ok(Layout::Year),
+            _ => Err(format!("Unknown layout '{}'", s)),
+        }
+    }
+}
+
+/// What organizing the entries has done.
+#[derive(Debug, Default, PartialEq)]
+pub struct Report {
+    /// The number of links created or pointed at a new file
+    pub linked: usize,
+    /// The number of links which were already in place
+    pub unchanged: usize,
+    /// The number of stale links removed
+    pub pruned: usize,
+    /// The number of entries without any directory in the layout
+    pub skipped: usize,
+    /// The paths which should be links, but are occupied by other files
+    pub conflicts: Vec<PathBuf>,
+}
+
+/// Returns where the links to the entries go in the layout provided: the
+/// paths of the links relative to the root directory, along with the files
+/// they point to. The links to different files with the same name in one
+/// directory are told apart by a number, e.g. `book (2).pdf`. Also returns
+/// the number of entries without any directory in the layout.
+pub fn plan<'a, I>(
+    entries: I,
+    layout: Layout,
+) -> (BTreeMap<PathBuf, PathBuf>, usize)
+where
+    I: IntoIterator<Item = &'a Entry>,
+{
+    let mut links = BTreeMap::new();
+    let mut skipped = 0;
+
+    for entry in entries {
+        let dirs = directories(entry, layout);
+
+        if dirs.is_empty() {
+            skipped += 1;
+        }
+
+        for dir in dirs {
+            let link = (1..)
+                .map(|n| dir.join(link_name(&entry.path, n)))
+                .find(|link| !links.contains_key(link))
+                .unwrap();
+
+            links.insert(link, entry.path.clone());
+        }
+    }
+
+    (links, skipped)
+}
+
+/// Builds a directory tree of symbolic links to the files of the entries
+/// under the root directory, in the layout provided. Running it again only
+/// changes what has changed: the links in place are kept, and the links
+/// made before which are no longer needed are removed along with the
+/// directories left empty. The links made are listed in the [`MANIFEST`]
+/// file, and nothing else in the directory is ever touched: the paths taken
+/// by other files or links are reported as conflicts and left out of it.
+///
+/// # Errors
+///
+/// This function will return an error if creating or removing the links
+/// or the directories fails.
+pub fn organize<'a, I>(
+    entries: I,
+    root: &Path,
+    layout: Layout,
+) -> io::Result<Report>
+where
+    I: IntoIterator<Item = &'a Entry>,
+{
+    let (links, skipped) = plan(entries, layout);
+    let mut report = Report {
+        skipped,
+        ..Default::default()
+    };
+
+    let manifest = root.join(MANIFEST);
+    let previous = read_to_string(&manifest).unwrap_or_default();
+    let previous = previous.lines().map(PathBuf::from).collect::<Vec<_>>();
+    let mut made = Vec::new();
+
+    for (relative, target) in links.iter() {
+        let link = root.join(relative);
+
+        match read_link(&link) {
+            Ok(existing) if &existing == target => {
+                report.unchanged += 1;
+                made.push(relative);
+                continue;
+            },
+            // Only the links made before are pointed at a new file
+            Ok(_) if previous.contains(relative) => remove_file(&link)?,
+            Err(_) if link.symlink_metadata().is_err() => {},
+            _ => {
+                report.conflicts.push(link);
+                continue;
+            },
+        }
+
+        create_dir_all(link.parent().unwrap())?;
+        symlink(target, &link)?;
+        report.linked += 1;
+        made.push(relative);
+    }
+
+    for link in previous.iter() {
+        if !made.contains(&link) {
+            report.pruned += prune(root, link)?;
+        }
+    }
+
+    if !made.is_empty() || manifest.exists() {
+        create_dir_all(root)?;
+
+        let listed = made
+            .iter()
+            .map(|link| format!("{}\n", link.display()))
+            .collect::<String>();
+        write(&manifest, listed)?;
+    }
+
+    Ok(report)
+}
+
+/// Returns the directories, relative to the root one, where the links to
+/// the entry go in the layout provided.
+fn directories(entry: &Entry, layout: Layout) -> Vec<PathBuf> {
+    match layout {
+        Layout::Tag => entry
+            .tags
+            .iter()
+            .flatten()
+            .map(|tag| {
+                tag.keyword().split('/').map(sanitize).collect::<PathBuf>()
+            })
+            .collect(),
+        Layout::Author => entry
+            .authors()
+            .iter()
+            .map(|author| match author.rsplit_once(' ') {
+                Some((first, last)) => format!("{}, {}", last, first),
+                None => author.clone(),
+            })
+            .map(|author| PathBuf::from(sanitize(&author)))
+            .collect(),
+        Layout::Year => entry
+            .year()
+            .map(|year| PathBuf::from(year.to_string()))
+            .into_iter()
+            .collect(),
+    }
+}
+
+/// Returns the name of a link to the file, numbered from the second one.
+fn link_name(path: &Path, n: usize) -> String {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+
+    if n == 1 {
+        return name.into_owned();
+    }
+
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+
+    match path.extension() {
+        Some(ext) => format!("{} ({}).{}", stem, n, ext.to_string_lossy()),
+        None => format!("{} ({})", stem, n),
+    }
+}
+
+/// Makes the name safe to use as a single path component.
+fn sanitize(name: &str) -> String {
+    let name = name.replace(['/', '\\', '\0'], "_");
+
+    match Path::new(&name).components().next() {
+        Some(Component::Normal(_)) => name,
+        _ => "_".to_string(),
+    }
+}
+
+/// Removes the link, relative to the root directory, if it still is
+/// a symbolic link, and then its parent directories left empty. Returns the
+/// number of links removed.
+fn prune(root: &Path, link: &Path) -> io::Result<usize> {
+    let path = root.join(link);
+
+    match path.symlink_metadata() {
+        Ok(metadata) if metadata.file_type().is_symlink() => {
+            remove_file(&path)?
+        },
+        _ => return Ok(0),
+    }
+
+    for dir in link.ancestors().skip(1) {
+        let dir = root.join(dir);
+
+        if dir == root || read_dir(&dir)?.next().is_some() {
+            break;
+        }
+
+        remove_dir(&dir)?;
+    }
+
+    Ok(1)
+}
+
+#[cfg(target_family = "unix")]
+fn symlink(target: &Path, link: &Path) -> io::Result<()> {
+    std::os::unix::fs::symlink(target, link)
+}
+
+#[cfg(target_family = "windows")]
+fn symlink(target: &Path, link: &Path) -> io::Result<()> {
+    std::os::windows::fs::symlink_file(target, link)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::{read_link, read_to_string, write};
+
+    use utils::test::setup;
+
+    use crate::{entry::*, organize::*, tag::*};
+
+    #[test]
+    fn numbered_links() {
+        let dir = setup();
+
+        let book = Entry::new(dir.path().join("book.txt").to_str().unwrap())
+            .with_tags(&[Tag::new("../fiction")])
+            .unwrap();
+        let mut other = book.clone();
+        other.path = dir.path().join("other").join("book.txt");
+
+        let (links, skipped) = plan([&book, &other], Layout::Tag);
+
+        assert_eq!(skipped, 0);
+        assert_eq!(
+            links.keys().collect::<Vec<&PathBuf>>(),
+            [
+                &PathBuf::from("_/fiction/book (2).txt"),
+                &PathBuf::from("_/fiction/book.txt")
+            ]
+        );
+    }
+
+    #[test]
+    fn idempotent_and_pruned() {
+        let dir = setup();
+        let root = dir.path().join("by-tag");
+
+        let mut book =
+            Entry::new(dir.path().join("book.txt").to_str().unwrap())
+                .with_tags(&[Tag::new("cs/algo"), Tag::new("fiction")])
+                .unwrap();
+        let article =
+            Entry::new(dir.path().join("article.txt").to_str().unwrap());
+
+        let report = organize([&book, &article], &root, Layout::Tag).unwrap();
+        assert_eq!((report.linked, report.skipped), (2, 1));
+        assert_eq!(
+            read_link(root.join("cs/algo/book.txt")).unwrap(),
+            book.path
+        );
+
+        let report = organize([&book, &article], &root, Layout::Tag).unwrap();
+        assert_eq!((report.linked, report.unchanged), (0, 2));
+
+        // The files and links of the user's own are never touched
+        write(root.join("fiction/notes.txt"), "notes").unwrap();
+        symlink(&book.path, &root.join("cs/algo/mine.txt")).unwrap();
+        book.tags = Some(vec![Tag::new("fiction")]);
+
+        let report = organize([&book], &root, Layout::Tag).unwrap();
+        assert_eq!((report.unchanged, report.pruned), (1, 1));
+        assert!(!root.join("cs/algo/book.txt").exists());
+        assert!(root.join("cs/algo/mine.txt").exists());
+        assert!(root.join("fiction/notes.txt").exists());
+
+        let report = organize([], &root, Layout::Tag).unwrap();
+        assert_eq!(report.pruned, 1);
+        assert!(!root.join("fiction/book.txt").exists());
+
+        // Neither are the links of the user's own in place of the planned ones
+        let mine = root.join("fiction/book.txt");
+        symlink(&article.path, &mine).unwrap();
+
+        let report = organize([&book], &root, Layout::Tag).unwrap();
+        assert_eq!((report.linked, report.conflicts), (0, vec![mine.clone()]));
+        assert_eq!(read_link(&mine).unwrap(), article.path);
+        assert!(read_to_string(root.join(MANIFEST)).unwrap().is_empty());
+
+        let report = organize([], &root, Layout::Tag).unwrap();
+        assert_eq!(report.pruned, 0);
+        assert!(mine.symlink_metadata().is_ok());
+    }
+}
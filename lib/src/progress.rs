@@ -0,0 +1,71 @@
+use serde::{Deserialize, Serialize};
+
+/// Reading progress through an entry, measured in words.
+#[derive(
+    Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize,
+)]
+pub struct Progress {
+    /// The number of words read
+    pub position: usize,
+    /// The total number of words in the entry
+    pub total: usize,
+}
+
+impl Progress {
+    /// Creates a new [`Progress`], clamping the position to the total.
+    pub fn new(position: usize, total: usize) -> Self {
+        Self {
+            position: position.min(total),
+            total,
+        }
+    }
+
+    /// Returns the share of the entry read, from 0 to 1.
+    pub fn fraction(&self) -> f64 {
+        match self.total {
+            0 => 0.0,
+            total => self.position as f64 / total as f64,
+        }
+    }
+
+    /// Returns the number of words left to read.
+    pub fn remaining(&self) -> usize {
+        self.total.saturating_sub(self.position)
+    }
+
+    /// Returns whether the entry has been read to the end. An entry without
+    /// any words is never complete, as there is nothing to read.
+    pub fn is_complete(&self) -> bool {
+        self.total > 0 && self.position >= self.total
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::progress::*;
+
+    #[test]
+    fn clamped_position() {
+        let progress = Progress::new(150, 100);
+
+        assert_eq!(progress.position, 100);
+        assert_eq!(progress.remaining(), 0);
+        assert!(progress.is_complete());
+
+        // The fields can be set past the total as well
+        let progress = Progress {
+            position: 150,
+            total: 100,
+        };
+        assert_eq!(progress.remaining(), 0);
+        assert!(progress.is_complete());
+
+        assert!(!Progress::new(0, 0).is_complete());
+    }
+
+    #[test]
+    fn fraction_read() {
+        assert_eq!(Progress::new(25, 100).fraction(), 0.25);
+        assert_eq!(Progress::new(0, 0).fraction(), 0.0);
+    }
+}
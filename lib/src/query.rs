@@ -0,0 +1,853 @@
+use std::{fmt::Display, str::FromStr};
+
+use super::{
+    entry::{Entry, Metadata},
+    status::Status,
+    tag::Tag,
+};
+
+/// An error in a query, pointing at the offending part of it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ParseError {
+    /// The position of the offending part, in characters
+    pub position: usize,
+    /// The length of the offending part, in characters
+    pub length: usize,
+    /// What is wrong with the query
+    pub message: String,
+}
+
+impl ParseError {
+    fn new(token: &Token, message: String) -> Self {
+        Self {
+            position: token.position,
+            length: token.length.max(1),
+            message,
+        }
+    }
+
+    /// Returns the query provided with the offending part underlined on the
+    /// next line.
+    pub fn underline(&self, query: &str) -> String {
+        format!(
+            "{}\n{}{}",
+            query,
+            " ".repeat(self.position),
+            "^".repeat(self.length)
+        )
+    }
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} at position {}", self.message, self.position + 1)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// A field of an entry that a condition tests.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Field {
+    /// The tags of the entry
+    Tag,
+    /// The title of the entry
+    Title,
+    /// The authors of the entry
+    Author,
+    /// The path to the entry
+    Path,
+    /// The review of the entry
+    Review,
+    /// The reading status of the entry
+    Status,
+    /// The year of publication of the entry
+    Year,
+    /// The rating of the entry
+    Rating,
+    /// The reading progress of the entry, in percent
+    Progress,
+    /// The name of the series of the entry
+    Series,
+    /// The title, the authors, the file name and the tags of the entry
+    Any,
+}
+
+impl Field {
+    fn is_numeric(&self) -> bool {
+        matches!(self, Field::Year | Field::Rating | Field::Progress)
+    }
+}
+
+impl FromStr for Field {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "tag" => Ok(Field::Tag),
+            "title" => Ok(Field::Title),
+            "author" => Ok(Field::Author),
+            "path" => Ok(Field::Path),
+            "review" => Ok(Field::Review),
+            "status" => Ok(Field::Status),
+            "year" => Ok(Field::Year),
+            "rating" => Ok(Field::Rating),
+            "progress" => Ok(Field::Progress),
+            "series" => Ok(Field::Series),
+            _ => Err(format!("Unknown field '{}'", s)),
+        }
+    }
+}
+
+/// The comparison a condition makes.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Op {
+    /// The field matches the value (`:` or `=`)
+    Is,
+    /// The field contains the value (`:~`)
+    Contains,
+    /// The field is less than the value (`<`)
+    Less,
+    /// The field is less than or equal to the value (`<=`)
+    LessOrEqual,
+    /// The field is greater than the value (`>`)
+    Greater,
+    /// The field is greater than or equal to the value (`>=`)
+    GreaterOrEqual,
+}
+
+/// The value a condition compares a field with.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    /// Text, compared case-insensitively
+    Text(String),
+    /// A number
+    Number(f32),
+    /// A reading status
+    Status(Status),
+    /// A tag
+    Tag(Tag),
+}
+
+/// A parsed query, which can be tested against entries.
+///
+/// A query is made of conditions combined with `and`, `or`, `not` and
+/// parentheses, e.g.
+/// `tag:fiction and (author:~tolkien or year>=1950) and status:reading`.
+/// `and` binds tighter than `or`, and two conditions next to each other are
+/// joined with an implicit `and`. The keywords are case-insensitive.
+///
+/// A condition is a field, an operator and a value:
+///
+/// | Field      | Operators             | Value                           |
+/// |------------|-----------------------|---------------------------------|
+/// | `tag`      | `:` `:~`              | a tag; `:` matches subtags      |
+/// | `title`    | `:` `:~`              | text                            |
+/// | `author`   | `:` `:~`              | text, matches any author        |
+/// | `path`     | `:` `:~`              | text                            |
+/// | `review`   | `:` `:~`              | text                            |
+/// | `series`   | `:` `:~`              | text, the name of the series    |
+/// | `status`   | `:`                   | `want`, `reading` or `finished` |
+/// | `year`     | `:` `<` `<=` `>` `>=` | a number                        |
+/// | `rating`   | `:` `<` `<=` `>` `>=` | a number of stars               |
+/// | `progress` | `:` `<` `<=` `>` `>=` | a percentage                    |
+///
+/// `:` (or `=`) is a case-insensitive match of the whole value, and `:~` is
+/// a case-insensitive match of a part of it. Values with spaces or special
+/// characters can be quoted, e.g. `title:~"the hobbit"`, while apostrophes
+/// within words need no quotes, e.g. `author:~o'brien`. A value on its
+/// own matches any part of the title, the authors, the file name or the
+/// tags of an entry. A saved query, or collection, is referred to as
+/// `@name` (see [`Query::parse_with`]).
+#[derive(Clone, Debug, PartialEq)]
+pub enum Query {
+    /// Matches the entries whose field compares with the value
+    Condition {
+        /// The field tested
+        field: Field,
+        /// The comparison made
+        op: Op,
+        /// The value compared with
+        value: Value,
+    },
+    /// Matches the entries not matched by the query
+    Not(Box<Query>),
+    /// Matches the entries matched by both of the queries
+    And(Box<Query>, Box<Query>),
+    /// Matches the entries matched by either of the queries
+    Or(Box<Query>, Box<Query>),
+}
+
+impl Query {
+    /// Parses a query.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error pointing at the offending part of
+    /// the query if the query is malformed, or if a field is unknown or
+    /// compared with a value it can't be compared with.
+    pub fn parse(query: &str) -> Result<Self, ParseError> {
+        Self::parse_with(query, |_| None)
+    }
+
+    /// Parses a query which can refer to saved queries, or collections, as
+    /// `@name`. The collections are looked up by their names, and are parsed
+    /// in place of the references.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error pointing at the offending part of
+    /// the query if the query is malformed, if a field is unknown or
+    /// compared with a value it can't be compared with, or if a collection
+    /// is unknown, malformed or refers to itself.
+    pub fn parse_with<F>(
+        query: &str,
+        collections: F,
+    ) -> Result<Self, ParseError>
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        parse_tokens(query, &collections, &mut vec![])
+    }
+
+    /// Returns whether the entry provided matches this query.
+    pub fn matches(&self, entry: &Entry) -> bool {
+        self.matches_with(entry, &mut None)
+    }
+
+    /// Returns whether the entry provided matches this query, reading the
+    /// metadata of the entry once for all of the conditions testing it.
+    fn matches_with(
+        &self,
+        entry: &Entry,
+        metadata: &mut Option<Metadata>,
+    ) -> bool {
+        match self {
+            Query::Condition { field, op, value } => {
+                test(entry, metadata, *field, *op, value)
+            },
+            Query::Not(query) => !query.matches_with(entry, metadata),
+            Query::And(lhs, rhs) => {
+                lhs.matches_with(entry, metadata)
+                    && rhs.matches_with(entry, metadata)
+            },
+            Query::Or(lhs, rhs) => {
+                lhs.matches_with(entry, metadata)
+                    || rhs.matches_with(entry, metadata)
+            },
+        }
+    }
+}
+
+impl FromStr for Query {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Query::parse(s)
+    }
+}
+
+/// Parses a query, keeping track of the collections being parsed to catch
+/// the ones referring to themselves.
+fn parse_tokens(
+    query: &str,
+    collections: &dyn Fn(&str) -> Option<String>,
+    expanding: &mut Vec<String>,
+) -> Result<Query, ParseError> {
+    let tokens = tokenize(query)?;
+    let mut parser = Parser {
+        tokens: &tokens,
+        next: 0,
+        end: query.chars().count(),
+        collections,
+        expanding,
+    };
+
+    let parsed = parser.parse_or()?;
+
+    match parser.peek() {
+        Some(token) if token.kind == Kind::RightParen => Err(ParseError::new(
+            token,
+            "Unmatched closing parenthesis".into(),
+        )),
+        Some(token) => Err(ParseError::new(
+            token,
+            format!("Expected 'and' or 'or', found '{}'", token.text),
+        )),
+        None => Ok(parsed),
+    }
+}
+
+/// Tests a field of the entry against the value. The metadata of the entry
+/// is read the first time it is needed.
+fn test(
+    entry: &Entry,
+    metadata: &mut Option<Metadata>,
+    field: Field,
+    op: Op,
+    value: &Value,
+) -> bool {
+    match (field, value) {
+        (Field::Tag, Value::Tag(tag)) if op == Op::Is => entry.has_tag(tag),
+        (Field::Tag, Value::Tag(tag)) => entry
+            .tags
+            .iter()
+            .flatten()
+            .any(|own| compare_text(own.keyword(), op, tag.keyword())),
+        (Field::Status, Value::Status(status)) => entry.status == *status,
+        (Field::Year, Value::Number(number)) => matches!(
+            metadata.get_or_insert_with(|| entry.metadata()).year,
+            Some(year) if compare_numbers(year as f32, op, *number)
+        ),
+        (Field::Rating, Value::Number(number)) => matches!(
+            entry.rating,
+            Some(rating) if compare_numbers(rating.stars(), op, *number)
+        ),
+        (Field::Progress, Value::Number(number)) => {
+            // Entries which haven't been read inside bookshelf are at 0%
+            let percent = entry
+                .progress
+                .map_or(0.0, |progress| progress.fraction() * 100.0);
+
+            compare_numbers(percent as f32, op, *number)
+        },
+        (Field::Any, Value::Text(text)) => {
+            let file_name =
+                entry.path.file_name().unwrap_or_default().to_string_lossy();
+
+            let metadata = metadata.get_or_insert_with(|| entry.metadata());
+
+            compare_text(&metadata.title, op, text)
+                || compare_text(&file_name, op, text)
+                || metadata
+                    .authors
+                    .iter()
+                    .any(|author| compare_text(author, op, text))
+                || entry
+                    .tags
+                    .iter()
+                    .flatten()
+                    .any(|tag| compare_text(tag.keyword(), op, text))
+        },
+        (Field::Author, Value::Text(text)) => metadata
+            .get_or_insert_with(|| entry.metadata())
+            .authors
+            .iter()
+            .any(|author| compare_text(author, op, text)),
+        (Field::Title, Value::Text(text)) => compare_text(
+            &metadata.get_or_insert_with(|| entry.metadata()).title,
+            op,
+            text,
+        ),
+        (Field::Path, Value::Text(text)) => {
+            compare_text(&entry.path.to_string_lossy(), op, text)
+        },
+        (Field::Series, Value::Text(text)) => matches!(
+            &metadata.get_or_insert_with(|| entry.metadata()).series,
+            Some(series) if compare_text(&series.name, op, text)
+        ),
+        (Field::Review, Value::Text(text)) => matches!(
+            &entry.review,
+            Some(review) if compare_text(review, op, text)
+        ),
+        _ => false,
+    }
+}
+
+fn compare_text(text: &str, op: Op, value: &str) -> bool {
+    let text = text.to_lowercase();
+    let value = value.to_lowercase();
+
+    match op {
+        Op::Contains => text.contains(&value),
+        _ => text == value,
+    }
+}
+
+fn compare_numbers(number: f32, op: Op, value: f32) -> bool {
+    match op {
+        Op::Less => number < value,
+        Op::LessOrEqual => number <= value,
+        Op::Greater => number > value,
+        Op::GreaterOrEqual => number >= value,
+        _ => number == value,
+    }
+}
+
+/// The kind of a token of a query.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Kind {
+    LeftParen,
+    RightParen,
+    Op(Op),
+    And,
+    Or,
+    Not,
+    /// An unquoted word
+    Word,
+    /// A quoted string, without the quotes
+    Quoted,
+}
+
+/// A token of a query, along with its place in the query.
+#[derive(Clone, Debug)]
+struct Token {
+    kind: Kind,
+    text: String,
+    position: usize,
+    length: usize,
+}
+
+/// Splits a query into tokens.
+fn tokenize(query: &str) -> Result<Vec<Token>, ParseError> {
+    let chars = query.chars().collect::<Vec<char>>();
+    let mut tokens = vec![];
+    let mut i = 0;
+
+    // An apostrophe only opens a quoted value at the start of one, so that
+    // words such as "o'brien" can be written as they are
+    let is_special = |c: char| c.is_whitespace() || "()\":<>=".contains(c);
+
+    while i < chars.len() {
+        let start = i;
+        let c = chars[i];
+        let next = chars.get(i + 1).copied();
+
+        let kind = match c {
+            _ if c.is_whitespace() => {
+                i += 1;
+                continue;
+            },
+            '(' => Kind::LeftParen,
+            ')' => Kind::RightParen,
+            ':' if next == Some('~') => Kind::Op(Op::Contains),
+            ':' | '=' => Kind::Op(Op::Is),
+            '<' if next == Some('=') => Kind::Op(Op::LessOrEqual),
+            '<' => Kind::Op(Op::Less),
+            '>' if next == Some('=') => Kind::Op(Op::GreaterOrEqual),
+            '>' => Kind::Op(Op::Greater),
+            '"' | '\'' => {
+                let end = chars[i + 1..]
+                    .iter()
+                    .position(|&other| other == c)
+                    .map(|offset| i + 1 + offset)
+                    .ok_or_else(|| ParseError {
+                        position: start,
+                        length: chars.len() - start,
+                        message: "Unterminated quoted value".into(),
+                    })?;
+
+                tokens.push(Token {
+                    kind: Kind::Quoted,
+                    text: chars[start + 1..end].iter().collect(),
+                    position: start,
+                    length: end + 1 - start,
+                });
+
+                i = end + 1;
+                continue;
+            },
+            _ => {
+                while i < chars.len() && !is_special(chars[i]) {
+                    i += 1;
+                }
+
+                let text = chars[start..i].iter().collect::<String>();
+                let kind = match text.to_lowercase().as_str() {
+                    "and" => Kind::And,
+                    "or" => Kind::Or,
+                    "not" => Kind::Not,
+                    _ => Kind::Word,
+                };
+
+                tokens.push(Token {
+                    kind,
+                    text,
+                    position: start,
+                    length: i - start,
+                });
+
+                continue;
+            },
+        };
+
+        i += match kind {
+            Kind::Op(Op::Contains)
+            | Kind::Op(Op::LessOrEqual)
+            | Kind::Op(Op::GreaterOrEqual) => 2,
+            _ => 1,
+        };
+
+        tokens.push(Token {
+            kind,
+            text: chars[start..i].iter().collect(),
+            position: start,
+            length: i - start,
+        });
+    }
+
+    Ok(tokens)
+}
+
+/// A recursive descent parser over the tokens of a query:
+///
+/// ```text
+/// or        = and { "or" and }
+/// and       = not { [ "and" ] not }
+/// not       = "not" not | primary
+/// primary   = "(" or ")" | "@" collection | condition
+/// condition = word operator value | value
+/// value     = word | quoted
+/// ```
+struct Parser<'a> {
+    tokens: &'a [Token],
+    next: usize,
+    /// The length of the query, where the errors at its end point
+    end: usize,
+    /// Looks up the collections by their names
+    collections: &'a dyn Fn(&str) -> Option<String>,
+    /// The names of the collections being parsed
+    expanding: &'a mut Vec<String>,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<&'a Token> {
+        self.tokens.get(self.next)
+    }
+
+    fn advance(&mut self) -> Option<&'a Token> {
+        let token = self.tokens.get(self.next);
+        self.next += 1;
+        token
+    }
+
+    /// Returns the next token, or an error at the end of the query if there
+    /// are no more tokens.
+    fn expect(&mut self, expected: &str) -> Result<&'a Token, ParseError> {
+        self.advance().ok_or_else(|| ParseError {
+            position: self.end,
+            length: 1,
+            message: format!(
+                "Expected {}, found the end of the query",
+                expected
+            ),
+        })
+    }
+
+    fn parse_or(&mut self) -> Result<Query, ParseError> {
+        let mut query = self.parse_and()?;
+
+        while let Some(Kind::Or) = self.peek().map(|token| token.kind) {
+            self.advance();
+            query = Query::Or(Box::new(query), Box::new(self.parse_and()?));
+        }
+
+        Ok(query)
+    }
+
+    fn parse_and(&mut self) -> Result<Query, ParseError> {
+        let mut query = self.parse_not()?;
+
+        loop {
+            match self.peek().map(|token| token.kind) {
+                Some(Kind::And) => {
+                    self.advance();
+                },
+                // Conditions next to each other are joined with an "and"
+                Some(
+                    Kind::Not | Kind::LeftParen | Kind::Word | Kind::Quoted,
+                ) => {},
+                _ => return Ok(query),
+            }
+
+            query = Query::And(Box::new(query), Box::new(self.parse_not()?));
+        }
+    }
+
+    fn parse_not(&mut self) -> Result<Query, ParseError> {
+        match self.peek().map(|token| token.kind) {
+            Some(Kind::Not) => {
+                self.advance();
+                Ok(Query::Not(Box::new(self.parse_not()?)))
+            },
+            _ => self.parse_primary(),
+        }
+    }
+
+    fn parse_primary(&mut self) -> Result<Query, ParseError> {
+        let token = self.expect("a condition")?;
+
+        match token.kind {
+            Kind::LeftParen => {
+                let query = self.parse_or()?;
+
+                match self.advance() {
+                    Some(closing) if closing.kind == Kind::RightParen => {
+                        Ok(query)
+                    },
+                    Some(other) => Err(ParseError::new(
+                        other,
+                        format!("Expected ')', found '{}'", other.text),
+                    )),
+                    None => Err(ParseError::new(
+                        token,
+                        "Unmatched opening parenthesis".into(),
+                    )),
+                }
+            },
+            Kind::Word
+                if matches!(
+                    self.peek(),
+                    Some(Token {
+                        kind: Kind::Op(_),
+                        ..
+                    })
+                ) =>
+            {
+                self.parse_condition(token)
+            },
+            Kind::Word if token.text.starts_with('@') => {
+                self.parse_collection(token)
+            },
+            Kind::Word | Kind::Quoted => Ok(Query::Condition {
+                field: Field::Any,
+                op: Op::Contains,
+                value: Value::Text(token.text.clone()),
+            }),
+            _ => Err(ParseError::new(
+                token,
+                format!("Expected a condition, found '{}'", token.text),
+            )),
+        }
+    }
+
+    /// Parses the query of the collection referred to in place of the
+    /// reference.
+    fn parse_collection(&mut self, token: &Token) -> Result<Query, ParseError> {
+        let name = &token.text[1..];
+
+        if self.expanding.iter().any(|expanding| expanding == name) {
+            return Err(ParseError::new(
+                token,
+                format!("Collection '{}' refers to itself", token.text),
+            ));
+        }
+
+        let query = (self.collections)(name).ok_or_else(|| {
+            ParseError::new(
+                token,
+                format!("Unknown collection '{}'", token.text),
+            )
+        })?;
+
+        self.expanding.push(name.to_string());
+        let parsed = parse_tokens(&query, self.collections, self.expanding);
+        self.expanding.pop();
+
+        parsed.map_err(|e| {
+            ParseError::new(
+                token,
+                format!(
+                    "Collection '{}' is invalid: {}",
+                    token.text, e.message
+                ),
+            )
+        })
+    }
+
+    /// Parses a condition on a field, checking that the operator and the
+    /// value make sense for the field.
+    fn parse_condition(&mut self, name: &Token) -> Result<Query, ParseError> {
+        let field = name
+            .text
+            .parse::<Field>()
+            .map_err(|message| ParseError::new(name, message))?;
+
+        let op_token = self.expect("an operator")?;
+        let op = match op_token.kind {
+            Kind::Op(op) => op,
+            _ => unreachable!("The operator has been peeked at"),
+        };
+
+        let supported = match op {
+            Op::Is => true,
+            Op::Contains => !field.is_numeric() && field != Field::Status,
+            _ => field.is_numeric(),
+        };
+
+        if !supported {
+            return Err(ParseError::new(
+                op_token,
+                format!(
+                    "Operator '{}' can't be used with the field '{}'",
+                    op_token.text, name.text
+                ),
+            ));
+        }
+
+        let value_token = self.expect("a value")?;
+        if !matches!(value_token.kind, Kind::Word | Kind::Quoted) {
+            return Err(ParseError::new(
+                value_token,
+                format!("Expected a value, found '{}'", value_token.text),
+            ));
+        }
+
+        let text = value_token.text.clone();
+        let value = match field {
+            Field::Tag => Value::Tag(Tag::new(&text)),
+            Field::Status => Value::Status(
+                text.parse()
+                    .map_err(|message| ParseError::new(value_token, message))?,
+            ),
+            _ if field.is_numeric() => {
+                Value::Number(text.parse().map_err(|_| {
+                    ParseError::new(
+                        value_token,
+                        format!("Expected a number, found '{}'", text),
+                    )
+                })?)
+            },
+            _ => Value::Text(text),
+        };
+
+        Ok(Query::Condition { field, op, value })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use utils::test::setup;
+
+    use crate::{entry::*, query::*, rating::Rating, status::Status, tag::*};
+
+    #[test]
+    fn precedence() {
+        let query = Query::parse("a or b c and not d").unwrap();
+
+        let any = |text: &str| Query::Condition {
+            field: Field::Any,
+            op: Op::Contains,
+            value: Value::Text(text.to_string()),
+        };
+
+        assert_eq!(
+            query,
+            Query::Or(
+                Box::new(any("a")),
+                Box::new(Query::And(
+                    Box::new(Query::And(
+                        Box::new(any("b")),
+                        Box::new(any("c"))
+                    )),
+                    Box::new(Query::Not(Box::new(any("d"))))
+                ))
+            )
+        );
+    }
+
+    #[test]
+    fn parse_errors() {
+        let error = Query::parse("tag:fiction and colour:red").unwrap_err();
+        assert_eq!((error.position, error.length), (16, 6));
+        assert_eq!(
+            error.underline("tag:fiction and colour:red"),
+            "tag:fiction and colour:red\n                ^^^^^^"
+        );
+
+        let error = Query::parse("year>=nineteen").unwrap_err();
+        assert_eq!((error.position, error.length), (6, 8));
+
+        let error = Query::parse("title>3").unwrap_err();
+        assert_eq!((error.position, error.length), (5, 1));
+
+        let error = Query::parse("(status:reading").unwrap_err();
+        assert_eq!(error.position, 0);
+
+        let error = Query::parse("status:reading)").unwrap_err();
+        assert_eq!(error.position, 14);
+
+        let error = Query::parse("tag:fiction and").unwrap_err();
+        assert_eq!(error.position, 15);
+
+        let error = Query::parse("title:\"the hobbit").unwrap_err();
+        assert_eq!(error.position, 6);
+
+        let error = Query::parse("title:'the hobbit").unwrap_err();
+        assert_eq!(error.position, 6);
+    }
+
+    #[test]
+    fn apostrophes_in_values() {
+        let text = |field, op, text: &str| {
+            Ok(Query::Condition {
+                field,
+                op,
+                value: Value::Text(text.to_string()),
+            })
+        };
+
+        assert_eq!(
+            Query::parse("author:~o'brien"),
+            text(Field::Author, Op::Contains, "o'brien")
+        );
+        assert_eq!(
+            Query::parse("o'reilly"),
+            text(Field::Any, Op::Contains, "o'reilly")
+        );
+        assert_eq!(
+            Query::parse("title:'the hobbit'"),
+            text(Field::Title, Op::Is, "the hobbit")
+        );
+    }
+
+    #[test]
+    fn collections() {
+        let collections = |name: &str| match name {
+            "fantasy" => Some("tag:fiction/fantasy".to_string()),
+            "unread" => Some("status:want and not @read".to_string()),
+            "read" => Some("status:finished or @unread".to_string()),
+            _ => None,
+        };
+
+        assert_eq!(
+            Query::parse_with("@fantasy", collections),
+            Query::parse("tag:fiction/fantasy")
+        );
+
+        let error = Query::parse_with("tag:x or @unread", collections);
+        assert_eq!(
+            error.unwrap_err().message,
+            "Collection '@unread' is invalid: Collection '@read' is invalid: \
+            Collection '@unread' refers to itself"
+        );
+
+        let error = Query::parse("tag:x @fantasy").unwrap_err();
+        assert_eq!((error.position, error.length), (6, 8));
+    }
+
+    #[test]
+    fn matching_entries() {
+        let dir = setup();
+
+        let mut book =
+            Entry::new(dir.path().join("book.txt").to_str().unwrap())
+                .with_bib(dir.path().join("book.bib").to_str().unwrap())
+                .with_tags(&[Tag::new("fiction/fantasy")])
+                .unwrap();
+        book.status = Status::Reading;
+        book.rating = Some(Rating::try_from(4.5).unwrap());
+
+        let matches = |query: &str| Query::parse(query).unwrap().matches(&book);
+
+        assert!(matches(
+            "tag:fiction and (author:~writer or year>=2050) and status:reading"
+        ));
+        assert!(matches("title:\"a good book\" rating>4"));
+        assert!(matches("good not tag:fiction/scifi"));
+        assert!(matches("tag:~fan and progress<50"));
+        assert!(!matches("tag:fan"));
+        assert!(!matches("author:writer or year<2000"));
+        assert!(!matches("NOT status:Reading"));
+    }
+}
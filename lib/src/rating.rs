@@ -0,0 +1,124 @@
+use serde::{Deserialize, Serialize};
+use std::{convert::TryFrom, fmt::Display, str::FromStr};
+
+/// A rating of an entry from 1 to 5 stars, in half-star steps.
+///
+/// Stored internally as the number of half-stars.
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    Deserialize,
+    Eq,
+    Hash,
+    Ord,
+    PartialEq,
+    PartialOrd,
+    Serialize,
+)]
+#[serde(into = "f32", try_from = "f32")]
+pub struct Rating {
+    /// The number of half-stars, from 2 to 10
+    half_stars: u8,
+}
+
+impl Rating {
+    /// The lowest rating possible, in stars
+    pub const MIN: f32 = 1.0;
+    /// The highest rating possible, in stars
+    pub const MAX: f32 = 5.0;
+
+    /// Returns the rating in stars.
+    pub fn stars(&self) -> f32 {
+        self.half_stars as f32 / 2.0
+    }
+
+    /// Returns the rating drawn with star characters, e.g. `★★★½`.
+    pub fn to_star_string(&self) -> String {
+        let mut stars = "★".repeat((self.half_stars / 2) as usize);
+
+        if self.half_stars % 2 == 1 {
+            stars.push('½');
+        }
+
+        stars
+    }
+}
+
+impl TryFrom<f32> for Rating {
+    type Error = String;
+
+    fn try_from(stars: f32) -> Result<Self, Self::Error> {
+        let half_stars = stars * 2.0;
+
+        if !(Self::MIN..=Self::MAX).contains(&stars)
+            || half_stars.fract() != 0.0
+        {
+            return Err(format!(
+                "A rating must be from {} to {} in steps of 0.5, got {}",
+                Self::MIN,
+                Self::MAX,
+                stars
+            ));
+        }
+
+        Ok(Self {
+            half_stars: half_stars as u8,
+        })
+    }
+}
+
+impl From<Rating> for f32 {
+    fn from(rating: Rating) -> Self {
+        rating.stars()
+    }
+}
+
+impl Display for Rating {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.stars())
+    }
+}
+
+impl FromStr for Rating {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let stars = s
+            .parse::<f32>()
+            .map_err(|_| format!("'{}' is not a valid rating", s))?;
+
+        Rating::try_from(stars)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::rating::*;
+
+    #[test]
+    fn parse_rating() {
+        let rating = "4.5".parse::<Rating>().unwrap();
+
+        assert_eq!(rating.stars(), 4.5);
+        assert_eq!(rating.to_string(), "4.5");
+        assert_eq!(rating.to_star_string(), "★★★★½");
+        assert_eq!("3".parse::<Rating>().unwrap().to_string(), "3");
+    }
+
+    #[test]
+    fn invalid_rating() {
+        assert!("0".parse::<Rating>().is_err());
+        assert!("5.5".parse::<Rating>().is_err());
+        assert!("4.2".parse::<Rating>().is_err());
+        assert!("good".parse::<Rating>().is_err());
+    }
+
+    #[test]
+    fn ratings_order() {
+        let good = "4".parse::<Rating>().unwrap();
+        let better = "4.5".parse::<Rating>().unwrap();
+
+        assert!(good < better);
+    }
+}
@@ -0,0 +1,506 @@
+use std::{
+    collections::{BTreeMap, BTreeSet, HashSet},
+    fmt::Display,
+    fs::{metadata, File},
+    io::{Read, Write},
+    path::{Path, PathBuf},
+    time::{Duration, UNIX_EPOCH},
+};
+
+use bincode::{deserialize, serialize};
+use serde::{Deserialize, Serialize};
+
+use super::document::{self, Document, Section};
+
+/// The number of words shown on either side of a match in a snippet
+const CONTEXT: usize = 8;
+
+/// Errors associated with [`Index`] operations.
+#[derive(Debug)]
+pub enum Error {
+    /// Writing the [`Index`] to the file specified failed.
+    Write,
+    /// Reading the [`Index`] from the file specified failed.
+    Read,
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Write => write!(f, "Writing to the search index failed"),
+            Error::Read => write!(f, "Reading the search index failed"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// A file in the [`Index`], along with its text.
+#[derive(Deserialize, Serialize)]
+struct Indexed {
+    path: PathBuf,
+    size: u64,
+    modified: Option<Duration>,
+    sections: Vec<Section>,
+}
+
+/// An inverted index of the words in the text of the files of the entries,
+/// used for full-text search.
+///
+/// Keeps the text of every file, so that it only has to be extracted again
+/// once the file changes. Can be saved to/read from a file (in binary
+/// format).
+#[derive(Default, Deserialize, Serialize)]
+pub struct Index {
+    /// The files indexed, by their ids
+    documents: BTreeMap<u32, Indexed>,
+    /// The id of the next file indexed
+    next_id: u32,
+    /// The sections of the files, as (file id, section), each word is in
+    words: BTreeMap<String, BTreeSet<(u32, u32)>>,
+}
+
+/// What updating the [`Index`] has done.
+#[derive(Debug, Default)]
+pub struct Update {
+    /// The number of files indexed for the first time or anew
+    pub indexed: usize,
+    /// The number of files removed from the index
+    pub removed: usize,
+    /// The files whose text couldn't be extracted, along with the reasons
+    pub failed: Vec<(PathBuf, document::Error)>,
+}
+
+/// Where in a file a match is.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Location {
+    /// A page of a PDF file, counted from 1
+    Page(usize),
+    /// A chapter of an EPUB book, counted from 1, along with its title
+    Chapter(usize, Option<String>),
+    /// A line of a plain text file, counted from 1
+    Line(usize),
+}
+
+impl Display for Location {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Location::Page(page) => write!(f, "page {}", page),
+            Location::Chapter(_, Some(title)) => write!(f, "'{}'", title),
+            Location::Chapter(chapter, None) => {
+                write!(f, "chapter {}", chapter)
+            },
+            Location::Line(line) => write!(f, "line {}", line),
+        }
+    }
+}
+
+/// The text around a match, split so that the match can be highlighted.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Snippet {
+    /// The text before the match
+    pub before: String,
+    /// The text matched
+    pub matched: String,
+    /// The text after the match
+    pub after: String,
+}
+
+impl Display for Snippet {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}{}{}", self.before, self.matched, self.after)
+    }
+}
+
+/// A match of a phrase in a file.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Hit {
+    /// The path to the file
+    pub path: PathBuf,
+    /// Where in the file the match is
+    pub location: Location,
+    /// The text around the match
+    pub snippet: Snippet,
+}
+
+impl Index {
+    /// Brings the [`Index`] up to date with the files provided: the files
+    /// which are new or have changed since they were indexed are indexed,
+    /// and the ones no longer provided are removed. Files whose text can't
+    /// be extracted are indexed without any, so that they are not tried
+    /// again until they change.
+    pub fn update<'a, I>(&mut self, paths: I) -> Update
+    where
+        I: IntoIterator<Item = &'a Path>,
+    {
+        let mut update = Update::default();
+
+        let paths = paths
+            .into_iter()
+            .filter(|path| Document::has_text(path))
+            .collect::<HashSet<&Path>>();
+
+        let stale = self
+            .documents
+            .iter()
+            .filter(|(_, indexed)| !paths.contains(indexed.path.as_path()))
+            .map(|(&id, _)| id)
+            .collect::<Vec<u32>>();
+
+        for id in stale {
+            self.remove(id);
+            update.removed += 1;
+        }
+
+        let mut paths = paths.into_iter().collect::<Vec<&Path>>();
+        paths.sort();
+
+        for path in paths {
+            let indexed = self
+                .documents
+                .iter()
+                .find(|(_, indexed)| indexed.path == path)
+                .map(|(&id, indexed)| (id, indexed));
+
+            let (size, modified) = match metadata(path) {
+                Ok(metadata) => (
+                    metadata.len(),
+                    metadata
+                        .modified()
+                        .ok()
+                        .and_then(|time| time.duration_since(UNIX_EPOCH).ok()),
+                ),
+                Err(_) => {
+                    // The text indexed before is no longer there to be found
+                    if let Some((id, _)) = indexed {
+                        self.remove(id);
+                    }
+
+                    update.failed.push((path.into(), document::Error::Read));
+                    continue;
+                },
+            };
+
+            match indexed {
+                Some((_, indexed))
+                    if indexed.size == size && indexed.modified == modified =>
+                {
+                    continue;
+                },
+                Some((id, _)) => self.remove(id),
+                None => {},
+            }
+
+            let sections = match Document::open(path) {
+                Ok(document) => {
+                    update.indexed += 1;
+                    document.sections
+                },
+                Err(e) => {
+                    update.failed.push((path.into(), e));
+                    vec![]
+                },
+            };
+
+            self.insert(Indexed {
+                path: path.into(),
+                size,
+                modified,
+                sections,
+            });
+        }
+
+        update
+    }
+
+    /// Returns the matches of the phrase in the files indexed, in the order
+    /// the files were indexed in and the order of the matches in each file.
+    /// The phrase is matched word by word, ignoring case and punctuation.
+    pub fn search(&self, phrase: &str) -> Vec<Hit> {
+        let phrase =
+            words(phrase).map(|(_, word)| word).collect::<Vec<String>>();
+
+        if phrase.is_empty() {
+            return vec![];
+        }
+
+        // Only the sections with all of the words can have the phrase
+        let mut sections = match self.words.get(&phrase[0]) {
+            Some(sections) => sections.clone(),
+            None => return vec![],
+        };
+
+        for word in phrase.iter().skip(1) {
+            match self.words.get(word) {
+                Some(other) => {
+                    sections.retain(|section| other.contains(section))
+                },
+                None => return vec![],
+            }
+        }
+
+        let mut hits = vec![];
+
+        for (id, i) in sections {
+            let indexed = &self.documents[&id];
+            let section = &indexed.sections[i as usize];
+
+            let text = section.text.as_str();
+            let words = words(text).collect::<Vec<(usize, String)>>();
+
+            for start in 0..words.len() {
+                let end = start + phrase.len();
+
+                let is_match = end <= words.len()
+                    && words[start..end]
+                        .iter()
+                        .zip(phrase.iter())
+                        .all(|((_, word), expected)| word == expected);
+
+                if !is_match {
+                    continue;
+                }
+
+                let offset = words[start].0;
+                let location = match extension(&indexed.path).as_deref() {
+                    Some("pdf") => Location::Page(i as usize + 1),
+                    Some("epub") => {
+                        Location::Chapter(i as usize + 1, section.title.clone())
+                    },
+                    _ => {
+                        Location::Line(text[..offset].matches('\n').count() + 1)
+                    },
+                };
+
+                hits.push(Hit {
+                    path: indexed.path.clone(),
+                    location,
+                    snippet: snippet(text, &words, start, end),
+                });
+            }
+        }
+
+        hits
+    }
+
+    /// Serializes the [`Index`] into a file in binary format.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if creating the file, serializing
+    /// the [`Index`], or writing the serialized data to the file fails.
+    pub fn save<P>(&self, file: P) -> Result<(), Error>
+    where
+        P: AsRef<Path>,
+    {
+        let mut index_file = File::create(file).map_err(|_| Error::Write)?;
+
+        let binary_data = serialize(&self).map_err(|_| Error::Write)?;
+        index_file.write_all(&binary_data).map_err(|_| Error::Write)
+    }
+
+    /// Reads an [`Index`] from a file in binary format, or returns an empty
+    /// [`Index`] if the file does not exist yet.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the file exists, but reading
+    /// and deserializing its contents fails.
+    pub fn open_or_default<P>(file: P) -> Result<Index, Error>
+    where
+        P: AsRef<Path>,
+    {
+        if !file.as_ref().exists() {
+            return Ok(Index::default());
+        }
+
+        let mut index_file = File::open(file).map_err(|_| Error::Read)?;
+
+        let mut binary_data = Vec::new();
+        index_file
+            .read_to_end(&mut binary_data)
+            .map_err(|_| Error::Read)?;
+
+        deserialize(&binary_data).map_err(|_| Error::Read)
+    }
+
+    fn insert(&mut self, indexed: Indexed) {
+        let id = self.next_id;
+        self.next_id += 1;
+
+        for (i, section) in indexed.sections.iter().enumerate() {
+            for (_, word) in words(&section.text) {
+                self.words.entry(word).or_default().insert((id, i as u32));
+            }
+        }
+
+        self.documents.insert(id, indexed);
+    }
+
+    fn remove(&mut self, id: u32) {
+        let indexed = match self.documents.remove(&id) {
+            Some(indexed) => indexed,
+            None => return,
+        };
+
+        for (i, section) in indexed.sections.iter().enumerate() {
+            for (_, word) in words(&section.text) {
+                if let Some(sections) = self.words.get_mut(&word) {
+                    sections.remove(&(id, i as u32));
+
+                    if sections.is_empty() {
+                        self.words.remove(&word);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Returns the words of the text, lowercased, along with their offsets.
+fn words(text: &str) -> impl Iterator<Item = (usize, String)> + '_ {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(move |word| {
+            let offset = word.as_ptr() as usize - text.as_ptr() as usize;
+
+            (offset, word.to_lowercase())
+        })
+}
+
+/// Returns the text around the words from `start` to `end`, with a few words
+/// on either side and the whitespace squeezed.
+fn snippet(
+    text: &str,
+    words: &[(usize, String)],
+    start: usize,
+    end: usize,
+) -> Snippet {
+    let word_end = |i: usize| {
+        let (offset, _) = words[i];
+
+        offset
+            + text[offset..]
+                .find(|c: char| !c.is_alphanumeric())
+                .unwrap_or(text.len() - offset)
+    };
+
+    let from = start.saturating_sub(CONTEXT);
+    let to = (end + CONTEXT).min(words.len());
+
+    let mut before = squeeze(&text[words[from].0..words[start].0]);
+    let matched = squeeze(&text[words[start].0..word_end(end - 1)]);
+    let mut after = squeeze(&text[word_end(end - 1)..word_end(to - 1)]);
+
+    if from > 0 {
+        before.insert(0, '…');
+    }
+
+    if to < words.len() {
+        after.push('…');
+    }
+
+    Snippet {
+        before,
+        matched,
+        after,
+    }
+}
+
+/// Replaces all runs of whitespace with single spaces.
+fn squeeze(text: &str) -> String {
+    let mut squeezed = String::with_capacity(text.len());
+
+    for c in text.chars() {
+        match c.is_whitespace() {
+            true if squeezed.ends_with(' ') => {},
+            true => squeezed.push(' '),
+            false => squeezed.push(c),
+        }
+    }
+
+    squeezed
+}
+
+/// Returns the lowercase extension of a file.
+fn extension(path: &Path) -> Option<String> {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ext.to_lowercase())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::{remove_file, write};
+
+    use utils::test::setup;
+
+    use crate::search::*;
+
+    #[test]
+    fn phrase_with_snippet() {
+        let dir = setup();
+        let path = dir.path().join("book.txt");
+        write(
+            &path,
+            "Call me Ishmael.\n\nSome years ago - never mind how long \
+             precisely - having little or no money in my purse...",
+        )
+        .unwrap();
+
+        let mut index = Index::default();
+        let update = index.update([path.as_path()]);
+        assert_eq!(update.indexed, 1);
+
+        let hits = index.search("Never  MIND");
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].location, Location::Line(3));
+        assert_eq!(hits[0].snippet.matched, "never mind");
+        assert_eq!(
+            hits[0].snippet.to_string(),
+            "Call me Ishmael. Some years ago - never mind how long precisely \
+             - having little or no money…"
+        );
+
+        // All of the words are there, but not as a phrase
+        assert!(index.search("mind never").is_empty());
+        assert!(index.search("whale").is_empty());
+    }
+
+    #[test]
+    fn incremental_updates() {
+        let dir = setup();
+        let book = dir.path().join("book.txt");
+        let article = dir.path().join("article.txt");
+        let invalid = dir.path().join("non_existent.txt");
+        write(&book, "the white whale").unwrap();
+        write(&article, "a white paper").unwrap();
+
+        let mut index = Index::default();
+        let all = [book.as_path(), article.as_path(), invalid.as_path()];
+        let update = index.update(all);
+        assert_eq!((update.indexed, update.failed.len()), (2, 1));
+        assert_eq!(index.search("white").len(), 2);
+
+        let update = index.update(all);
+        assert_eq!((update.indexed, update.failed.len()), (0, 1));
+
+        // Only the files which have changed are indexed again
+        write(&book, "the great white whale of the sea").unwrap();
+        let update = index.update(all);
+        assert_eq!(update.indexed, 1);
+        assert_eq!(index.search("great white").len(), 1);
+
+        let update = index.update([article.as_path()]);
+        assert_eq!((update.indexed, update.removed), (0, 1));
+        assert!(index.search("whale").is_empty());
+        assert!(!index.words.contains_key("whale"));
+
+        // Files which can no longer be read are dropped from the index
+        remove_file(&article).unwrap();
+        let update = index.update([article.as_path()]);
+        assert_eq!(update.failed.len(), 1);
+        assert!(index.search("white").is_empty());
+        assert!(index.documents.is_empty());
+    }
+}
@@ -0,0 +1,300 @@
+use std::fmt::Display;
+
+use indexmap::IndexSet;
+
+use super::{
+    entry::Entry,
+    fuzzy::{rank, Fields},
+    query::{Field, ParseError, Query, Value},
+};
+
+/// Errors associated with selecting the entries.
+#[derive(Debug, PartialEq)]
+pub enum Error {
+    /// The selector is neither a list nor a valid query.
+    Parse(ParseError),
+    /// There is no entry with the index provided.
+    NoSuchIndex(usize),
+    /// The range provided ends before it starts.
+    InvalidRange(usize, usize),
+    /// There is no entry with an identifier starting with the one provided.
+    NoSuchId(String),
+    /// Several entries have identifiers starting with the one provided.
+    AmbiguousId(String, usize),
+    /// There are no entries to select from.
+    NoEntries,
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Parse(e) => write!(f, "Invalid selector: {}", e),
+            Error::NoSuchIndex(index) => {
+                write!(f, "There is no entry with the index {}", index)
+            },
+            Error::InvalidRange(start, end) => {
+                write!(f, "The range {}-{} ends before it starts", start, end)
+            },
+            Error::NoSuchId(id) => write!(f, "There is no entry 'id:{}'", id),
+            Error::AmbiguousId(id, count) => {
+                write!(f, "{} entries match 'id:{}', add more of it", count, id)
+            },
+            Error::NoEntries => write!(f, "The shelf is empty"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// One of the comma-separated parts of a list selector.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Part {
+    /// The entry with the **1-based** index, e.g. `3`
+    Index(usize),
+    /// The entries with the indices in the range, inclusive, e.g. `3-7`
+    Range(usize, usize),
+    /// The entry whose identifier starts with the hex digits, e.g. `id:ab12`
+    Id(String),
+    /// The entry added last, i.e. the last one on the shelf, written as
+    /// `last`
+    Last,
+}
+
+impl Part {
+    /// Parses a part of a list selector, or returns None if it isn't one.
+    fn parse(s: &str) -> Option<Self> {
+        if s.eq_ignore_ascii_case("last") {
+            return Some(Part::Last);
+        }
+
+        if let Some(id) = s.strip_prefix("id:") {
+            return match !id.is_empty()
+                && id.chars().all(|c| c.is_ascii_hexdigit())
+            {
+                true => Some(Part::Id(id.to_lowercase())),
+                false => None,
+            };
+        }
+
+        if let Ok(index) = s.parse::<usize>() {
+            return Some(Part::Index(index));
+        }
+
+        let (start, end) = s.split_once('-')?;
+
+        Some(Part::Range(
+            start.trim().parse().ok()?,
+            end.trim().parse().ok()?,
+        ))
+    }
+
+    /// Returns the **1-based** indices of the entries this part selects.
+    fn select(&self, entries: &IndexSet<Entry>) -> Result<Vec<usize>, Error> {
+        let check = |index: usize| match (1..=entries.len()).contains(&index) {
+            true => Ok(index),
+            false => Err(Error::NoSuchIndex(index)),
+        };
+
+        match self {
+            Part::Index(index) => Ok(vec![check(*index)?]),
+            Part::Range(start, end) if start > end => {
+                Err(Error::InvalidRange(*start, *end))
+            },
+            Part::Range(start, end) => {
+                check(*start)?;
+                check(*end)?;
+
+                Ok((*start..=*end).collect())
+            },
+            Part::Id(id) => {
+                let found = entries
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, entry)| entry.id.starts_with(id.as_str()))
+                    .map(|(i, _)| i + 1)
+                    .collect::<Vec<usize>>();
+
+                match found.len() {
+                    0 => Err(Error::NoSuchId(id.clone())),
+                    1 => Ok(found),
+                    count => Err(Error::AmbiguousId(id.clone(), count)),
+                }
+            },
+            Part::Last => match entries.len() {
+                0 => Err(Error::NoEntries),
+                len => Ok(vec![len]),
+            },
+        }
+    }
+}
+
+/// A way of choosing entries on a shelf. Written as one of:
+///
+/// - a comma-separated list of indices, ranges of indices, identifiers and
+///   `last`, e.g. `1,4,9`, `3-7`, `id:ab12` or `2,5-6,last`;
+/// - a query, e.g. `tag:fiction` or `@collection` (see [`Query`]);
+/// - any other text, which is a fuzzy search for an entry (see
+///   [`rank`]).
+///
+/// A bare number is always an index, so text looking like a list is quoted
+/// to search for it instead, e.g. `"1984"`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Selector {
+    /// The entries in the list, in its order
+    List(Vec<Part>),
+    /// The entries matching the query, in the order of the shelf
+    Query(Query),
+    /// The entries matching the text, the best match first
+    Fuzzy(String),
+}
+
+impl Selector {
+    /// Parses a selector which can refer to saved queries, or collections,
+    /// as `@name` (see [`Query::parse_with`]).
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the selector is neither a
+    /// list nor a valid query.
+    pub fn parse_with<F>(selector: &str, collections: F) -> Result<Self, Error>
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        let parts = selector
+            .split(',')
+            .map(|part| Part::parse(part.trim()))
+            .collect::<Option<Vec<Part>>>();
+
+        if let Some(parts) = parts {
+            return Ok(Selector::List(parts));
+        }
+
+        match Query::parse_with(selector, collections).map_err(Error::Parse)? {
+            // Text on its own is looked for the same way it is everywhere
+            Query::Condition {
+                field: Field::Any,
+                value: Value::Text(text),
+                ..
+            } => Ok(Selector::Fuzzy(text)),
+            query => Ok(Selector::Query(query)),
+        }
+    }
+
+    /// Returns the **1-based** indices of the entries selected, without
+    /// repeating any of them. A query or a fuzzy search selects no entries
+    /// if none match, while a list must refer to existing entries.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if an index of the list doesn't
+    /// correspond to an entry, if a range is reversed, or if an identifier
+    /// matches none or several of the entries.
+    pub fn select(
+        &self,
+        entries: &IndexSet<Entry>,
+    ) -> Result<Vec<usize>, Error> {
+        let mut indices = match self {
+            Selector::List(parts) => parts
+                .iter()
+                .map(|part| part.select(entries))
+                .collect::<Result<Vec<Vec<usize>>, Error>>()?
+                .concat(),
+            Selector::Query(query) => entries
+                .iter()
+                .enumerate()
+                .filter(|(_, entry)| query.matches(entry))
+                .map(|(i, _)| i + 1)
+                .collect(),
+            Selector::Fuzzy(text) => {
+                let mut found = entries
+                    .iter()
+                    .map(Fields::of)
+                    .enumerate()
+                    .collect::<Vec<(usize, Fields)>>();
+                rank(text, &mut found, |(_, fields)| fields);
+
+                found.into_iter().map(|(i, _)| i + 1).collect()
+            },
+        };
+
+        let mut seen = vec![false; entries.len() + 1];
+        indices.retain(|&index| !std::mem::replace(&mut seen[index], true));
+
+        Ok(indices)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::write;
+
+    use indexmap::IndexSet;
+    use utils::test::setup;
+
+    use crate::{entry::Entry, selector::*, tag::Tag};
+
+    #[test]
+    fn parsed_selectors() {
+        let parse = |s| Selector::parse_with(s, |_| None);
+
+        assert_eq!(
+            parse("2, 5-6,last,id:AB12"),
+            Ok(Selector::List(vec![
+                Part::Index(2),
+                Part::Range(5, 6),
+                Part::Last,
+                Part::Id("ab12".to_string()),
+            ]))
+        );
+        assert_eq!(parse("sicp"), Ok(Selector::Fuzzy("sicp".to_string())));
+        assert_eq!(parse("1984"), Ok(Selector::List(vec![Part::Index(1984)])));
+        assert_eq!(parse("\"1984\""), Ok(Selector::Fuzzy("1984".to_string())));
+        assert!(matches!(parse("tag:fiction"), Ok(Selector::Query(_))));
+        assert!(matches!(parse("@unread"), Err(Error::Parse(_))));
+        assert!(matches!(
+            Selector::parse_with("@unread", |_| Some("status:want".into())),
+            Ok(Selector::Query(_))
+        ));
+    }
+
+    #[test]
+    fn selected_entries() {
+        let dir = setup();
+        let mut entries = IndexSet::new();
+
+        for (file, id) in [("a", "ab01"), ("b", "ab02"), ("c", "cd"), ("d", "")]
+        {
+            let path = dir.path().join(file);
+            write(&path, "").unwrap();
+
+            let mut entry = Entry::new(path.to_str().unwrap());
+            if !id.is_empty() {
+                entry.id = id.to_string();
+            }
+
+            entries.insert(entry);
+        }
+
+        let tagged = entries.pop().unwrap();
+        entries.insert(tagged.with_tags(&[Tag::new("fiction/sf")]).unwrap());
+
+        let select = |s: &str| {
+            Selector::parse_with(s, |_| None).unwrap().select(&entries)
+        };
+
+        assert_eq!(select("3,1-2,2"), Ok(vec![3, 1, 2]));
+        assert_eq!(select("last"), Ok(vec![4]));
+        assert_eq!(select("tag:fiction"), Ok(vec![4]));
+        assert_eq!(select("5"), Err(Error::NoSuchIndex(5)));
+        assert_eq!(select("3-2"), Err(Error::InvalidRange(3, 2)));
+
+        assert_eq!(select("id:AB02"), Ok(vec![2]));
+        assert_eq!(select("id:ab"), Err(Error::AmbiguousId("ab".into(), 2)));
+        assert_eq!(select("id:ef"), Err(Error::NoSuchId("ef".into())));
+
+        // The generated identifiers are unique as well
+        let id = entries.get_index(3).unwrap().id.clone();
+        assert_eq!(id.len(), 12);
+        assert_eq!(select(&format!("id:{}", id)), Ok(vec![4]));
+    }
+}
@@ -0,0 +1,177 @@
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, fmt::Display, str::FromStr};
+
+use super::{entry::Entry, status::Status};
+
+/// A series of works which an entry belongs to, along with the number of
+/// the entry in it. Written as the name of the series, followed by `#` and
+/// the number, if any, e.g. `The Expanse #3`.
+#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub struct Series {
+    /// The name of the series
+    pub name: String,
+    /// The number of the entry in the series
+    pub number: Option<u32>,
+}
+
+impl Display for Series {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.number {
+            Some(number) => write!(f, "{} #{}", self.name, number),
+            None => write!(f, "{}", self.name),
+        }
+    }
+}
+
+impl FromStr for Series {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, number) = match s.rsplit_once('#') {
+            Some((name, number)) => {
+                let number = number.trim().parse::<u32>().map_err(|_| {
+                    format!("Invalid number '{}' in the series", number.trim())
+                })?;
+
+                (name, Some(number))
+            },
+            None => (s, None),
+        };
+
+        match name.trim() {
+            "" => Err("The name of the series is empty".to_string()),
+            name => Ok(Series {
+                name: name.to_string(),
+                number,
+            }),
+        }
+    }
+}
+
+/// Groups the items by the series of the entries they hold. The series are
+/// in alphabetical order, and the items of each series are ordered by their
+/// number, the ones without a number last. The items whose entries are not
+/// part of a series are left out.
+pub fn group<T, F>(items: Vec<T>, entry: F) -> Vec<(String, Vec<T>)>
+where
+    F: Fn(&T) -> &Entry,
+{
+    let mut groups = BTreeMap::<String, Vec<(Option<u32>, T)>>::new();
+
+    for item in items {
+        if let Some(series) = entry(&item).series() {
+            groups
+                .entry(series.name)
+                .or_default()
+                .push((series.number, item));
+        }
+    }
+
+    groups
+        .into_iter()
+        .map(|(name, mut items)| {
+            items.sort_by_key(|(number, _)| number.unwrap_or(u32::MAX));
+
+            (name, items.into_iter().map(|(_, item)| item).collect())
+        })
+        .collect()
+}
+
+/// Returns the item to read next in a series, ordered as by [`group`]: the
+/// first one not started yet after the furthest one started or finished.
+/// Returns None if no item of the series has been started, or if all of
+/// the items after it have been.
+pub fn next_unread<T, F>(series: &[T], entry: F) -> Option<&T>
+where
+    F: Fn(&T) -> &Entry,
+{
+    let furthest = series
+        .iter()
+        .rposition(|item| entry(item).status != Status::Want)?;
+
+    series[furthest + 1..]
+        .iter()
+        .find(|item| entry(item).status == Status::Want)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::write;
+
+    use utils::test::setup;
+
+    use crate::{entry::*, series::*, status::*};
+
+    #[test]
+    fn parsed_series() {
+        let series = "The Expanse #3".parse::<Series>().unwrap();
+        assert_eq!(series.name, "The Expanse");
+        assert_eq!(series.number, Some(3));
+        assert_eq!(series.to_string(), "The Expanse #3");
+
+        assert_eq!("Discworld".parse::<Series>().unwrap().number, None);
+        assert!("The Expanse #three".parse::<Series>().is_err());
+        assert!(" #3".parse::<Series>().is_err());
+    }
+
+    #[test]
+    fn grouped_series() {
+        let dir = setup();
+        let entry = |file: &str, series: Option<&str>, status: Status| {
+            let path = dir.path().join(file);
+            write(&path, "").unwrap();
+
+            let mut entry = Entry::new(path.to_str().unwrap());
+            entry.series = series.map(|series| series.parse().unwrap());
+            entry.status = status;
+
+            entry
+        };
+
+        let entries = [
+            entry("caliban.txt", Some("The Expanse #2"), Status::Finished),
+            entry("gate.txt", Some("The Expanse #4"), Status::Want),
+            entry("notes.txt", None, Status::Want),
+            entry("abaddon.txt", Some("The Expanse #3"), Status::Want),
+            entry("colour.txt", Some("Discworld #1"), Status::Want),
+            entry("leviathan.txt", Some("The Expanse #1"), Status::Finished),
+        ];
+
+        let groups = group(entries.iter().collect(), |entry| entry);
+        let names = groups
+            .iter()
+            .map(|(name, items)| (name.as_str(), items.len()))
+            .collect::<Vec<(&str, usize)>>();
+        assert_eq!(names, [("Discworld", 1), ("The Expanse", 4)]);
+
+        let expanse = &groups[1].1;
+        assert_eq!(expanse[0], &entries[5]);
+        assert_eq!(next_unread(expanse, |entry| entry), Some(&&entries[3]));
+
+        // Nothing is suggested in a series that hasn't been started
+        assert_eq!(next_unread(&groups[0].1, |entry| entry), None);
+    }
+
+    #[test]
+    fn series_from_bib() {
+        let dir = setup();
+        let bib = dir.path().join("expanse.bib");
+        write(
+            &bib,
+            "@book{expanse, title = {Abaddon's Gate}, \
+             series = {The Expanse}, number = {3}}",
+        )
+        .unwrap();
+
+        let entry = Entry::new(dir.path().join("book.txt").to_str().unwrap())
+            .with_bib(bib.to_str().unwrap());
+
+        assert_eq!(
+            entry.series(),
+            Some(Series {
+                name: "The Expanse".to_string(),
+                number: Some(3),
+            })
+        );
+    }
+}
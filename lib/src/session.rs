@@ -0,0 +1,20 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// A single reading session of an entry.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Session {
+    /// The moment the session started
+    pub started: DateTime<Utc>,
+    /// How long the session lasted
+    pub duration: Duration,
+}
+
+impl Session {
+    /// Creates a new [`Session`] which started at `started` and lasted for
+    /// `duration`.
+    pub fn new(started: DateTime<Utc>, duration: Duration) -> Self {
+        Self { started, duration }
+    }
+}
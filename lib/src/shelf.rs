@@ -1,10 +1,16 @@
-use super::{entry::Entry, tag::Tag};
+use super::{
+    entry::{self, Entry},
+    query::{ParseError, Query},
+    selector::{Error as SelectError, Selector},
+    speed::SpeedTest,
+    tag::{Tag, TagTree},
+};
 
 use bincode::{deserialize, serialize};
-use indexmap::IndexSet;
+use indexmap::{set::MutableValues, IndexSet};
 use serde::{Deserialize, Serialize};
 use std::{
-    collections::HashSet,
+    collections::{BTreeMap, HashSet},
     fmt::{Debug, Display},
     fs::File,
     io::{Read, Write},
@@ -18,10 +24,26 @@ pub enum Error {
     DuplicateEntry,
     /// The entry requested is not on the [`Shelf`].
     NoSuchEntry,
+    /// No entry on the [`Shelf`] has the tag requested.
+    NoSuchTag,
+    /// Some entries on the [`Shelf`] already have the tag specified.
+    TagExists,
+    /// A tag is to be moved into itself or one of its descendants.
+    TagIntoSubtag,
+    /// The tags specified are invalid.
+    InvalidTags(entry::Error),
+    /// There is no collection with the name requested on the [`Shelf`].
+    NoSuchCollection,
+    /// The name of a collection is not a single word.
+    InvalidCollectionName,
+    /// The query of a collection is invalid.
+    InvalidQuery(ParseError),
     /// Writing the [`Shelf`] to the file specified failed.
     Write,
     /// Writing the [`Shelf`] from the file specified failed.
     Read,
+    /// The file specified was written by a newer version of the [`Shelf`].
+    UnknownVersion(u32),
 }
 
 impl Display for Error {
@@ -33,14 +55,48 @@ impl Display for Error {
             Error::NoSuchEntry => {
                 write!(f, "The requested entry is not on the shelf")
             },
+            Error::NoSuchTag => {
+                write!(f, "No entry on the shelf has the requested tag")
+            },
+            Error::TagExists => {
+                write!(f, "Some entries on the shelf already have the tag")
+            },
+            Error::TagIntoSubtag => {
+                write!(f, "A tag can't be moved into itself or its subtags")
+            },
+            Error::InvalidTags(e) => write!(f, "{}", e),
+            Error::NoSuchCollection => {
+                write!(f, "There is no such collection on the shelf")
+            },
+            Error::InvalidCollectionName => write!(
+                f,
+                "A collection name can only have letters, digits, '-' and '_'"
+            ),
+            Error::InvalidQuery(e) => write!(f, "Invalid query: {}", e),
             Error::Write => write!(f, "Writing to the database failed"),
-            Error::Read => write!(f, "Writing from the database failed"),
+            Error::Read => write!(f, "Reading from the database failed"),
+            Error::UnknownVersion(version) => write!(
+                f,
+                "The database has a newer format ({}) than this version of \
+                 bookshelf supports ({})",
+                version, VERSION
+            ),
         }
     }
 }
 
 impl std::error::Error for Error {}
 
+/// The bytes a serialized [`Shelf`] starts with, followed by its
+/// [`VERSION`]. The shelves saved before the versions were introduced start
+/// with the number of their entries instead, which is never this large.
+const MAGIC: [u8; 4] = *b"BKSH";
+
+/// The version of the layout of a serialized [`Shelf`]. It is to be bumped
+/// whenever the fields of [`Shelf`] or [`Entry`] change, with a migration
+/// from the previous layout added to [`Shelf::from_bytes`].
+pub const VERSION: u32 = 1;
+
 /// A storage for entries, which can be books, articles, etc., as well as
 /// the tags that those entries have.
 ///
@@ -51,6 +107,12 @@ pub struct Shelf {
     pub entries: IndexSet<Entry>,
     /// All tags associated with entries
     pub tags: HashSet<Tag>,
+    /// Results of the speed-reading practices, from oldest to newest
+    #[serde(default)]
+    pub speed_tests: Vec<SpeedTest>,
+    /// Saved queries, or smart collections, by their names
+    #[serde(default)]
+    pub collections: BTreeMap<String, String>,
 }
 
 impl Shelf {
@@ -62,8 +124,13 @@ impl Shelf {
     /// # Errors
     ///
     /// This function will return an error if the [`Entry`] provided already
-    /// existed on the [`Shelf`].
+    /// existed on the [`Shelf`], or if its file is another format of an
+    /// existing entry.
     pub fn add(&mut self, entry: &Entry) -> Result<(), Error> {
+        if self.contains(entry) {
+            return Err(Error::DuplicateEntry);
+        }
+
         match self.entries.insert(entry.clone()) {
             true => {
                 if entry.tags.is_some() {
@@ -78,6 +145,16 @@ impl Shelf {
         }
     }
 
+    /// Returns whether the file of the [`Entry`] is already on the [`Shelf`],
+    /// either as an entry of its own or as another format of an entry.
+    pub fn contains(&self, entry: &Entry) -> bool {
+        self.entries.contains(entry)
+            || self
+                .entries
+                .iter()
+                .any(|existing| existing.formats.contains(&entry.path))
+    }
+
     /// Removes the given [`Entry`] from the shelf. Preserves the relative
     /// order of the entries.
     ///
@@ -105,6 +182,79 @@ impl Shelf {
         }
     }
 
+    /// Returns the **1-based** index of an [`Entry`] on the [`Shelf`].
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the [`Entry`] is not on the
+    /// [`Shelf`].
+    pub fn index_of(&self, entry: &Entry) -> Result<usize, Error> {
+        match self.entries.get_index_of(entry) {
+            Some(index) => Ok(index + 1),
+            None => Err(Error::NoSuchEntry),
+        }
+    }
+
+    /// Modifies an [`Entry`] by its **1-based** index in place. The entry
+    /// keeps its position on the [`Shelf`]. Its path tells it apart from the
+    /// others, so it has to stay the same (see [`Shelf::replace_index`]).
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if there wasn't an [`Entry`] with
+    /// the index provided on the [`Shelf`].
+    pub fn update_index<F>(&mut self, index: usize, f: F) -> Result<(), Error>
+    where
+        F: FnOnce(&mut Entry),
+    {
+        match self.entries.get_index_mut2(index - 1) {
+            Some(entry) => {
+                f(entry);
+                Ok(())
+            },
+            None => Err(Error::NoSuchEntry),
+        }
+    }
+
+    /// Replaces an [`Entry`] by its **1-based** index with another one, which
+    /// may have another file. The new entry keeps the position on the
+    /// [`Shelf`], and its tags are added to the ones on the shelf.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if there wasn't an [`Entry`] with
+    /// the index provided on the [`Shelf`], or if the file of the new entry
+    /// is already on the [`Shelf`] in another entry.
+    pub fn replace_index(
+        &mut self,
+        index: usize,
+        entry: Entry,
+    ) -> Result<(), Error> {
+        let old_path = self.get_index(index)?.path.clone();
+
+        if entry.path == old_path {
+            self.set_index(index, entry);
+        } else {
+            let is_duplicate = self.entries.iter().any(|existing| {
+                existing.path != old_path
+                    && (existing.path == entry.path
+                        || existing.formats.contains(&entry.path))
+            });
+
+            if is_duplicate {
+                return Err(Error::DuplicateEntry);
+            }
+
+            let mut entries = self.entries.drain(..).collect::<Vec<Entry>>();
+            entries[index - 1] = entry;
+            self.entries = entries.into_iter().collect();
+        }
+
+        self.collect_tags();
+
+        Ok(())
+    }
+
     /// Removes an [`Entry`] by its **1-based** index on the [`Shelf`].
     /// Preserves the relative order of the entries (insertion order).
     ///
@@ -119,6 +269,365 @@ impl Shelf {
         }
     }
 
+    /// Merges the entries with the **1-based** indices provided into the
+    /// [`Entry`] with the first index, as in [`Entry::merge`], and removes
+    /// them from the [`Shelf`]. The merged entry keeps its position.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if any of the indices don't
+    /// correspond to existing entries. No entries are changed in that case.
+    pub fn merge_indices(
+        &mut self,
+        into: usize,
+        others: &[usize],
+    ) -> Result<(), Error> {
+        self.get_index(into)?;
+
+        let mut others = others
+            .iter()
+            .filter(|&&index| index != into)
+            .copied()
+            .collect::<Vec<usize>>();
+        others.sort_unstable();
+        others.dedup();
+
+        let merged = others
+            .iter()
+            .map(|&index| self.get_index(index).cloned())
+            .collect::<Result<Vec<Entry>, Error>>()?;
+
+        self.update_index(into, |entry| {
+            for other in merged {
+                entry.merge(other);
+            }
+        })?;
+
+        // Removing from the end keeps the rest of the indices valid
+        for &index in others.iter().rev() {
+            self.remove_index(index)?;
+        }
+
+        Ok(())
+    }
+
+    /// Returns the hierarchy of the tags on the [`Shelf`], along with the
+    /// number of entries tagged with each tag or any of its descendants.
+    /// The top-level tags are returned in alphabetical order.
+    pub fn tag_tree(&self) -> Vec<TagTree> {
+        let mut counts = BTreeMap::new();
+
+        for entry in self.entries.iter() {
+            let tags = entry
+                .tags
+                .iter()
+                .flatten()
+                .flat_map(Tag::ancestry)
+                .collect::<HashSet<Tag>>();
+
+            for tag in tags {
+                *counts.entry(tag).or_insert(0) += 1;
+            }
+        }
+
+        fn subtrees(
+            counts: &BTreeMap<Tag, usize>,
+            parent: Option<&Tag>,
+        ) -> Vec<TagTree> {
+            counts
+                .iter()
+                .filter(|(tag, _)| tag.parent().as_ref() == parent)
+                .map(|(tag, &count)| TagTree {
+                    tag: tag.clone(),
+                    count,
+                    children: subtrees(counts, Some(tag)),
+                })
+                .collect()
+        }
+
+        subtrees(&counts, None)
+    }
+
+    /// Adds the tags to the entries with the **1-based** indices provided,
+    /// skipping the tags an entry already has. Returns the number of entries
+    /// retagged.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if any of the indices don't
+    /// correspond to existing entries, or if the tags are invalid (see
+    /// [`Entry::add_tags`]). No entries are changed in that case.
+    pub fn tag_indices(
+        &mut self,
+        indices: &[usize],
+        tags: &[Tag],
+    ) -> Result<usize, Error> {
+        let mut retagged = vec![];
+
+        for &index in indices {
+            let mut entry = self.get_index(index)?.clone();
+
+            if entry.add_tags(tags).map_err(Error::InvalidTags)? > 0 {
+                retagged.push((index, entry));
+            }
+        }
+
+        let count = retagged.len();
+        for (index, entry) in retagged {
+            self.set_index(index, entry);
+        }
+
+        self.tags.extend(tags.iter().cloned());
+
+        Ok(count)
+    }
+
+    /// Removes the tags, but not their subtags, from the entries with the
+    /// **1-based** indices provided. Returns the number of entries retagged.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if any of the indices don't
+    /// correspond to existing entries. No entries are changed in that case.
+    pub fn untag_indices(
+        &mut self,
+        indices: &[usize],
+        tags: &[Tag],
+    ) -> Result<usize, Error> {
+        let mut retagged = vec![];
+
+        for &index in indices {
+            let mut entry = self.get_index(index)?.clone();
+
+            if entry.remove_tags(tags) > 0 {
+                retagged.push((index, entry));
+            }
+        }
+
+        let count = retagged.len();
+        for (index, entry) in retagged {
+            self.set_index(index, entry);
+        }
+
+        self.collect_tags();
+
+        Ok(count)
+    }
+
+    /// Renames a tag along with all of its descendants on every [`Entry`],
+    /// e.g. renaming `cs/algo` to `algorithms` turns `cs/algo/graphs` into
+    /// `algorithms/graphs`. Returns the number of entries retagged.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if no entry has the tag, if the
+    /// new tag is the tag or one of its descendants, or if some entries
+    /// already have the new tag (use [`Shelf::merge_tag`] to merge tags).
+    pub fn rename_tag(&mut self, from: &Tag, to: &Tag) -> Result<usize, Error> {
+        if to.is_within(from) {
+            return Err(Error::TagIntoSubtag);
+        }
+
+        if self.entries.iter().any(|entry| entry.has_tag(to)) {
+            return Err(Error::TagExists);
+        }
+
+        self.merge_tag(from, to)
+    }
+
+    /// Merges a tag along with all of its descendants into another tag on
+    /// every [`Entry`], e.g. merging `sci-fi` into `fiction/sf` turns
+    /// `sci-fi/classics` into `fiction/sf/classics`. Returns the number of
+    /// entries retagged.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if no entry has the tag, or if
+    /// the tag merged into is the tag or one of its descendants.
+    pub fn merge_tag(
+        &mut self,
+        from: &Tag,
+        into: &Tag,
+    ) -> Result<usize, Error> {
+        // Its own subtags would be moved further down again and again
+        if into.is_within(from) {
+            return Err(Error::TagIntoSubtag);
+        }
+
+        let retagged = self
+            .entries
+            .iter()
+            .cloned()
+            .enumerate()
+            .filter_map(|(i, mut entry)| {
+                entry.retag(from, into).then_some((i + 1, entry))
+            })
+            .collect::<Vec<(usize, Entry)>>();
+
+        if retagged.is_empty() {
+            return Err(Error::NoSuchTag);
+        }
+
+        let count = retagged.len();
+        for (index, entry) in retagged {
+            self.set_index(index, entry);
+        }
+
+        self.collect_tags();
+
+        Ok(count)
+    }
+
+    /// Writes an [`Entry`] over the one with the same path at the **1-based**
+    /// index, by its position rather than by looking the entry up.
+    fn set_index(&mut self, index: usize, entry: Entry) {
+        if let Some(existing) = self.entries.get_index_mut2(index - 1) {
+            *existing = entry;
+        }
+    }
+
+    /// Gathers the tags of the entries anew, after some of them are changed.
+    fn collect_tags(&mut self) {
+        self.tags = self
+            .entries
+            .iter()
+            .flat_map(|entry| entry.tags.iter().flatten().cloned())
+            .collect();
+    }
+
+    /// Parses a query, which can refer to the collections on the [`Shelf`]
+    /// as `@name`.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error pointing at the offending part of
+    /// the query if the query is invalid.
+    pub fn parse_query(&self, query: &str) -> Result<Query, ParseError> {
+        Query::parse_with(query, |name| self.collections.get(name).cloned())
+    }
+
+    /// Parses a selector, which can refer to the collections on the
+    /// [`Shelf`] as `@name`.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the selector is neither a list
+    /// nor a valid query.
+    pub fn parse_selector(
+        &self,
+        selector: &str,
+    ) -> Result<Selector, SelectError> {
+        Selector::parse_with(selector, |name| {
+            self.collections.get(name).cloned()
+        })
+    }
+
+    /// Saves a query as a collection, replacing the collection with the same
+    /// name, if there was one. The collection is kept as the text of the
+    /// query, so it is up to date with the entries and other collections.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the name has characters other
+    /// than letters, digits, `-` and `_`, or if the query is invalid or
+    /// refers to the collection itself.
+    pub fn save_collection(
+        &mut self,
+        name: &str,
+        query: &str,
+    ) -> Result<(), Error> {
+        let is_valid_name = !name.is_empty()
+            && name
+                .chars()
+                .all(|c| c.is_alphanumeric() || c == '-' || c == '_');
+
+        if !is_valid_name {
+            return Err(Error::InvalidCollectionName);
+        }
+
+        // The query is checked as if it was saved already
+        Query::parse_with(query, |other| match other == name {
+            true => Some(query.to_string()),
+            false => self.collections.get(other).cloned(),
+        })
+        .map_err(Error::InvalidQuery)?;
+
+        self.collections.insert(name.to_string(), query.to_string());
+
+        Ok(())
+    }
+
+    /// Removes a collection from the [`Shelf`].
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if there is no such collection.
+    pub fn remove_collection(&mut self, name: &str) -> Result<(), Error> {
+        match self.collections.remove(name) {
+            Some(_) => Ok(()),
+            None => Err(Error::NoSuchCollection),
+        }
+    }
+
+    /// Returns the reading speed in words per minute, averaged over the
+    /// latest speed-reading practices, or None if there were none.
+    pub fn reading_speed(&self) -> Option<f64> {
+        const LATEST: usize = 5;
+
+        let latest = self.speed_tests.iter().rev().take(LATEST);
+        let count = latest.len();
+
+        match count {
+            0 => None,
+            count => {
+                Some(latest.map(SpeedTest::wpm).sum::<f64>() / count as f64)
+            },
+        }
+    }
+
+    /// Serializes the [`Shelf`] into binary format, prefixed with the
+    /// version of its layout.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if serializing the [`Shelf`] fails.
+    pub fn to_bytes(&self) -> Result<Vec<u8>, Error> {
+        let mut bytes = MAGIC.to_vec();
+        bytes.extend(VERSION.to_le_bytes());
+        bytes.extend(serialize(&self).map_err(|_| Error::Write)?);
+
+        Ok(bytes)
+    }
+
+    /// Deserializes a [`Shelf`] from binary format, migrating it from an
+    /// older layout if needed.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if deserializing the [`Shelf`]
+    /// fails, or if its layout is newer than [`VERSION`].
+    pub fn from_bytes(bytes: &[u8]) -> Result<Shelf, Error> {
+        let versioned = match bytes.strip_prefix(&MAGIC[..]) {
+            Some(versioned) => versioned,
+            None => {
+                let shelf: legacy::Shelf =
+                    deserialize(bytes).map_err(|_| Error::Read)?;
+
+                return Ok(shelf.into());
+            },
+        };
+
+        if versioned.len() < 4 {
+            return Err(Error::Read);
+        }
+
+        let (version, data) = versioned.split_at(4);
+        match u32::from_le_bytes(version.try_into().unwrap()) {
+            VERSION => deserialize(data).map_err(|_| Error::Read),
+            version => Err(Error::UnknownVersion(version)),
+        }
+    }
+
     /// Serializes the [`Shelf`] into a file in binary format.
     ///
     /// # Errors
@@ -132,11 +641,29 @@ impl Shelf {
     {
         let mut db_file = File::create(file).map_err(|_| Error::Write)?;
 
-        let binary_data = serialize(&self).map_err(|_| Error::Write)?;
+        let binary_data = self.to_bytes()?;
         db_file.write_all(&binary_data).map_err(|_| Error::Write)
     }
 
-    /// Reads a [`Shelf`] from a file in binary format.
+    /// Reads a [`Shelf`] from a file in binary format, or returns an empty
+    /// [`Shelf`] if the file does not exist yet.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the file exists, but reading
+    /// and deserializing its contents fails.
+    pub fn open_or_default<P>(file: P) -> Result<Shelf, Error>
+    where
+        P: AsRef<Path>,
+    {
+        match file.as_ref().exists() {
+            true => Self::open(file),
+            false => Ok(Shelf::default()),
+        }
+    }
+
+    /// Reads a [`Shelf`] from a file in binary format. The shelves saved in
+    /// an older layout are migrated to the current one.
     ///
     /// # Errors
     ///
@@ -153,9 +680,57 @@ impl Shelf {
         db_file
             .read_to_end(&mut binary_data)
             .map_err(|_| Error::Read)?;
-        let shelf = deserialize(&binary_data).map_err(|_| Error::Read)?;
 
-        Ok(shelf)
+        Self::from_bytes(&binary_data)
+    }
+}
+
+/// The layout of the shelves saved before the versions were introduced,
+/// which is migrated to [`VERSION`] 1.
+mod legacy {
+    use std::{collections::HashSet, path::PathBuf};
+
+    use serde::Deserialize;
+
+    use crate::tag::Tag;
+
+    #[derive(Deserialize)]
+    pub(super) struct Entry {
+        path: PathBuf,
+        bib_path: Option<PathBuf>,
+        tags: Option<Vec<Tag>>,
+    }
+
+    #[derive(Deserialize)]
+    pub(super) struct Shelf {
+        entries: Vec<Entry>,
+        tags: HashSet<Tag>,
+    }
+
+    impl From<Shelf> for super::Shelf {
+        fn from(legacy: Shelf) -> Self {
+            let entries = legacy
+                .entries
+                .into_iter()
+                .map(|legacy| {
+                    let mut entry = super::Entry {
+                        path: legacy.path,
+                        bib_path: legacy.bib_path,
+                        tags: legacy.tags,
+                        ..Default::default()
+                    };
+                    entry.backfill_id();
+
+                    entry
+                })
+                .collect();
+
+            Self {
+                entries,
+                tags: legacy.tags,
+                ..Default::default()
+            }
+        }
     }
 }
 
@@ -163,17 +738,316 @@ impl Shelf {
 mod tests {
     use utils::test::setup;
 
-    use crate::{entry::*, shelf::*, tag::*};
+    use std::fs::remove_file;
+
+    use crate::{entry::Entry, shelf::*, status::Status, tag::*};
+
+    fn tagged_shelf(dir: &std::path::Path) -> Shelf {
+        let entry1 = Entry::new(dir.join("book.txt").to_str().unwrap())
+            .with_tags(&[Tag::new("cs/algo/graphs"), Tag::new("fiction")])
+            .unwrap();
+        let entry2 = Entry::new(dir.join("another_book.txt").to_str().unwrap())
+            .with_tags(&[Tag::new("cs/algo"), Tag::new("cs/systems")])
+            .unwrap();
+
+        let mut shelf = Shelf::default();
+        shelf.add(&entry1).unwrap();
+        shelf.add(&entry2).unwrap();
+
+        shelf
+    }
+
+    #[test]
+    fn migrated_legacy_shelf() {
+        #[derive(Serialize)]
+        struct LegacyEntry {
+            path: std::path::PathBuf,
+            bib_path: Option<std::path::PathBuf>,
+            tags: Option<Vec<Tag>>,
+        }
+
+        #[derive(Serialize)]
+        struct LegacyShelf {
+            entries: IndexSet<LegacyEntry>,
+            tags: HashSet<Tag>,
+        }
+
+        impl PartialEq for LegacyEntry {
+            fn eq(&self, other: &Self) -> bool {
+                self.path == other.path
+            }
+        }
+
+        impl Eq for LegacyEntry {}
+
+        impl std::hash::Hash for LegacyEntry {
+            fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+                self.path.hash(state);
+            }
+        }
+
+        let dir = setup();
+        let book = dir.path().join("book.txt").canonicalize().unwrap();
+        let article = dir.path().join("article.txt").canonicalize().unwrap();
+
+        let legacy = LegacyShelf {
+            entries: IndexSet::from([
+                LegacyEntry {
+                    path: book.clone(),
+                    bib_path: None,
+                    tags: Some(vec![Tag::new("fiction")]),
+                },
+                LegacyEntry {
+                    path: article.clone(),
+                    bib_path: None,
+                    tags: None,
+                },
+            ]),
+            tags: HashSet::from([Tag::new("fiction")]),
+        };
+        let db = dir.path().join("legacy.db");
+        File::create(&db)
+            .unwrap()
+            .write_all(&serialize(&legacy).unwrap())
+            .unwrap();
+
+        let shelf = Shelf::open(&db).unwrap();
+        assert_eq!(shelf.entries.len(), 2);
+        assert!(shelf.tags.contains(&Tag::new("fiction")));
+
+        let migrated = shelf.get_index(1).unwrap();
+        assert_eq!(migrated.path, book);
+        assert!(migrated.has_tag(&Tag::new("fiction")));
+        assert!(migrated.sessions.is_empty());
+
+        // The migrated entries can be selected by their new ids
+        let id = &shelf.get_index(2).unwrap().id;
+        assert_eq!(id.len(), 12);
+        assert_ne!(id, &migrated.id);
+        let selector = shelf.parse_selector(&format!("id:{}", id)).unwrap();
+        let selected = selector.select(&shelf.entries).unwrap();
+        assert_eq!(selected, [2]);
+
+        // Once saved, the shelf is read back in the current layout
+        shelf.save(&db).unwrap();
+        let reopened = Shelf::open(&db).unwrap();
+        assert_eq!(&reopened.get_index(2).unwrap().id, id);
+
+        let mut newer = MAGIC.to_vec();
+        newer.extend((VERSION + 1).to_le_bytes());
+        assert!(matches!(
+            Shelf::from_bytes(&newer),
+            Err(Error::UnknownVersion(_))
+        ));
+    }
+
+    #[test]
+    fn merged_entries() {
+        let dir = setup();
+        let mut shelf = tagged_shelf(dir.path());
+        let article =
+            Entry::new(dir.path().join("article.txt").to_str().unwrap());
+        shelf.add(&article).unwrap();
+
+        shelf.merge_indices(3, &[1]).unwrap();
+
+        assert_eq!(shelf.entries.len(), 2);
+        let merged = shelf.get_index(2).unwrap();
+        assert_eq!(merged.path, article.path);
+        assert!(merged.tags.as_ref().unwrap().contains(&Tag::new("fiction")));
+
+        // The merged file can't be added as an entry of its own again
+        let book = Entry::new(dir.path().join("book.txt").to_str().unwrap());
+        assert_eq!(merged.formats, vec![book.path.clone()]);
+        assert!(shelf.add(&book).is_err());
+    }
+
+    #[test]
+    fn replaced_entries() {
+        let dir = setup();
+        let mut shelf = tagged_shelf(dir.path());
+        let article =
+            Entry::new(dir.path().join("article.txt").to_str().unwrap());
+        shelf.add(&article).unwrap();
+
+        let new_file = dir.path().join("moved.txt");
+        File::create(&new_file).unwrap();
+
+        let mut moved = shelf.get_index(1).unwrap().clone();
+        let old_path = moved.path.clone();
+        moved.path = new_file.canonicalize().unwrap();
+        moved.tags = Some(vec![Tag::new("moved")]);
+        shelf.replace_index(1, moved.clone()).unwrap();
+
+        // The entry keeps its position under its new file
+        assert_eq!(shelf.entries.len(), 3);
+        assert_eq!(shelf.get_index(1).unwrap().path, moved.path);
+        assert_eq!(shelf.get_index(1).unwrap().id, moved.id);
+        assert_eq!(shelf.get_index(3).unwrap().path, article.path);
+        assert!(shelf.entries.iter().all(|entry| entry.path != old_path));
+        assert!(shelf.tags.contains(&Tag::new("moved")));
+        assert!(!shelf.tags.contains(&Tag::new("fiction")));
+
+        moved.path = article.path.clone();
+        assert!(shelf.replace_index(1, moved).is_err());
+    }
+
+    #[test]
+    fn entries_without_files() {
+        let dir = setup();
+        let mut shelf = tagged_shelf(dir.path());
+        let article =
+            Entry::new(dir.path().join("article.txt").to_str().unwrap());
+        shelf.add(&article).unwrap();
+
+        // The file is deleted after the entry is added
+        remove_file(&article.path).unwrap();
+
+        shelf
+            .update_index(3, |entry| entry.status = Status::Finished)
+            .unwrap();
+        assert_eq!(shelf.tag_indices(&[3], &[Tag::new("gone")]).unwrap(), 1);
+        assert_eq!(shelf.index_of(&article).unwrap(), 3);
+
+        let updated = shelf.get_index(3).unwrap();
+        assert_eq!(updated.status, Status::Finished);
+        assert!(updated.has_tag(&Tag::new("gone")));
+        assert_eq!(shelf.entries.len(), 3);
+    }
+
+    #[test]
+    fn bulk_tagging() {
+        let dir = setup();
+        let mut shelf = tagged_shelf(dir.path());
+
+        assert_eq!(
+            shelf.tag_indices(&[1, 2], &[Tag::new("fiction")]).unwrap(),
+            1
+        );
+        assert!(shelf.get_index(2).unwrap().has_tag(&Tag::new("fiction")));
+        assert!(shelf.tag_indices(&[1, 3], &[Tag::new("cs")]).is_err());
+        assert!(!shelf
+            .get_index(1)
+            .unwrap()
+            .tags
+            .as_ref()
+            .unwrap()
+            .contains(&Tag::new("cs")));
+
+        assert_eq!(
+            shelf
+                .untag_indices(&[1, 2], &[Tag::new("fiction")])
+                .unwrap(),
+            2
+        );
+        assert!(!shelf.tags.contains(&Tag::new("fiction")));
+    }
+
+    #[test]
+    fn saved_collections() {
+        let dir = setup();
+        let mut shelf = tagged_shelf(dir.path());
+
+        shelf
+            .save_collection("cs", "tag:cs and not tag:fiction")
+            .unwrap();
+        shelf
+            .save_collection("read-cs", "@cs status:finished")
+            .unwrap();
+
+        let query = shelf.parse_query("@cs").unwrap();
+        let matched = shelf
+            .entries
+            .iter()
+            .filter(|entry| query.matches(entry))
+            .count();
+        assert_eq!(matched, 1);
+
+        assert!(matches!(
+            shelf.save_collection("cs", "@read-cs"),
+            Err(Error::InvalidQuery(_))
+        ));
+        assert!(matches!(
+            shelf.save_collection("my cs", "tag:cs"),
+            Err(Error::InvalidCollectionName)
+        ));
+        assert_eq!(shelf.collections["cs"], "tag:cs and not tag:fiction");
+
+        shelf.remove_collection("read-cs").unwrap();
+        assert!(matches!(
+            shelf.remove_collection("read-cs"),
+            Err(Error::NoSuchCollection)
+        ));
+    }
+
+    #[test]
+    fn tag_tree_counts() {
+        let dir = setup();
+        let shelf = tagged_shelf(dir.path());
+
+        let tree = shelf.tag_tree();
+
+        assert_eq!(tree.len(), 2);
+        assert_eq!(tree[0].tag, Tag::new("cs"));
+        assert_eq!(tree[0].count, 2);
+        assert_eq!(tree[0].children.len(), 2);
+        assert_eq!(tree[0].children[0].tag, Tag::new("cs/algo"));
+        assert_eq!(tree[0].children[0].count, 2);
+        assert_eq!(tree[0].children[0].children[0].count, 1);
+        assert_eq!(tree[1].tag, Tag::new("fiction"));
+        assert_eq!(tree[1].count, 1);
+    }
+
+    #[test]
+    fn renamed_subtree() {
+        let dir = setup();
+        let mut shelf = tagged_shelf(dir.path());
+
+        let renamed = shelf.rename_tag(&Tag::new("cs/algo"), &Tag::new("algo"));
+
+        assert_eq!(renamed.unwrap(), 2);
+        assert!(shelf.tags.contains(&Tag::new("algo/graphs")));
+        assert!(!shelf.tags.contains(&Tag::new("cs/algo")));
+        assert!(matches!(
+            shelf.rename_tag(&Tag::new("cs"), &Tag::new("fiction")),
+            Err(Error::TagExists)
+        ));
+        assert!(matches!(
+            shelf.merge_tag(&Tag::new("math"), &Tag::new("cs")),
+            Err(Error::NoSuchTag)
+        ));
+
+        // Moving a tag into its own subtree would nest the subtree in itself
+        assert!(matches!(
+            shelf.merge_tag(&Tag::new("cs"), &Tag::new("cs/old")),
+            Err(Error::TagIntoSubtag)
+        ));
+        assert!(matches!(
+            shelf.rename_tag(&Tag::new("cs"), &Tag::new("cs/old")),
+            Err(Error::TagIntoSubtag)
+        ));
+        assert!(!shelf.tags.contains(&Tag::new("cs/old")));
+
+        assert_eq!(
+            shelf
+                .merge_tag(&Tag::new("cs"), &Tag::new("fiction"))
+                .unwrap(),
+            1
+        );
+        assert!(shelf.tags.contains(&Tag::new("fiction/systems")));
+    }
 
     #[test]
     fn no_duplicate_tags() {
         let dir = setup();
 
         let entry1 = Entry::new(dir.path().join("book.txt").to_str().unwrap())
-            .with_tags(&[Tag::new("fiction")]);
+            .with_tags(&[Tag::new("fiction")])
+            .unwrap();
         let entry2 =
             Entry::new(dir.path().join("another_book.txt").to_str().unwrap())
-                .with_tags(&[Tag::new("fiction"), Tag::new("classics")]);
+                .with_tags(&[Tag::new("fiction"), Tag::new("classics")])
+                .unwrap();
 
         let mut shelf = Shelf::default();
 
@@ -0,0 +1,319 @@
+use chrono::{DateTime, Utc};
+use std::{cmp::Ordering, fmt::Display, str::FromStr};
+
+use super::entry::Entry;
+
+/// A property of an entry the entries can be sorted by.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Key {
+    /// The title, from the BibTeX file or the file name
+    Title,
+    /// The last name of the first author
+    Author,
+    /// The year of publication
+    Year,
+    /// The date the entry was added, the latest first
+    Added,
+    /// The date the entry was last opened, the latest first
+    Opened,
+    /// The rating, the highest first
+    Rating,
+    /// The reading progress, the furthest first
+    Progress,
+    /// The path to the file
+    Path,
+    /// The name of the series, then the number in it
+    Series,
+    /// The first of the tags in alphabetical order
+    Tag,
+}
+
+impl Key {
+    /// All of the keys, in the order they are cycled through
+    pub const ALL: [Key; 10] = [
+        Key::Title,
+        Key::Author,
+        Key::Year,
+        Key::Added,
+        Key::Opened,
+        Key::Rating,
+        Key::Progress,
+        Key::Path,
+        Key::Series,
+        Key::Tag,
+    ];
+
+    /// Returns whether the entries are sorted by this key in descending
+    /// order, unless the order is reversed.
+    fn is_descending(&self) -> bool {
+        matches!(self, Key::Added | Key::Opened | Key::Rating | Key::Progress)
+    }
+
+    /// Returns the value of this key for the entry provided, or None if the
+    /// entry doesn't have it.
+    fn value(&self, entry: &Entry) -> Option<Value> {
+        match self {
+            Key::Title => Some(Value::Text(entry.title().to_lowercase())),
+            Key::Author => entry.authors().first().map(|author| {
+                let last_name = author.rsplit(' ').next().unwrap_or(author);
+
+                Value::Text(last_name.to_lowercase())
+            }),
+            Key::Year => entry.year().map(|year| Value::Number(year as f64)),
+            Key::Added => entry.added.map(Value::Date),
+            Key::Opened => entry.last_opened.map(Value::Date),
+            Key::Rating => entry
+                .rating
+                .map(|rating| Value::Number(rating.stars() as f64)),
+            Key::Progress => entry
+                .progress
+                .map(|progress| Value::Number(progress.fraction())),
+            Key::Path => {
+                Some(Value::Text(entry.path.to_string_lossy().to_lowercase()))
+            },
+            // The entries without a number go last in their series
+            Key::Series => entry.series().map(|series| {
+                Value::Series(
+                    series.name.to_lowercase(),
+                    series.number.unwrap_or(u32::MAX),
+                )
+            }),
+            Key::Tag => entry
+                .tags
+                .iter()
+                .flatten()
+                .map(|tag| tag.keyword().to_lowercase())
+                .min()
+                .map(Value::Text),
+        }
+    }
+}
+
+impl Display for Key {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Key::Title => write!(f, "title"),
+            Key::Author => write!(f, "author"),
+            Key::Year => write!(f, "year"),
+            Key::Added => write!(f, "added"),
+            Key::Opened => write!(f, "opened"),
+            Key::Rating => write!(f, "rating"),
+            Key::Progress => write!(f, "progress"),
+            Key::Path => write!(f, "path"),
+            Key::Series => write!(f, "series"),
+            Key::Tag => write!(f, "tag"),
+        }
+    }
+}
+
+impl FromStr for Key {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Key::ALL
+            .into_iter()
+            .find(|key| key.to_string() == s.to_lowercase())
+            .ok_or_else(|| {
+                let keys = Key::ALL.map(|key| key.to_string()).join(", ");
+
+                format!("Unknown sort key '{}', expected one of {}", s, keys)
+            })
+    }
+}
+
+/// A key to sort the entries by, in its natural order or in reverse.
+///
+/// Text and years are sorted in ascending order, while dates, ratings and
+/// progress are sorted in descending order. Written as the name of the key,
+/// prefixed with `-` to reverse the order, e.g. `-year`. The entries without
+/// the key always go last.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct SortKey {
+    /// The key to sort by
+    pub key: Key,
+    /// Whether the natural order of the key is reversed
+    pub reverse: bool,
+}
+
+impl SortKey {
+    /// Creates a new [`SortKey`] sorting in the natural order of the key.
+    pub fn new(key: Key) -> Self {
+        Self {
+            key,
+            reverse: false,
+        }
+    }
+
+    /// Returns the same [`SortKey`] with the order reversed.
+    pub fn reversed(self) -> Self {
+        Self {
+            reverse: !self.reverse,
+            ..self
+        }
+    }
+
+    /// Parses a comma-separated list of sort keys, e.g. `author,-year`.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if any of the keys is unknown.
+    pub fn parse_list(s: &str) -> Result<Vec<Self>, String> {
+        s.split(',').map(str::trim).map(str::parse).collect()
+    }
+
+    fn compare(&self, a: &Option<Value>, b: &Option<Value>) -> Ordering {
+        match (a, b) {
+            (Some(a), Some(b)) => {
+                let ordering = a.partial_cmp(b).unwrap_or(Ordering::Equal);
+
+                match self.key.is_descending() != self.reverse {
+                    true => ordering.reverse(),
+                    false => ordering,
+                }
+            },
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => Ordering::Equal,
+        }
+    }
+}
+
+impl Display for SortKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.reverse {
+            true => write!(f, "-{}", self.key),
+            false => write!(f, "{}", self.key),
+        }
+    }
+}
+
+impl FromStr for SortKey {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.strip_prefix('-') {
+            Some(key) => Ok(SortKey::new(key.parse()?).reversed()),
+            None => Ok(SortKey::new(s.parse()?)),
+        }
+    }
+}
+
+/// The value of a sort key for an entry.
+#[derive(PartialEq, PartialOrd)]
+enum Value {
+    Text(String),
+    Number(f64),
+    Date(DateTime<Utc>),
+    Series(String, u32),
+}
+
+/// Sorts the items by the entries they hold, by each of the keys in turn.
+/// The sort is stable, so the items equal by all of the keys stay in their
+/// order. The values of the keys are looked up once per entry.
+pub fn sort_by_keys<T, F>(items: &mut Vec<T>, keys: &[SortKey], entry: F)
+where
+    F: Fn(&T) -> &Entry,
+{
+    let mut keyed = items
+        .drain(..)
+        .map(|item| {
+            let values = keys
+                .iter()
+                .map(|sort_key| sort_key.key.value(entry(&item)))
+                .collect::<Vec<Option<Value>>>();
+
+            (values, item)
+        })
+        .collect::<Vec<(Vec<Option<Value>>, T)>>();
+
+    keyed.sort_by(|(a, _), (b, _)| {
+        keys.iter()
+            .zip(a.iter().zip(b.iter()))
+            .map(|(sort_key, (a, b))| sort_key.compare(a, b))
+            .find(|ordering| ordering.is_ne())
+            .unwrap_or(Ordering::Equal)
+    });
+
+    items.extend(keyed.into_iter().map(|(_, item)| item));
+}
+
+#[cfg(test)]
+mod tests {
+    use utils::test::setup;
+
+    use crate::{entry::*, rating::Rating, sort::*, tag::Tag};
+
+    #[test]
+    fn parse_sort_keys() {
+        assert_eq!(
+            SortKey::parse_list("author, -year"),
+            Ok(vec![
+                SortKey::new(Key::Author),
+                SortKey::new(Key::Year).reversed()
+            ])
+        );
+        assert!(SortKey::parse_list("rating,colour").is_err());
+        assert_eq!(SortKey::new(Key::Opened).reversed().to_string(), "-opened");
+    }
+
+    #[test]
+    fn multiple_keys() {
+        let dir = setup();
+
+        let rated = |file: &str, stars: Option<f32>| {
+            let mut entry = Entry::new(dir.path().join(file).to_str().unwrap());
+            entry.rating = stars.map(|stars| Rating::try_from(stars).unwrap());
+            entry
+        };
+
+        let mut entries = vec![
+            rated("article.txt", None),
+            rated("book.txt", Some(3.0)),
+            rated("another_book.txt", Some(3.0)),
+        ];
+
+        let names = |entries: &Vec<Entry>| {
+            entries
+                .iter()
+                .map(|entry| entry.title())
+                .collect::<Vec<String>>()
+        };
+
+        let keys = [SortKey::new(Key::Rating), SortKey::new(Key::Title)];
+        sort_by_keys(&mut entries, &keys, |entry| entry);
+        assert_eq!(names(&entries), ["another_book", "book", "article"]);
+
+        // The unrated entries stay last in reverse
+        let keys = SortKey::parse_list("-rating,-title").unwrap();
+        sort_by_keys(&mut entries, &keys, |entry| entry);
+        assert_eq!(names(&entries), ["book", "another_book", "article"]);
+    }
+
+    #[test]
+    fn tag_key() {
+        let dir = setup();
+
+        let tagged = |file: &str, tags: &[&str]| {
+            Entry::new(dir.path().join(file).to_str().unwrap())
+                .with_tags(
+                    &tags.iter().map(|tag| Tag::new(tag)).collect::<Vec<Tag>>(),
+                )
+                .unwrap()
+        };
+
+        let mut entries = vec![
+            tagged("article.txt", &[]),
+            tagged("book.txt", &["sf", "Fiction"]),
+            tagged("another_book.txt", &["cs/algo"]),
+        ];
+
+        sort_by_keys(&mut entries, &[SortKey::new(Key::Tag)], |entry| entry);
+        assert_eq!(
+            entries
+                .iter()
+                .map(|entry| entry.title())
+                .collect::<Vec<_>>(),
+            ["another_book", "book", "article"]
+        );
+    }
+}
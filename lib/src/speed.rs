@@ -0,0 +1,233 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::{collections::HashSet, time::Duration};
+
+use super::document::Document;
+
+/// The result of a single speed-reading practice.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct SpeedTest {
+    /// The moment the practice took place
+    pub date: DateTime<Utc>,
+    /// The number of words read
+    pub words: usize,
+    /// The time it took to read the words
+    pub duration: Duration,
+    /// The number of comprehension questions asked
+    pub questions: usize,
+    /// The number of comprehension questions answered correctly
+    pub correct: usize,
+}
+
+impl SpeedTest {
+    /// Returns the reading speed in words per minute.
+    pub fn wpm(&self) -> f64 {
+        let minutes = self.duration.as_secs_f64() / 60.0;
+
+        match minutes > 0.0 {
+            true => self.words as f64 / minutes,
+            false => 0.0,
+        }
+    }
+
+    /// Returns the share of the comprehension questions answered correctly,
+    /// or None if no questions were asked.
+    pub fn comprehension(&self) -> Option<f64> {
+        match self.questions {
+            0 => None,
+            questions => Some(self.correct as f64 / questions as f64),
+        }
+    }
+}
+
+/// A comprehension question about a [`Passage`]: whether a word was in it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Question {
+    /// The word asked about
+    pub word: String,
+    /// Whether the word was in the passage
+    pub answer: bool,
+}
+
+/// A passage of a [`Document`] to practice speed reading on.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Passage {
+    /// The words of the passage
+    pub words: Vec<String>,
+    /// Comprehension questions about the passage
+    pub questions: Vec<Question>,
+}
+
+impl Passage {
+    /// The least number of words a [`Passage`] can have
+    pub const MIN_WORDS: usize = 20;
+
+    /// Picks a passage of about `length` words from a [`Document`], starting
+    /// at a sentence, along with `questions` comprehension questions. The
+    /// `seed` determines which passage is picked.
+    ///
+    /// Returns None if the document is shorter than [`Passage::MIN_WORDS`].
+    pub fn pick(
+        document: &Document,
+        length: usize,
+        questions: usize,
+        seed: u64,
+    ) -> Option<Passage> {
+        let words = document.words();
+        let length = length.max(Self::MIN_WORDS).min(words.len());
+
+        if length < Self::MIN_WORDS {
+            return None;
+        }
+
+        let mut rng = Rng::new(seed);
+
+        // Moving the start to the beginning of the closest sentence
+        let mut start = rng.below(words.len() - length + 1);
+        start = (start..(start + 50).min(words.len() - length))
+            .find(|&i| i == 0 || words[i - 1].ends_with(['.', '!', '?']))
+            .unwrap_or(start);
+
+        let passage = &words[start..start + length];
+
+        let mut present = distinct_words(passage.iter());
+        let mut absent = distinct_words(
+            words[..start].iter().chain(words[start + length..].iter()),
+        )
+        .into_iter()
+        .filter(|word| !present.contains(word))
+        .collect::<Vec<String>>();
+
+        // Every word is asked about at most once
+        let questions = (0..questions)
+            .filter_map(|_| match rng.below(2) == 0 && !absent.is_empty() {
+                true => Some(Question {
+                    word: absent.swap_remove(rng.below(absent.len())),
+                    answer: false,
+                }),
+                false if !present.is_empty() => Some(Question {
+                    word: present.swap_remove(rng.below(present.len())),
+                    answer: true,
+                }),
+                false => None,
+            })
+            .collect();
+
+        Some(Passage {
+            words: passage.iter().map(|word| word.to_string()).collect(),
+            questions,
+        })
+    }
+}
+
+/// Returns the time it takes to read `words` words at `wpm` words per
+/// minute.
+pub fn estimate(words: usize, wpm: f64) -> Duration {
+    match wpm > 0.0 {
+        true => Duration::from_secs_f64(words as f64 / wpm * 60.0),
+        false => Duration::ZERO,
+    }
+}
+
+/// Returns the distinct long words (stripped of punctuation and lowercased)
+/// in the order of their first appearance. Long words make for better
+/// comprehension questions.
+fn distinct_words<'a, I>(words: I) -> Vec<String>
+where
+    I: Iterator<Item = &'a &'a str>,
+{
+    let mut seen = HashSet::new();
+
+    words
+        .map(|word| {
+            word.trim_matches(|c: char| !c.is_alphanumeric())
+                .to_lowercase()
+        })
+        .filter(|word| word.chars().count() >= 6)
+        .filter(|word| seen.insert(word.clone()))
+        .collect()
+}
+
+/// A tiny xorshift pseudorandom number generator, good enough to pick
+/// passages and questions.
+struct Rng(u64);
+
+impl Rng {
+    fn new(seed: u64) -> Self {
+        // The state of a xorshift generator must not be zero
+        Self(seed.max(1))
+    }
+
+    /// Returns a pseudorandom number in `0..bound`.
+    fn below(&mut self, bound: usize) -> usize {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+
+        (self.0 % bound as u64) as usize
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{document::Section, speed::*};
+
+    fn document() -> Document {
+        let text = (1..=100)
+            .map(|i| format!("Sentence number{} is here.", i))
+            .collect::<Vec<String>>()
+            .join(" ");
+
+        Document {
+            sections: vec![Section { title: None, text }],
+        }
+    }
+
+    #[test]
+    fn words_per_minute() {
+        let test = SpeedTest {
+            date: Utc::now(),
+            words: 300,
+            duration: Duration::from_secs(90),
+            questions: 4,
+            correct: 3,
+        };
+
+        assert_eq!(test.wpm(), 200.0);
+        assert_eq!(test.comprehension(), Some(0.75));
+        assert_eq!(estimate(600, test.wpm()), Duration::from_secs(180));
+    }
+
+    #[test]
+    fn passage_starts_at_sentence() {
+        let document = document();
+
+        for seed in 0..20 {
+            let passage = Passage::pick(&document, 40, 3, seed).unwrap();
+
+            assert_eq!(passage.words.len(), 40);
+            assert_eq!(passage.words[0], "Sentence");
+            assert_eq!(passage.questions.len(), 3);
+
+            for question in passage.questions {
+                let in_passage = passage.words.iter().any(|word| {
+                    word.to_lowercase().trim_end_matches('.') == question.word
+                });
+
+                assert_eq!(in_passage, question.answer);
+            }
+        }
+    }
+
+    #[test]
+    fn document_too_short() {
+        let document = Document {
+            sections: vec![Section {
+                title: None,
+                text: "Too short.".to_string(),
+            }],
+        };
+
+        assert!(Passage::pick(&document, 100, 3, 42).is_none());
+    }
+}
@@ -0,0 +1,52 @@
+use serde::{Deserialize, Serialize};
+use std::{fmt::Display, str::FromStr};
+
+/// The reading status of an entry
+#[derive(
+    Clone, Copy, Debug, Default, Deserialize, Eq, Hash, PartialEq, Serialize,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum Status {
+    /// The entry is yet to be read
+    #[default]
+    Want,
+    /// The entry is being read at the moment
+    Reading,
+    /// The entry has been read
+    Finished,
+}
+
+impl Display for Status {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Status::Want => write!(f, "want"),
+            Status::Reading => write!(f, "reading"),
+            Status::Finished => write!(f, "finished"),
+        }
+    }
+}
+
+impl FromStr for Status {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "want" => Ok(Status::Want),
+            "reading" => Ok(Status::Reading),
+            "finished" => Ok(Status::Finished),
+            _ => Err(format!("Unknown status '{}'", s)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::status::*;
+
+    #[test]
+    fn parse_status() {
+        assert_eq!("reading".parse::<Status>(), Ok(Status::Reading));
+        assert_eq!("Finished".parse::<Status>(), Ok(Status::Finished));
+        assert!("unread".parse::<Status>().is_err());
+    }
+}
@@ -1,6 +1,10 @@
 use serde::{Deserialize, Serialize};
+use std::fmt::Display;
 
-/// An entry tag, which can be used for sorting the entries
+/// An entry tag, which can be used for sorting the entries.
+///
+/// Tags are hierarchical: a keyword like `cs/algorithms/graphs` is a path,
+/// and the tag is a descendant of `cs/algorithms` and `cs`.
 #[derive(
     Clone,
     Eq,
@@ -19,10 +23,140 @@ pub struct Tag {
 }
 
 impl Tag {
-    /// Creates a new tag from the keyword
+    /// The separator of the components of a tag path
+    pub const SEPARATOR: char = '/';
+
+    /// Creates a new tag from the keyword. Empty path components and the
+    /// whitespace around them are dropped, e.g. ` cs//algorithms/ ` becomes
+    /// `cs/algorithms`.
     pub fn new(keyword: &str) -> Self {
-        Self {
-            keyword: keyword.to_string(),
+        let keyword = keyword
+            .split(Self::SEPARATOR)
+            .map(str::trim)
+            .filter(|component| !component.is_empty())
+            .collect::<Vec<&str>>()
+            .join(&Self::SEPARATOR.to_string());
+
+        Self { keyword }
+    }
+
+    /// Returns the whole keyword of the tag.
+    pub fn keyword(&self) -> &str {
+        &self.keyword
+    }
+
+    /// Returns the last component of the tag path, e.g. `graphs` for
+    /// `cs/algorithms/graphs`.
+    pub fn name(&self) -> &str {
+        self.keyword
+            .rsplit(Self::SEPARATOR)
+            .next()
+            .unwrap_or_default()
+    }
+
+    /// Returns the parent of the tag, or None for a top-level tag.
+    pub fn parent(&self) -> Option<Tag> {
+        self.keyword
+            .rsplit_once(Self::SEPARATOR)
+            .map(|(parent, _)| Tag::new(parent))
+    }
+
+    /// Returns the tag along with all of its ancestors, from the top-level
+    /// one down to the tag itself.
+    pub fn ancestry(&self) -> Vec<Tag> {
+        let mut ancestry = vec![self.clone()];
+
+        while let Some(parent) = ancestry.last().unwrap().parent() {
+            ancestry.push(parent);
+        }
+
+        ancestry.reverse();
+
+        ancestry
+    }
+
+    /// Returns the depth of the tag in the hierarchy, 0 for a top-level tag.
+    pub fn depth(&self) -> usize {
+        self.keyword.matches(Self::SEPARATOR).count()
+    }
+
+    /// Returns whether this tag is the tag provided or one of its
+    /// descendants.
+    pub fn is_within(&self, ancestor: &Tag) -> bool {
+        match self.keyword.strip_prefix(&ancestor.keyword) {
+            Some(rest) => rest.is_empty() || rest.starts_with(Self::SEPARATOR),
+            None => false,
         }
     }
+
+    /// Moves the tag from the subtree of `from` to the subtree of `to`,
+    /// e.g. rebasing `cs/algo/graphs` from `cs/algo` to `algorithms` gives
+    /// `algorithms/graphs`. Returns None if the tag is not within `from`.
+    pub fn rebase(&self, from: &Tag, to: &Tag) -> Option<Tag> {
+        if !self.is_within(from) {
+            return None;
+        }
+
+        let rest = &self.keyword[from.keyword.len()..];
+
+        Some(Tag::new(&format!("{}{}", to.keyword, rest)))
+    }
+}
+
+impl Display for Tag {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.keyword)
+    }
+}
+
+/// A node of the tag hierarchy on a shelf.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TagTree {
+    /// The tag of the node
+    pub tag: Tag,
+    /// The number of entries tagged with the tag or any of its descendants
+    pub count: usize,
+    /// The child tags, in alphabetical order
+    pub children: Vec<TagTree>,
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tag::*;
+
+    #[test]
+    fn normalized_keyword() {
+        assert_eq!(Tag::new(" cs//algorithms/ ").keyword(), "cs/algorithms");
+        assert_eq!(Tag::new("fiction").keyword(), "fiction");
+    }
+
+    #[test]
+    fn tag_hierarchy() {
+        let tag = Tag::new("cs/algorithms/graphs");
+
+        assert_eq!(tag.name(), "graphs");
+        assert_eq!(tag.depth(), 2);
+        assert_eq!(tag.parent(), Some(Tag::new("cs/algorithms")));
+        assert_eq!(Tag::new("cs").parent(), None);
+        assert_eq!(
+            tag.ancestry(),
+            vec![Tag::new("cs"), Tag::new("cs/algorithms"), tag.clone()]
+        );
+
+        assert!(tag.is_within(&Tag::new("cs")));
+        assert!(tag.is_within(&tag));
+        assert!(!tag.is_within(&Tag::new("cs/algo")));
+        assert!(!Tag::new("cs").is_within(&tag));
+    }
+
+    #[test]
+    fn rebased_tag() {
+        let tag = Tag::new("cs/algo/graphs");
+
+        assert_eq!(
+            tag.rebase(&Tag::new("cs/algo"), &Tag::new("algorithms")),
+            Some(Tag::new("algorithms/graphs"))
+        );
+        assert_eq!(tag.rebase(&Tag::new("math"), &Tag::new("maths")), None);
+    }
 }